@@ -0,0 +1,67 @@
+//! Rebuild encounters from packet captures without starting the app.
+//!
+//! usage: rebuild-encounters --db <encounters.db> [--resources <dir>] [--local-players <file>]
+//!        [--settings <settings.json>] <capture>...
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, bail};
+use app_lib::headless::{RebuildArgs, rebuild_from_captures};
+
+const USAGE: &str = "usage: rebuild-encounters --db <encounters.db> [--resources <dir>] \
+                     [--local-players <file>] [--settings <settings.json>] <capture>...";
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    flexi_logger::Logger::try_with_env_or_str("info")?.start()?;
+
+    let args = parse_args(std::env::args().skip(1))?;
+    let runtime = tokio::runtime::Handle::current();
+    tokio::task::spawn_blocking(move || rebuild_from_captures(args, runtime))
+        .await
+        .context("rebuild task panicked")?
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<RebuildArgs> {
+    let mut database = None;
+    let mut resource_dir = std::env::current_dir()?;
+    let mut local_players = None;
+    let mut settings = None;
+    let mut captures = Vec::new();
+
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .with_context(|| format!("missing value for {arg}"))
+        };
+        match arg.as_str() {
+            "--db" => database = Some(PathBuf::from(value()?)),
+            "--resources" => resource_dir = PathBuf::from(value()?),
+            "--local-players" => local_players = Some(PathBuf::from(value()?)),
+            "--settings" => {
+                let path = value()?;
+                let file = std::fs::File::open(&path)
+                    .with_context(|| format!("could not open settings {path}"))?;
+                settings = Some(serde_json::from_reader(file).context("invalid settings")?);
+            }
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                std::process::exit(0);
+            }
+            _ if arg.starts_with("--") => bail!("unknown option {arg}\n{USAGE}"),
+            _ => captures.push(PathBuf::from(&arg)),
+        }
+    }
+
+    let Some(database) = database else {
+        bail!("--db is required\n{USAGE}");
+    };
+
+    Ok(RebuildArgs {
+        captures,
+        database,
+        resource_dir,
+        local_players,
+        settings,
+    })
+}
@@ -14,6 +14,8 @@ pub const DATABASE_PATH: &str = "encounters.db";
 pub const MIGRATIONS_PATH: &str = "migrations.tar";
 pub const SETTINGS_PATH: &str = "settings.json";
 pub const LOCAL_PLAYERS_PATH: &str = "local_players.json";
+pub const CAPTURES_PATH: &str = "captures";
+pub const BACKUPS_PATH: &str = "backups";
 pub const STEAM_GAME_URL: &str = "steam://rungameid/1599340";
 pub const GAME_EXE_NAME: &str = "LOSTARK.exe";
 pub const NINEVEH_EXE_NAME: &str = "nineveh.exe";
@@ -25,6 +27,9 @@ pub const NINEVEH_COMPAT_EXE_NAME: &str = "LOSTARK.exe";
 pub const TASK_NAME: &str = "LOA_Logs_Auto_Start";
 pub const DEFAULT_BLUR: Color = (10, 10, 10, 50);
 pub const DEFAULT_PORT: u16 = 6040;
+pub const DEFAULT_LIVE_SERVER_PORT: u16 = 6041;
+pub const DEFAULT_BACKUP_INTERVAL_HOURS: u32 = 24;
+pub const DEFAULT_BACKUP_RETENTION: u32 = 7;
 pub const BETA_ENDPOINT: &str = "https://snow.xyz/loa-logs/beta.json";
 pub const WINDOW_POSITION: Position = Position::Logical(LogicalPosition { x: 100.0, y: 100.0 });
 pub const DEFAULT_MINI_METER_WINDOW_SIZE: Size = Size::Logical(LogicalSize {
@@ -16,6 +16,8 @@ pub struct AppContext {
     pub database_path: PathBuf,
     pub migrations_path: PathBuf,
     pub local_player_path: PathBuf,
+    pub captures_path: PathBuf,
+    pub backups_path: PathBuf,
     pub region: RwLock<Option<String>>,
 }
 
@@ -39,6 +41,8 @@ impl AppContext {
         let database_path = assets_path.join(DATABASE_PATH);
         let migrations_path = assets_path.join(MIGRATIONS_PATH);
         let local_player_path = assets_path.join(LOCAL_PLAYERS_PATH);
+        let captures_path = assets_path.join(CAPTURES_PATH);
+        let backups_path = assets_path.join(BACKUPS_PATH);
         Ok(Self {
             version,
             app_path,
@@ -47,6 +51,8 @@ impl AppContext {
             database_path,
             migrations_path,
             local_player_path,
+            captures_path,
+            backups_path,
             region: RwLock::new(None),
         })
     }
@@ -26,6 +26,7 @@ pub static ESTHER_DATA: OnceLockWrapper<Vec<Esther>> = OnceLockWrapper::new();
 pub static NPC_DATA: OnceLockWrapper<HashMap<u32, Npc>> = OnceLockWrapper::new();
 pub static GEM_SKILL_MAP: OnceLockWrapper<HashMap<u32, Vec<u32>>> = OnceLockWrapper::new();
 pub static RAID_MAP: OnceLockWrapper<HashMap<String, String>> = OnceLockWrapper::new();
+pub static RAID_DEFINITIONS: OnceLockWrapper<RaidDefinitions> = OnceLockWrapper::new();
 pub static IP_RANGES: OnceLockWrapper<Vec<IpRangeEntry>> = OnceLockWrapper::new();
 pub static SUPPORT_MARKING_GROUP: OnceLockWrapper<HashSet<u32>> = OnceLockWrapper::new();
 pub static EXTERNAL_ABILITY_DATA: OnceLockWrapper<HashMap<u32, ExternalAbilityData>> =
@@ -88,6 +89,16 @@ impl<T> Deref for OnceLockWrapper<T> {
 
 pub struct AssetPreloader;
 
+/// [`AssetPreloader`] can only fill the asset cells once per process, tests share this load.
+#[cfg(test)]
+pub fn preload_test_assets() {
+    static PRELOAD: std::sync::Once = std::sync::Once::new();
+    PRELOAD.call_once(|| {
+        let current_dir = std::env::current_dir().unwrap();
+        AssetPreloader::new(&current_dir).unwrap();
+    });
+}
+
 fn load<T: DeserializeOwned>(path: &Path) -> Result<T> {
     let s = fs::read_to_string(path).with_context(|| anyhow!("Missing file at: {path:?}"))?;
     serde_json::from_str::<T>(&s).with_context(|| anyhow!("Error parsing JSON in {path:?}"))
@@ -275,6 +286,7 @@ impl AssetPreloader {
                 })
                 .collect()
         })?;
+        RAID_DEFINITIONS.set(load_meter_data(resource_dir, "raids.json")?)?;
         SUPPORT_AP_GROUP.set(HashSet::from([
             101204, // bard
             101105, // paladin
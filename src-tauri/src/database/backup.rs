@@ -0,0 +1,284 @@
+use std::ffi::c_int;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context, Result, bail};
+use chrono::Local;
+use log::*;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::backup::{Backup, Progress};
+use rusqlite::{Connection, MAIN_DB, OpenFlags};
+use serde::Serialize;
+
+use crate::constants::DB_VERSION;
+use crate::database::{Database, Migrator};
+
+const BACKUP_PREFIX: &str = "encounters-";
+const BACKUP_EXTENSION: &str = "db";
+const RESTORE_STAGING_FILE: &str = "restore-staging.db";
+/// copy in small steps so the live meter can keep writing while a backup runs
+const PAGES_PER_STEP: c_int = 1024;
+const STEP_PAUSE: Duration = Duration::from_millis(5);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupInfo {
+    pub path: String,
+    pub created: i64,
+    pub size: u64,
+}
+
+impl Database {
+    /// Copies the live database into `directory` with SQLite's online backup API and removes the
+    /// oldest backups beyond `retention`.
+    pub fn backup(&self, directory: &Path, retention: usize) -> Result<PathBuf> {
+        std::fs::create_dir_all(directory)?;
+        let path = directory.join(format!(
+            "{BACKUP_PREFIX}{}.{BACKUP_EXTENSION}",
+            Local::now().format("%Y-%m-%d-%H-%M-%S-%3f")
+        ));
+        if path.exists() {
+            bail!("backup {} already exists", path.display());
+        }
+
+        let source = self.0.get()?;
+        let mut destination = Connection::open(&path)
+            .with_context(|| format!("could not create backup {}", path.display()))?;
+        Backup::new(&source, &mut destination)?.run_to_completion(
+            PAGES_PER_STEP,
+            STEP_PAUSE,
+            None,
+        )?;
+        drop(destination);
+        info!("backed up database to {}", path.display());
+
+        prune_backups(directory, retention)?;
+
+        Ok(path)
+    }
+
+    /// Result of `PRAGMA integrity_check`, `ok` when the database is healthy.
+    pub fn integrity_check(&self) -> Result<String> {
+        let connection = self.0.get()?;
+        check_integrity(&connection)
+    }
+
+    /// Replaces the contents of the live database with `backup`.
+    ///
+    /// The backup must pass an integrity check and must not come from a newer schema than
+    /// [`DB_VERSION`]. It is migrated on a scratch copy first, and the current database is backed
+    /// up to `backups_directory` before being overwritten.
+    pub fn restore(
+        &self,
+        backup: &Path,
+        app_version: &str,
+        backups_directory: &Path,
+    ) -> Result<()> {
+        validate_backup(backup)?;
+
+        std::fs::create_dir_all(backups_directory)?;
+        let staging = backups_directory.join(RESTORE_STAGING_FILE);
+        std::fs::copy(backup, &staging)
+            .with_context(|| format!("could not copy backup {}", backup.display()))?;
+
+        let result = self.restore_staged(&staging, app_version, backups_directory);
+        let _ = std::fs::remove_file(&staging);
+        result?;
+
+        info!("restored database from {}", backup.display());
+
+        Ok(())
+    }
+
+    fn restore_staged(
+        &self,
+        staging: &Path,
+        app_version: &str,
+        backups_directory: &Path,
+    ) -> Result<()> {
+        let pool = r2d2::Pool::builder()
+            .max_size(1)
+            .build(SqliteConnectionManager::file(staging))?;
+        Migrator::new(pool, app_version).run()?;
+
+        // keep the state being replaced, without rotating older backups out
+        self.backup(backups_directory, usize::MAX)?;
+
+        let mut connection = self.0.get()?;
+        connection.restore(MAIN_DB, staging, None::<fn(Progress)>)?;
+
+        Ok(())
+    }
+}
+
+/// Backups in `directory`, newest first.
+pub fn list_backups(directory: &Path) -> Result<Vec<BackupInfo>> {
+    if !directory.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups = Vec::new();
+    for entry in std::fs::read_dir(directory)? {
+        let path = entry?.path();
+        if !is_backup(&path) {
+            continue;
+        }
+
+        let metadata = std::fs::metadata(&path)?;
+        let created = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .map(|duration| duration.as_millis() as i64)
+            .unwrap_or_default();
+        backups.push(BackupInfo {
+            path: path.to_string_lossy().into_owned(),
+            created,
+            size: metadata.len(),
+        });
+    }
+
+    // timestamps in the file name sort chronologically
+    backups.sort_by(|a, b| b.path.cmp(&a.path));
+
+    Ok(backups)
+}
+
+/// Takes a backup every `interval`, counted from the newest backup already on disk, for as long
+/// as the app runs.
+pub fn spawn_backup_schedule(
+    database: Database,
+    directory: PathBuf,
+    interval: Duration,
+    retention: usize,
+) {
+    tokio::task::spawn(async move {
+        loop {
+            let last_backup = list_backups(&directory)
+                .ok()
+                .and_then(|backups| backups.into_iter().next())
+                .map(|b| SystemTime::UNIX_EPOCH + Duration::from_millis(b.created as u64));
+            let wait = last_backup
+                .and_then(|time| (time + interval).duration_since(SystemTime::now()).ok())
+                .unwrap_or_default();
+            tokio::time::sleep(wait).await;
+
+            let database = database.clone();
+            let backup_directory = directory.clone();
+            let result =
+                tokio::task::spawn_blocking(move || database.backup(&backup_directory, retention))
+                    .await;
+
+            match result {
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => {
+                    warn!("scheduled database backup failed: {e:?}");
+                    tokio::time::sleep(interval).await;
+                }
+                Err(e) => {
+                    warn!("scheduled database backup task failed: {e}");
+                    tokio::time::sleep(interval).await;
+                }
+            }
+        }
+    });
+}
+
+fn validate_backup(path: &Path) -> Result<()> {
+    let connection = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .with_context(|| format!("could not open backup {}", path.display()))?;
+
+    let integrity = check_integrity(&connection)?;
+    if integrity != "ok" {
+        bail!("backup failed integrity check: {integrity}");
+    }
+
+    let has_encounters = connection
+        .prepare("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'encounter'")?
+        .exists([])?;
+    if !has_encounters {
+        bail!("{} is not an encounters database", path.display());
+    }
+
+    let version: Option<i32> =
+        connection.query_row("SELECT MAX(version) FROM encounter", [], |row| row.get(0))?;
+    if let Some(version) = version
+        && version > DB_VERSION
+    {
+        bail!("backup uses database version {version}, newer than supported {DB_VERSION}");
+    }
+
+    Ok(())
+}
+
+fn check_integrity(connection: &Connection) -> Result<String> {
+    let messages = connection
+        .prepare("PRAGMA integrity_check")?
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(messages.join("\n"))
+}
+
+fn prune_backups(directory: &Path, retention: usize) -> Result<()> {
+    for backup in list_backups(directory)?.into_iter().skip(retention) {
+        if let Err(e) = std::fs::remove_file(&backup.path) {
+            warn!("could not remove old backup {}: {e}", backup.path);
+        }
+    }
+
+    Ok(())
+}
+
+fn is_backup(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == BACKUP_EXTENSION)
+        && path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with(BACKUP_PREFIX))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backs_up_restores_and_rotates() {
+        let directory = std::env::temp_dir().join(format!("backup-{}", uuid::Uuid::new_v4()));
+        let backups = directory.join("backups");
+        std::fs::create_dir_all(&directory).unwrap();
+        let database = Database::new(directory.join("encounters.db"), "1.14.0").unwrap();
+
+        let insert = |id: i32| {
+            database
+                .get_connection()
+                .execute(
+                    "INSERT INTO encounter (id, version) VALUES (?, ?)",
+                    rusqlite::params![id, DB_VERSION],
+                )
+                .unwrap();
+        };
+        let count = || -> i32 {
+            database
+                .get_connection()
+                .query_row("SELECT COUNT(*) FROM encounter", [], |row| row.get(0))
+                .unwrap()
+        };
+
+        insert(1);
+        let backup = database.backup(&backups, 1).unwrap();
+        assert_eq!(database.integrity_check().unwrap(), "ok");
+
+        insert(2);
+        assert_eq!(count(), 2);
+        database.restore(&backup, "1.14.0", &backups).unwrap();
+        assert_eq!(count(), 1);
+
+        // the pre-restore backup was kept next to the restored one
+        assert_eq!(list_backups(&backups).unwrap().len(), 2);
+        prune_backups(&backups, 1).unwrap();
+        assert_eq!(list_backups(&backups).unwrap().len(), 1);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,274 @@
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+
+use crate::database::Repository;
+use crate::models::*;
+
+/// encounters loaded per query, bounds memory use on large exports
+const ENCOUNTER_BATCH_SIZE: usize = 50;
+
+pub(crate) const PLAYER_COLUMNS: &[&str] = &[
+    "encounter_id",
+    "fight_start",
+    "boss",
+    "difficulty",
+    "cleared",
+    "duration",
+    "is_local_player",
+    "name",
+    "class_id",
+    "class",
+    "spec",
+    "gear_score",
+    "combat_power",
+    "damage_dealt",
+    "damage_taken",
+    "dps",
+    "rdps",
+    "ndps",
+    "udps",
+    "crit_damage",
+    "back_attack_damage",
+    "front_attack_damage",
+    "hyper_awakening_damage",
+    "rdps_damage_given",
+    "rdps_damage_received",
+    "deaths",
+    "support_ap",
+    "support_brand",
+    "support_identity",
+    "support_hyper",
+];
+
+const SKILL_COLUMNS: &[&str] = &[
+    "encounter_id",
+    "fight_start",
+    "boss",
+    "name",
+    "class",
+    "skill_id",
+    "skill_name",
+    "total_damage",
+    "dps",
+    "max_damage",
+    "casts",
+    "hits",
+    "crits",
+    "adjusted_crit",
+    "crit_damage",
+    "back_attacks",
+    "front_attacks",
+    "back_attack_damage",
+    "front_attack_damage",
+    "time_available",
+    "tripod_index",
+    "tripod_level",
+    "gem_cooldown",
+    "gem_tier",
+    "gem_damage",
+    "gem_tier_dmg",
+];
+
+/// Writes one row per encounter × player and one row per encounter × player × skill for every
+/// selected encounter, as `<timestamp>_players.csv` and `<timestamp>_skills.csv` in `directory`.
+pub fn export_csv(
+    repository: &Repository,
+    selection: EncounterSelection,
+    directory: &Path,
+) -> Result<CsvExportSummary> {
+    std::fs::create_dir_all(directory)?;
+    let prefix = Utc::now().format("%Y-%m-%d-%H-%M-%S");
+    let players_path = directory.join(format!("{prefix}_players.csv"));
+    let skills_path = directory.join(format!("{prefix}_skills.csv"));
+    let mut players = CsvWriter::create(&players_path, PLAYER_COLUMNS)?;
+    let mut skills = CsvWriter::create(&skills_path, SKILL_COLUMNS)?;
+
+    let ids = repository.get_encounter_ids(selection)?;
+    let mut summary = CsvExportSummary {
+        players_path: players_path.to_string_lossy().into_owned(),
+        skills_path: skills_path.to_string_lossy().into_owned(),
+        ..Default::default()
+    };
+
+    for batch in ids.chunks(ENCOUNTER_BATCH_SIZE) {
+        let encounters = repository
+            .get_encounters_with_support_buffs(batch)
+            .with_context(|| format!("could not load encounters {batch:?}"))?;
+        for (id, encounter, support_buffs) in encounters {
+            let mut entities: Vec<_> = encounter
+                .entities
+                .values()
+                .filter(|e| e.entity_type == EntityType::Player)
+                .collect();
+            entities.sort_by_key(|e| std::cmp::Reverse(e.damage_stats.damage_dealt));
+
+            for entity in entities {
+                let stats = &entity.damage_stats;
+                let support = support_buffs.get(&entity.name);
+                players.row(&[
+                    &id,
+                    &encounter.fight_start,
+                    &encounter.current_boss_name,
+                    &optional(encounter.difficulty.as_ref()),
+                    &encounter.cleared,
+                    &encounter.duration,
+                    &(entity.name == encounter.local_player),
+                    &entity.name,
+                    &entity.class_id,
+                    &entity.class,
+                    &optional(entity.spec.as_ref()),
+                    &entity.gear_score,
+                    &optional(entity.combat_power),
+                    &stats.damage_dealt,
+                    &stats.damage_taken,
+                    &stats.dps,
+                    &stats.rdps,
+                    &stats.ndps,
+                    &stats.unbuffed_dps,
+                    &stats.crit_damage,
+                    &stats.back_attack_damage,
+                    &stats.front_attack_damage,
+                    &stats.hyper_awakening_damage,
+                    &stats.rdps_damage_given,
+                    &stats.rdps_damage_received,
+                    &stats.deaths,
+                    &optional(support.map(|s| s.buff)),
+                    &optional(support.map(|s| s.brand)),
+                    &optional(support.map(|s| s.identity)),
+                    &optional(support.map(|s| s.hyper)),
+                ])?;
+                summary.player_rows += 1;
+
+                let mut entity_skills: Vec<_> = entity.skills.values().collect();
+                entity_skills.sort_by_key(|s| std::cmp::Reverse(s.total_damage));
+                for skill in entity_skills {
+                    skills.row(&[
+                        &id,
+                        &encounter.fight_start,
+                        &encounter.current_boss_name,
+                        &entity.name,
+                        &entity.class,
+                        &skill.id,
+                        &skill.name,
+                        &skill.total_damage,
+                        &skill.dps,
+                        &skill.max_damage,
+                        &skill.casts,
+                        &skill.hits,
+                        &skill.crits,
+                        &optional(skill.adjusted_crit),
+                        &skill.crit_damage,
+                        &skill.back_attacks,
+                        &skill.front_attacks,
+                        &skill.back_attack_damage,
+                        &skill.front_attack_damage,
+                        &optional(skill.time_available),
+                        &optional(
+                            skill
+                                .tripod_index
+                                .as_ref()
+                                .map(|t| format!("{}/{}/{}", t.first, t.second, t.third)),
+                        ),
+                        &optional(
+                            skill
+                                .tripod_level
+                                .as_ref()
+                                .map(|t| format!("{}/{}/{}", t.first, t.second, t.third)),
+                        ),
+                        &optional(skill.gem_cooldown),
+                        &optional(skill.gem_tier),
+                        &optional(skill.gem_damage),
+                        &optional(skill.gem_tier_dmg),
+                    ])?;
+                    summary.skill_rows += 1;
+                }
+            }
+
+            summary.encounters += 1;
+        }
+    }
+
+    players.finish()?;
+    skills.finish()?;
+
+    Ok(summary)
+}
+
+/// empty cell for `None`
+fn optional<T: Display>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+struct CsvWriter {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    line: String,
+}
+
+impl CsvWriter {
+    fn create(path: &Path, columns: &[&str]) -> Result<Self> {
+        let file =
+            File::create(path).with_context(|| format!("could not create {}", path.display()))?;
+        let mut writer = Self {
+            path: path.to_path_buf(),
+            writer: BufWriter::new(file),
+            line: String::new(),
+        };
+        let columns: Vec<&dyn Display> = columns.iter().map(|c| c as &dyn Display).collect();
+        writer.row(&columns)?;
+
+        Ok(writer)
+    }
+
+    fn row(&mut self, values: &[&dyn Display]) -> Result<()> {
+        self.line.clear();
+        for (index, value) in values.iter().enumerate() {
+            if index > 0 {
+                self.line.push(',');
+            }
+            push_field(&mut self.line, &value.to_string());
+        }
+        self.line.push('\n');
+        self.writer.write_all(self.line.as_bytes())?;
+
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<()> {
+        self.writer
+            .flush()
+            .with_context(|| format!("could not write {}", self.path.display()))
+    }
+}
+
+/// RFC 4180 quoting: fields containing separators, quotes or line breaks are wrapped in quotes
+fn push_field(line: &mut String, field: &str) {
+    if field.contains([',', '"', '\n', '\r']) {
+        line.push('"');
+        line.push_str(&field.replace('"', "\"\""));
+        line.push('"');
+    } else {
+        line.push_str(field);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::push_field;
+
+    #[test]
+    fn quotes_fields_with_separators() {
+        let mut line = String::new();
+        for field in ["plain", "a,b", "say \"hi\""] {
+            push_field(&mut line, field);
+            line.push('|');
+        }
+
+        assert_eq!(line, "plain|\"a,b\"|\"say \"\"hi\"\"\"|");
+    }
+}
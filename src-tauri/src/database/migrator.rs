@@ -3,7 +3,7 @@ use log::*;
 use r2d2_sqlite::SqliteConnectionManager;
 use rusqlite::Transaction;
 
-use crate::constants::DB_VERSION;
+use crate::{constants::DB_VERSION, database::repository::backfill_phase_splits};
 
 pub struct Migrator<'a> {
     pool: r2d2::Pool<SqliteConnectionManager>,
@@ -54,7 +54,18 @@ impl<'a> Migrator<'a> {
 
         migration_statistics_indexes(&tx)?;
 
+        migration_inspect_snapshots(&tx)?;
+
+        migration_battle_items(&tx)?;
+
+        migration_encounter_events(&tx)?;
+
+        migration_encounter_splits(&tx)?;
+
+        migration_character_identity(&tx)?;
+
         stmt.finalize()?;
+        backfill_phase_splits(&tx)?;
         info!("finished setting up database");
 
         tx.commit()?;
@@ -369,6 +380,82 @@ pub fn migration_statistics_indexes(tx: &Transaction) -> Result<(), rusqlite::Er
     Ok(())
 }
 
+pub fn migration_inspect_snapshots(tx: &Transaction) -> Result<(), rusqlite::Error> {
+    let mut stmt = tx.prepare("SELECT 1 FROM pragma_table_info(?) WHERE name=?")?;
+    if !stmt.exists(["entity", "inspect_snapshot"])? {
+        info!("adding inspect snapshots");
+        tx.execute_batch(
+            "CREATE TABLE IF NOT EXISTS inspect_snapshot (
+                hash TEXT PRIMARY KEY,
+                data BLOB NOT NULL
+             );
+             ALTER TABLE entity ADD COLUMN inspect_snapshot TEXT DEFAULT NULL;",
+        )?;
+    }
+    stmt.finalize()
+}
+
+pub fn migration_battle_items(tx: &Transaction) -> Result<(), rusqlite::Error> {
+    let mut stmt = tx.prepare("SELECT 1 FROM pragma_table_info(?) WHERE name=?")?;
+    if !stmt.exists(["entity", "battle_items"])? {
+        info!("adding battle items column");
+        tx.execute(
+            "ALTER TABLE entity ADD COLUMN battle_items TEXT DEFAULT NULL",
+            [],
+        )?;
+    }
+    stmt.finalize()
+}
+
+pub fn migration_encounter_events(tx: &Transaction) -> Result<(), rusqlite::Error> {
+    let mut stmt = tx.prepare("SELECT 1 FROM sqlite_master WHERE type=? AND name=?")?;
+    if !stmt.exists(["table", "encounter_event"])? {
+        info!("adding encounter event table");
+        tx.execute_batch(
+            "CREATE TABLE encounter_event (
+                encounter_id INTEGER NOT NULL,
+                timestamp INTEGER NOT NULL,
+                kind TEXT NOT NULL,
+                entity TEXT,
+                value INTEGER,
+                FOREIGN KEY (encounter_id) REFERENCES encounter (id) ON DELETE CASCADE
+             );
+             CREATE INDEX encounter_event_encounter_id_index
+             ON encounter_event (encounter_id, kind);",
+        )?;
+    }
+    stmt.finalize()
+}
+
+pub fn migration_encounter_splits(tx: &Transaction) -> Result<(), rusqlite::Error> {
+    let mut stmt = tx.prepare("SELECT 1 FROM sqlite_master WHERE type=? AND name=?")?;
+    if !stmt.exists(["table", "encounter_split"])? {
+        info!("adding encounter split table");
+        tx.execute_batch(
+            "CREATE TABLE encounter_split (
+                encounter_id INTEGER NOT NULL,
+                phase INTEGER NOT NULL,
+                name TEXT NOT NULL,
+                timestamp INTEGER NOT NULL,
+                PRIMARY KEY (encounter_id, phase),
+                FOREIGN KEY (encounter_id) REFERENCES encounter (id) ON DELETE CASCADE
+             );",
+        )?;
+    }
+    stmt.finalize()?;
+
+    let mut stmt = tx.prepare("SELECT 1 FROM pragma_table_info(?) WHERE name=?")?;
+    if !stmt.exists(["encounter_preview", "splits_computed"])? {
+        info!("adding splits computed column");
+        tx.execute_batch(
+            "ALTER TABLE encounter_preview ADD COLUMN splits_computed BOOLEAN NOT NULL DEFAULT 0;
+             UPDATE encounter_preview SET splits_computed = 1
+             WHERE id IN (SELECT encounter_id FROM encounter_split);",
+        )?;
+    }
+    stmt.finalize()
+}
+
 pub fn migration_boss_hp(tx: &Transaction) -> Result<(), rusqlite::Error> {
     let mut stmt = tx.prepare("SELECT 1 FROM pragma_table_info(?) WHERE name=?")?;
     if !stmt.exists(["entity", "hp_bars"])? {
@@ -381,3 +468,30 @@ pub fn migration_boss_hp(tx: &Transaction) -> Result<(), rusqlite::Error> {
 
     stmt.finalize()
 }
+
+pub fn migration_character_identity(tx: &Transaction) -> Result<(), rusqlite::Error> {
+    let mut stmt = tx.prepare("SELECT 1 FROM sqlite_master WHERE type=? AND name=?")?;
+    if !stmt.exists(["table", "character_identity"])? {
+        info!("adding character identity table");
+        tx.execute_batch(
+            "CREATE TABLE character_identity (
+                region TEXT NOT NULL DEFAULT '',
+                character_id INTEGER NOT NULL,
+                name TEXT NOT NULL COLLATE NOCASE,
+                first_seen INTEGER NOT NULL,
+                last_seen INTEGER NOT NULL,
+                PRIMARY KEY (region, character_id, name)
+             );
+             CREATE INDEX character_identity_name_index ON character_identity(name);
+             INSERT INTO character_identity (region, character_id, name, first_seen, last_seen)
+             SELECT COALESCE(json_extract(enc.misc, '$.region'), ''), p.character_id, p.name,
+                MIN(e.fight_start), MAX(e.fight_start)
+             FROM entity p
+             JOIN encounter_preview e ON e.id = p.encounter_id
+             JOIN encounter enc ON enc.id = e.id
+             WHERE p.entity_type = 'PLAYER' AND p.character_id > 0
+             GROUP BY 1, p.character_id, p.name COLLATE NOCASE;",
+        )?;
+    }
+    stmt.finalize()
+}
@@ -1,9 +1,16 @@
+pub mod backup;
+pub mod csv_export;
 pub mod migrator;
 pub mod models;
+pub mod portable;
 mod queries;
 pub mod repository;
+pub mod runs;
+pub mod splits;
 mod sql_types;
+pub mod synergy;
 pub mod utils;
+pub mod weekly;
 
 use anyhow::Result;
 use r2d2::{Pool, PooledConnection};
@@ -13,6 +20,7 @@ use std::{fs, path::PathBuf};
 pub use migrator::*;
 pub use repository::Repository;
 
+#[derive(Clone)]
 pub struct Database(r2d2::Pool<SqliteConnectionManager>, PathBuf);
 
 impl Database {
@@ -1,4 +1,5 @@
 use hashbrown::HashMap;
+use serde_json::Value;
 use std::collections::BTreeMap;
 
 use crate::models::*;
@@ -16,6 +17,14 @@ pub struct GetEncounterPreviewArgs {
     pub filter: SearchFilter,
 }
 
+/// Boss hp log of the fastest stored clear, the live pace reference.
+#[derive(Debug, Clone)]
+pub struct PaceReference {
+    pub encounter_id: i32,
+    pub duration: i64,
+    pub hp_log: Vec<BossHpLog>,
+}
+
 #[derive(Debug, Clone)]
 pub struct InsertEncounterArgs {
     pub encounter: Encounter,
@@ -37,4 +46,7 @@ pub struct InsertEncounterArgs {
     pub intermission_start: Option<i64>,
     pub intermission_end: Option<i64>,
     pub contribution_splits: Vec<ContributionSplit>,
+    /// serialized inspect snapshots of the players, keyed by name
+    pub inspect_snapshots: HashMap<String, Value>,
+    pub timeline: Vec<TimelineEvent>,
 }
@@ -0,0 +1,143 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::database::models::InsertEncounterArgs;
+use crate::models::*;
+
+/// bump whenever the layout changes in a way older readers can't handle
+///
+/// version notes:
+/// - 1: initial layout. Files written before `playerInfo` was dropped still carry it, it is
+///   ignored on import since the exported entities already hold the stored inspect results
+///   and `inspectSnapshots` the raw in-game inspects.
+pub const ENCOUNTER_EXPORT_VERSION: u32 = 1;
+pub const ENCOUNTER_EXPORT_EXTENSION: &str = "loalog";
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// A single stored encounter in a self-contained file that can be moved between machines.
+///
+/// `encounter` is the fully loaded encounter, so it carries the `EncounterMisc` (party info,
+/// contribution splits, ntp fight start, ...), the boss hp log and every entity with its skills
+/// and cast logs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncounterExport {
+    pub version: u32,
+    pub app_version: String,
+    pub exported_at: i64,
+    pub encounter: Encounter,
+    /// in-game inspect snapshots of the players, keyed by name
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub inspect_snapshots: HashMap<String, serde_json::Value>,
+    /// raid events ordered by time
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub timeline: Vec<TimelineEvent>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedEncounter {
+    pub id: i64,
+    /// the encounter was already in the database, nothing was inserted
+    pub duplicate: bool,
+}
+
+impl EncounterExport {
+    /// Writes the export as gzip compressed json.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let file = File::create(path)
+            .with_context(|| format!("could not create export file {}", path.display()))?;
+        let mut writer = GzEncoder::new(BufWriter::new(file), Compression::default());
+        serde_json::to_writer(&mut writer, self)?;
+        writer.finish()?.flush()?;
+
+        Ok(())
+    }
+
+    /// Reads an export written by [`EncounterExport::write`], plain json is accepted as well.
+    pub fn read(path: &Path) -> Result<Self> {
+        let file = File::open(path)
+            .with_context(|| format!("could not open export file {}", path.display()))?;
+        let mut reader = BufReader::new(file);
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+
+        let export: Self = if bytes.starts_with(&GZIP_MAGIC) {
+            serde_json::from_reader(GzDecoder::new(bytes.as_slice()))
+        } else {
+            serde_json::from_slice(&bytes)
+        }
+        .with_context(|| format!("{} is not an encounter export", path.display()))?;
+
+        if export.version > ENCOUNTER_EXPORT_VERSION {
+            bail!(
+                "export version {} is newer than supported version {ENCOUNTER_EXPORT_VERSION}",
+                export.version
+            );
+        }
+
+        Ok(export)
+    }
+
+    /// Rebuilds the insert arguments the encounter was originally saved with, keeping its
+    /// original timestamps.
+    pub fn into_insert_args(self) -> InsertEncounterArgs {
+        let EncounterExport {
+            app_version,
+            mut encounter,
+            inspect_snapshots,
+            timeline,
+            ..
+        } = self;
+
+        let misc = encounter
+            .encounter_damage_stats
+            .misc
+            .clone()
+            .unwrap_or_default();
+
+        let mut parties: Vec<_> = misc.party_info.unwrap_or_default().into_iter().collect();
+        parties.sort_by_key(|(index, _)| *index);
+        let party_info = parties.into_iter().map(|(_, party)| party).collect();
+
+        // buffed damage is not persisted, restore it so the recomputed udps matches the original
+        for entity in encounter.entities.values_mut() {
+            entity.damage_stats.buffed_damage =
+                entity.damage_stats.damage_dealt - entity.damage_stats.unbuffed_damage;
+        }
+
+        InsertEncounterArgs {
+            boss_hp_log: encounter.encounter_damage_stats.boss_hp_log.clone(),
+            raid_clear: misc.raid_clear.unwrap_or(encounter.cleared),
+            party_info,
+            raid_difficulty: encounter.difficulty.clone().unwrap_or_default(),
+            region: misc.region.or_else(|| encounter.region.clone()),
+            player_info: None,
+            meter_version: misc.version.unwrap_or(app_version),
+            ntp_fight_start: misc.ntp_fight_start.unwrap_or_default(),
+            // missing on encounters saved before it was stored, keep their rdps
+            rdps_valid: misc.rdps_valid.unwrap_or(true),
+            rdps_message: misc.rdps_message,
+            manual: misc.manual_save.unwrap_or_default(),
+            intermission_start: misc.intermission_start,
+            intermission_end: misc.intermission_end,
+            contribution_splits: misc.contribution_splits.unwrap_or_default(),
+            inspect_snapshots,
+            timeline,
+            encounter,
+            damage_log: HashMap::new(),
+            cast_log: HashMap::new(),
+            skill_cast_log: HashMap::new(),
+            skill_cooldowns: HashMap::new(),
+        }
+    }
+}
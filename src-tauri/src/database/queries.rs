@@ -20,13 +20,45 @@ SELECT
     total_shielding,
     total_effective_shielding,
     applied_shield_buffs,
-    boss_hp_log
+    boss_hp_log,
+    stagger_log
 FROM encounter
 JOIN encounter_preview
     USING (id)
 WHERE id = ?
 ";
 
+pub const SELECT_FROM_ENCOUNTER_JOIN_PREVIEW_BY_IDS: &str = r"
+SELECT
+    id,
+    last_combat_packet,
+    fight_start,
+    local_player,
+    current_boss,
+    duration,
+    total_damage_dealt,
+    top_damage_dealt,
+    total_damage_taken,
+    top_damage_taken,
+    dps,
+    buffs,
+    debuffs,
+    misc,
+    difficulty,
+    favorite,
+    cleared,
+    boss_only_damage,
+    total_shielding,
+    total_effective_shielding,
+    applied_shield_buffs,
+    boss_hp_log,
+    stagger_log
+FROM encounter
+JOIN encounter_preview
+    USING (id)
+WHERE id IN (SELECT value FROM json_each(?))
+";
+
 pub const SELECT_ENTITIES_BY_ENCOUNTER: &str = r"
 SELECT
     name,
@@ -49,11 +81,41 @@ SELECT
     ark_passive_active,
     ark_passive_data,
     loadout_hash,
-    combat_power
+    combat_power,
+    battle_items
 FROM entity
 WHERE encounter_id = ?;
 ";
 
+pub const SELECT_ENTITIES_BY_ENCOUNTERS: &str = r"
+SELECT
+    encounter_id,
+    name,
+    class_id,
+    class,
+    gear_score,
+    current_hp,
+    max_hp,
+    is_dead,
+    skills,
+    damage_stats,
+    skill_stats,
+    last_update,
+    entity_type,
+    npc_id,
+    hp_bars,
+    character_id,
+    engravings,
+    spec,
+    ark_passive_active,
+    ark_passive_data,
+    loadout_hash,
+    combat_power,
+    battle_items
+FROM entity
+WHERE encounter_id IN (SELECT value FROM json_each(?));
+";
+
 pub const SELECT_LOCAL_PLAYERS: &str = r"
 SELECT
     e.local_player,
@@ -77,6 +139,41 @@ WHERE le.gear_score > 0
 GROUP BY e.local_player
 ORDER BY max_gs DESC";
 
+pub const SELECT_MATCHING_ENCOUNTER: &str = r"
+SELECT id
+FROM encounter_preview
+WHERE fight_start = ? AND current_boss = ? AND local_player = ?
+LIMIT 1";
+
+pub const ATTACH_MERGE_SOURCE: &str = "ATTACH DATABASE ? AS merge_source";
+pub const DETACH_MERGE_SOURCE: &str = "DETACH DATABASE merge_source";
+
+pub const SELECT_MERGE_SOURCE_PREVIEWS: &str = r"
+SELECT id, fight_start, current_boss, local_player, favorite
+FROM merge_source.encounter_preview
+ORDER BY id";
+
+pub const SELECT_TABLE_COLUMNS: &str = "SELECT name FROM pragma_table_info(?, ?)";
+
+pub const SET_ENCOUNTER_FAVORITE: &str = "UPDATE encounter_preview SET favorite = 1 WHERE id = ?";
+
+pub const INSERT_MERGED_SYNC_LOGS: &str = r"
+INSERT OR IGNORE INTO main.sync_logs
+(encounter_id, upstream_id, failed)
+SELECT ?, upstream_id, failed
+FROM merge_source.sync_logs
+WHERE encounter_id = ?";
+
+pub const SELECT_ENTITY_SUPPORT_BUFFS: &str = r"
+SELECT name, support_ap, support_brand, support_identity, support_hyper
+FROM entity
+WHERE encounter_id = ? AND support_ap IS NOT NULL";
+
+pub const SELECT_ENTITY_SUPPORT_BUFFS_BY_ENCOUNTERS: &str = r"
+SELECT encounter_id, name, support_ap, support_brand, support_identity, support_hyper
+FROM entity
+WHERE encounter_id IN (SELECT value FROM json_each(?)) AND support_ap IS NOT NULL";
+
 pub const INSERT_ENCOUNTER: &str = r"
 INSERT INTO encounter
 (
@@ -93,10 +190,11 @@ INSERT INTO encounter
     applied_shield_buffs,
     misc,
     version,
-    boss_hp_log
+    boss_hp_log,
+    stagger_log
 )
 VALUES
-(?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)";
+(?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)";
 
 pub const INSERT_ENTITY: &str = r"
 INSERT INTO entity (
@@ -132,10 +230,179 @@ INSERT INTO entity (
     rdps_damage_received_support,
     rdps_damage_given,
     rdps,
-    ndps
+    ndps,
+    inspect_snapshot,
+    battle_items
 )
 VALUES
-(?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28, ?29, ?30, ?31, ?32, ?33)";
+(?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28, ?29, ?30, ?31, ?32, ?33, ?34, ?35)";
+
+pub const INSERT_INSPECT_SNAPSHOT: &str =
+    "INSERT OR IGNORE INTO inspect_snapshot (hash, data) VALUES (?, ?)";
+
+pub const SELECT_ENTITY_INSPECT_SNAPSHOT: &str = r"
+SELECT inspect_snapshot.hash, inspect_snapshot.data
+FROM entity
+JOIN inspect_snapshot ON inspect_snapshot.hash = entity.inspect_snapshot
+WHERE entity.encounter_id = ? AND entity.name = ?";
+
+/// Region and character id of a player in an encounter, the key of its identity.
+pub const SELECT_ENTITY_IDENTITY: &str = r"
+SELECT COALESCE(json_extract(enc.misc, '$.region'), ''), entity.character_id
+FROM entity
+JOIN encounter enc ON enc.id = entity.encounter_id
+WHERE entity.encounter_id = ? AND entity.name = ?";
+
+pub const SELECT_CHARACTER_INSPECT_SNAPSHOT: &str = r"
+SELECT inspect_snapshot.hash, inspect_snapshot.data
+FROM entity
+JOIN encounter enc ON enc.id = entity.encounter_id
+JOIN inspect_snapshot ON inspect_snapshot.hash = entity.inspect_snapshot
+WHERE entity.encounter_id = ?
+  AND COALESCE(json_extract(enc.misc, '$.region'), '') = ?
+  AND entity.character_id = ?";
+
+pub const INSERT_MERGED_INSPECT_SNAPSHOTS: &str = r"
+INSERT OR IGNORE INTO main.inspect_snapshot (hash, data)
+SELECT hash, data
+FROM merge_source.inspect_snapshot
+WHERE hash IN (SELECT inspect_snapshot FROM main.entity)";
+
+pub const INSERT_ENCOUNTER_EVENT: &str = r"
+INSERT INTO encounter_event (encounter_id, timestamp, kind, entity, value)
+VALUES (?1, ?2, ?3, ?4, ?5)";
+
+pub const SELECT_ENCOUNTER_EVENTS: &str = r"
+SELECT timestamp, kind, entity, value
+FROM encounter_event
+WHERE encounter_id = ?";
+
+pub const INSERT_MERGED_ENCOUNTER_EVENTS: &str = r"
+INSERT INTO main.encounter_event (encounter_id, timestamp, kind, entity, value)
+SELECT ?, timestamp, kind, entity, value
+FROM merge_source.encounter_event
+WHERE encounter_id = ?";
+
+pub const INSERT_ENCOUNTER_SPLIT: &str = r"
+INSERT INTO encounter_split (encounter_id, phase, name, timestamp)
+VALUES (?1, ?2, ?3, ?4)";
+
+pub const SELECT_ENCOUNTER_SPLITS: &str = r"
+SELECT encounter_id, name, timestamp
+FROM encounter_split
+WHERE encounter_id = ?
+ORDER BY phase";
+
+pub const SELECT_CLEARED_ENCOUNTER_SPLITS: &str = r"
+SELECT s.encounter_id, s.name, s.timestamp
+FROM encounter_split s
+JOIN encounter_preview e ON e.id = s.encounter_id
+WHERE e.current_boss = ? AND e.difficulty = ? AND e.cleared = 1
+ORDER BY s.encounter_id, s.phase";
+
+pub const SELECT_FASTEST_CLEAR: &str = r"
+SELECT e.id, e.duration, enc.boss_hp_log
+FROM encounter_preview e
+JOIN encounter enc ON enc.id = e.id
+WHERE e.current_boss = ? AND e.difficulty = ? AND e.cleared = 1
+ORDER BY e.duration
+LIMIT 1";
+
+pub const SELECT_LOCAL_PLAYER_REGIONS: &str = r"
+SELECT e.local_player, json_extract(enc.misc, '$.region') AS region, MAX(e.fight_start)
+FROM encounter_preview e
+JOIN encounter enc ON enc.id = e.id
+WHERE e.local_player IS NOT NULL
+  AND json_extract(enc.misc, '$.region') IS NOT NULL
+GROUP BY e.local_player";
+
+pub const SELECT_WEEKLY_CLEARS: &str = r"
+SELECT
+    e.id,
+    e.fight_start,
+    e.current_boss,
+    e.difficulty,
+    e.local_player,
+    boss.max_hp AS boss_max_hp
+FROM encounter_preview e
+LEFT JOIN entity boss ON boss.encounter_id = e.id AND boss.name = e.current_boss
+WHERE e.cleared = 1
+  AND e.fight_start >= ?
+  AND e.difficulty IS NOT NULL AND e.difficulty != ''
+ORDER BY e.fight_start";
+
+pub const UPSERT_CHARACTER_IDENTITIES: &str = r"
+INSERT INTO character_identity (region, character_id, name, first_seen, last_seen)
+SELECT COALESCE(json_extract(enc.misc, '$.region'), ''), p.character_id, p.name,
+    e.fight_start, e.fight_start
+FROM entity p
+JOIN encounter_preview e ON e.id = p.encounter_id
+JOIN encounter enc ON enc.id = e.id
+WHERE p.encounter_id = ? AND p.entity_type = 'PLAYER' AND p.character_id > 0
+ON CONFLICT (region, character_id, name) DO UPDATE SET
+    first_seen = MIN(first_seen, excluded.first_seen),
+    last_seen = MAX(last_seen, excluded.last_seen)";
+
+pub const SELECT_CHARACTER_NAMES: &str = r"
+SELECT other.character_id, other.name, other.first_seen, other.last_seen
+FROM character_identity own
+JOIN character_identity other
+    ON other.region = own.region AND other.character_id = own.character_id
+WHERE own.name = ?
+ORDER BY other.last_seen DESC";
+
+/// Latest name of a character, in any region when the region is bound as null.
+pub const SELECT_CHARACTER_LATEST_NAME: &str = r"
+SELECT name
+FROM character_identity
+WHERE character_id = ?1 AND region = COALESCE(?2, region)
+ORDER BY last_seen DESC
+LIMIT 1";
+
+/// Latest name every local player was stored under, among the other local player names.
+pub const SELECT_LOCAL_PLAYER_LATEST_NAMES: &str = r"
+SELECT own.name, other.name, MAX(other.last_seen)
+FROM character_identity own
+JOIN character_identity other
+    ON other.region = own.region AND other.character_id = own.character_id
+WHERE own.name IN (SELECT local_player FROM encounter_preview)
+  AND other.name IN (SELECT local_player FROM encounter_preview)
+GROUP BY own.name";
+
+/// Matches `e.local_player` against every name of the characters that used a name,
+/// the name has to be bound twice.
+pub const LOCAL_PLAYER_IDENTITY_FILTER: &str = "e.local_player IN (
+    SELECT ?
+    UNION
+    SELECT other.name
+    FROM character_identity own
+    JOIN character_identity other
+        ON other.region = own.region AND other.character_id = own.character_id
+    WHERE own.name = ?
+)";
+
+pub const SELECT_ENCOUNTERS_WITHOUT_COMPUTED_SPLITS: &str = r"
+SELECT e.id, e.current_boss, e.cleared, enc.fight_start, enc.last_combat_packet,
+    enc.boss_hp_log, enc.misc, boss.hp_bars
+FROM encounter_preview e
+JOIN encounter enc ON enc.id = e.id
+LEFT JOIN entity boss ON boss.encounter_id = e.id AND boss.name = e.current_boss
+WHERE e.splits_computed = 0";
+
+pub const UPDATE_ENCOUNTER_SPLITS_COMPUTED: &str =
+    "UPDATE encounter_preview SET splits_computed = 1 WHERE id = ?";
+
+pub const INSERT_MERGED_ENCOUNTER_SPLITS: &str = r"
+INSERT INTO main.encounter_split (encounter_id, phase, name, timestamp)
+SELECT ?, phase, name, timestamp
+FROM merge_source.encounter_split
+WHERE encounter_id = ?";
+
+pub const DELETE_ORPHANED_INSPECT_SNAPSHOTS: &str = r"
+DELETE FROM inspect_snapshot
+WHERE hash NOT IN (
+    SELECT inspect_snapshot FROM entity WHERE inspect_snapshot IS NOT NULL
+)";
 
 pub const INSERT_ENCOUNTER_PREVIEW: &str = r"
 INSERT INTO encounter_preview
@@ -151,10 +418,11 @@ INSERT INTO encounter_preview
     cleared,
     boss_only_damage,
     my_rdps,
-    my_ndps
+    my_ndps,
+    splits_computed
     )
 VALUES
-(?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)";
+(?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, 1)";
 
 pub const DELETE_NOT_FAV_UNCLEARED_ENCOUNTERS: &str = r"
 DELETE
@@ -206,6 +474,11 @@ SELECT upstream_id
 FROM sync_logs
 WHERE encounter_id = ? AND failed = false;";
 
+pub const SELECT_SYNC_LOGS_BY_ENCOUNTERS: &str = r"
+SELECT encounter_id, upstream_id
+FROM sync_logs
+WHERE encounter_id IN (SELECT value FROM json_each(?)) AND failed = false;";
+
 pub const INSERT_SYNC_LOGS: &str = r"
 INSERT OR REPLACE INTO sync_logs
 (encounter_id, upstream_id, failed)
@@ -1,17 +1,21 @@
-use anyhow::{Ok, Result};
+use anyhow::{Context, Ok, Result};
 use chrono::Utc;
 use hashbrown::HashMap;
 use log::*;
 use r2d2_sqlite::SqliteConnectionManager;
-use rusqlite::{OptionalExtension, Transaction, params, params_from_iter};
+use rusqlite::{MAIN_DB, OpenFlags, OptionalExtension, Transaction, params, params_from_iter};
 use serde_json::json;
 use std::cmp::{Ordering, Reverse, max};
 use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
 
 use crate::{
     constants::DB_VERSION,
+    data::RAID_DEFINITIONS,
+    database::Migrator,
+    database::portable::{ENCOUNTER_EXPORT_VERSION, EncounterExport, ImportedEncounter},
     database::sql_types::{CompressedJson, JsonColumn},
-    database::{models::*, queries::*, utils::*},
+    database::{models::*, queries::*, runs::*, splits::*, synergy::*, utils::*, weekly::*},
     models::*,
     utils::*,
 };
@@ -25,6 +29,7 @@ impl Repository {
 
     pub fn optimize(&self) -> Result<()> {
         let connection = self.0.get()?;
+        connection.execute(DELETE_ORPHANED_INSPECT_SNAPSHOTS, [])?;
         connection.execute_batch(OPTIMIZE_ENCOUNTER_SEARCH_FTS)?;
 
         Ok(())
@@ -145,7 +150,9 @@ impl Repository {
         } = args;
 
         let connection = self.0.get()?;
-        let (mut params, query, count_query) = prepare_get_encounter_preview_query(search, filter);
+        let aliases = search_aliases(&connection, &search)?;
+        let (mut params, query, count_query) =
+            prepare_get_encounter_preview_query(search, filter, &aliases);
         let count_params = params.clone();
 
         let mut statement = connection.prepare_cached(&query)?;
@@ -191,7 +198,174 @@ impl Repository {
             .into_iter()
             .filter(|c| c.name.len() >= 2 && !c.name.chars().any(|ch| ch.is_ascii_digit()))
             .collect();
-        Ok(characters)
+
+        let latest_names = connection
+            .prepare_cached(SELECT_LOCAL_PLAYER_LATEST_NAMES)?
+            .query_map([], |row| {
+                std::result::Result::Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<Result<HashMap<_, _>, _>>()?;
+
+        // renamed characters are listed once, under their latest name
+        let mut merged: Vec<CharacterInfo> = Vec::with_capacity(characters.len());
+        for character in characters.iter() {
+            let latest = latest_names
+                .get(&character.name)
+                .filter(|name| characters.iter().any(|c| &c.name == *name))
+                .unwrap_or(&character.name)
+                .clone();
+
+            match merged.iter_mut().find(|c| c.name == latest) {
+                Some(existing) => {
+                    existing.max_gear_score = existing.max_gear_score.max(character.max_gear_score)
+                }
+                None => {
+                    let mut info = characters
+                        .iter()
+                        .find(|c| c.name == latest)
+                        .unwrap_or(character)
+                        .clone();
+                    info.max_gear_score = info.max_gear_score.max(character.max_gear_score);
+                    merged.push(info);
+                }
+            }
+        }
+
+        Ok(merged)
+    }
+
+    /// Every name used by the characters that were seen as `name`, latest first.
+    pub fn get_character_names(&self, name: &str) -> Result<Vec<CharacterName>> {
+        let connection = self.0.get()?;
+        character_names(&connection, name)
+    }
+
+    /// Non-local characters seen in our encounters, most shared pulls first.
+    pub fn get_teammates(&self, criteria: TeammateCriteria) -> Result<Vec<Teammate>> {
+        let connection = self.0.get()?;
+        let (params, query) = build_teammates_query(&criteria, None);
+        let rows = connection
+            .prepare_cached(&query)?
+            .query_map(params_from_iter(params), map_teammate_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut grouped: HashMap<u64, Vec<TeammateRow>> = HashMap::new();
+        for row in rows {
+            grouped.entry(row.character_id).or_default().push(row);
+        }
+
+        // sqlite only folds ascii case, names are matched here instead
+        let search = criteria.search.to_lowercase();
+        let mut teammates = grouped
+            .into_iter()
+            .filter(|(_, rows)| {
+                search.is_empty()
+                    || rows
+                        .iter()
+                        .any(|row| row.pull.name.to_lowercase().contains(&search))
+            })
+            .map(|(character_id, rows)| build_teammate(character_id, &rows))
+            .collect::<Vec<_>>();
+        teammates.sort_by_key(|teammate| Reverse((teammate.shared_pulls, teammate.last_seen)));
+
+        Ok(teammates)
+    }
+
+    /// Every shared pull with one non-local character.
+    pub fn get_teammate_details(&self, character_id: u64) -> Result<TeammateDetails> {
+        let connection = self.0.get()?;
+        let criteria = TeammateCriteria {
+            range: "all".to_string(),
+            ..Default::default()
+        };
+        let (params, query) = build_teammates_query(&criteria, Some(character_id));
+        let rows = connection
+            .prepare_cached(&query)?
+            .query_map(params_from_iter(params), map_teammate_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if rows.is_empty() {
+            anyhow::bail!("no shared encounters with character {character_id}");
+        }
+
+        Ok(TeammateDetails {
+            teammate: build_teammate(character_id, &rows),
+            pulls: rows.into_iter().rev().map(|row| row.pull).collect(),
+        })
+    }
+
+    /// Raid gates every local character cleared since the weekly reset of their region.
+    pub fn get_weekly_tracker(&self) -> Result<Vec<WeeklyCharacter>> {
+        let characters = self.get_local_characters()?;
+        let connection = self.0.get()?;
+        let now = Utc::now().timestamp_millis();
+
+        let regions = connection
+            .prepare_cached(SELECT_LOCAL_PLAYER_REGIONS)?
+            .query_map([], |row| {
+                std::result::Result::Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<Result<HashMap<_, _>, _>>()?;
+
+        let mut tracker = characters
+            .into_iter()
+            .map(|character| {
+                let region = regions.get(&character.name).cloned();
+                let reset = RAID_DEFINITIONS.weekly_reset(region.as_deref());
+                WeeklyCharacter {
+                    region,
+                    week_start: reset.last_reset(now),
+                    next_reset: reset.next_reset(now),
+                    character,
+                    raids: Vec::new(),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let Some(earliest_reset) = tracker.iter().map(|character| character.week_start).min()
+        else {
+            return Ok(tracker);
+        };
+
+        let clears = connection
+            .prepare_cached(SELECT_WEEKLY_CLEARS)?
+            .query_map([earliest_reset], |row| {
+                std::result::Result::Ok((
+                    WeeklyClear {
+                        id: row.get("id")?,
+                        fight_start: row.get("fight_start")?,
+                        boss_name: row.get("current_boss")?,
+                        difficulty: row.get("difficulty")?,
+                        local_player: row
+                            .get::<_, Option<String>>("local_player")?
+                            .unwrap_or_default(),
+                        ..Default::default()
+                    },
+                    row.get::<_, Option<i64>>("boss_max_hp")?
+                        .unwrap_or_default(),
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .filter_map(|(mut clear, max_hp)| {
+                let gate = boss_to_raid_map(&clear.boss_name, max_hp)?;
+                let (raid, gate_index) = RAID_DEFINITIONS.gate_position(&gate)?;
+                clear.gate_count = RAID_DEFINITIONS.gate_order[raid].len();
+                clear.raid = raid.to_string();
+                clear.gate = gate;
+                clear.gate_index = gate_index;
+                Some(clear)
+            })
+            .collect::<Vec<_>>();
+
+        for character in tracker.iter_mut() {
+            character.raids = build_weekly_raids(clears.iter().filter(|clear| {
+                clear.local_player == character.character.name
+                    && clear.fight_start >= character.week_start
+            }));
+        }
+
+        Ok(tracker)
     }
 
     pub fn get_character_statistics(
@@ -203,21 +377,7 @@ impl Repository {
 
         let mode = criteria.mode.clone();
         let damage_type = criteria.damage_type.clone();
-        let character_name = criteria.character.name.clone();
-        let boss_to_raid = criteria.boss_to_raid.clone();
-        let (params, query) = build_character_statistics_query(criteria);
-        let mut rows = connection
-            .prepare_cached(&query)?
-            .query_map(params_from_iter(params), map_character_statistics_row)?
-            .collect::<Result<Vec<_>, _>>()?;
-
-        if mode == "support" {
-            populate_support_contribution_denominators(&connection, &mut rows, &character_name);
-        }
-
-        for row in rows.iter_mut() {
-            row.raid_name = boss_to_raid.get(&row.boss_name).cloned();
-        }
+        let rows = character_statistics_rows(&connection, criteria)?;
 
         Ok(build_character_statistics(
             character,
@@ -227,6 +387,107 @@ impl Repository {
         ))
     }
 
+    /// Statistics of every character in `roster`, given as character id and the name
+    /// it was last stored under, along with the roster wide aggregates.
+    pub fn get_roster_statistics(
+        &self,
+        roster: Vec<(u64, String)>,
+        criteria: RosterStatisticsCriteria,
+    ) -> Result<RosterStatistics> {
+        let local_characters = self.get_local_characters()?;
+        let connection = self.0.get()?;
+
+        let regions = connection
+            .prepare_cached(SELECT_LOCAL_PLAYER_REGIONS)?
+            .query_map([], |row| {
+                std::result::Result::Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<Result<HashMap<_, _>, _>>()?;
+
+        let mut names: Vec<String> = Vec::with_capacity(roster.len());
+        for (character_id, name) in roster {
+            // the name in local_players.json is stale after a rename
+            let name = connection
+                .query_row(
+                    SELECT_CHARACTER_LATEST_NAME,
+                    params![character_id, regions.get(&name)],
+                    |row| row.get::<_, String>(0),
+                )
+                .optional()?
+                .unwrap_or(name);
+            if !name.is_empty() && !names.contains(&name) {
+                names.push(name);
+            }
+        }
+
+        let mut characters = Vec::with_capacity(names.len());
+        for name in names {
+            let character = local_characters
+                .iter()
+                .find(|character| character.name == name)
+                .cloned()
+                .unwrap_or(CharacterInfo {
+                    name,
+                    ..Default::default()
+                });
+            let reset =
+                RAID_DEFINITIONS.weekly_reset(regions.get(&character.name).map(String::as_str));
+            let rows = character_statistics_rows(
+                &connection,
+                CharacterStatisticsCriteria {
+                    character: character.clone(),
+                    range: criteria.range.clone(),
+                    mode: criteria.mode.clone(),
+                    damage_type: criteria.damage_type.clone(),
+                    boss_to_raid: criteria.boss_to_raid.clone(),
+                    start_time: criteria.start_time,
+                    end_time: criteria.end_time,
+                    difficulty: criteria.difficulty.clone(),
+                    min_duration: criteria.min_duration,
+                    ..Default::default()
+                },
+            )?;
+            characters.push((character, reset, rows));
+        }
+
+        Ok(build_roster_statistics(
+            characters,
+            criteria.mode.as_str(),
+            criteria.damage_type.as_str(),
+        ))
+    }
+
+    /// Gear score, combat power and loadout of a character over time, with the performance
+    /// on every boss before and after each loadout change.
+    pub fn get_loadout_progression(
+        &self,
+        criteria: LoadoutProgressionCriteria,
+    ) -> Result<LoadoutProgression> {
+        let connection = self.0.get()?;
+        let (params, query) = build_loadout_progression_query(criteria);
+        let rows = connection
+            .prepare_cached(&query)?
+            .query_map(params_from_iter(params), map_loadout_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(build_loadout_progression(rows))
+    }
+
+    /// rDPS every local dps character received from each support across stored encounters.
+    pub fn get_support_synergy(
+        &self,
+        criteria: SupportSynergyCriteria,
+    ) -> Result<Vec<SupportSynergy>> {
+        let connection = self.0.get()?;
+        let (params, query) = build_support_synergy_query(criteria);
+        let encounters = connection
+            .prepare_cached(&query)?
+            .query_map(params_from_iter(params), map_synergy_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(build_support_synergy(encounters))
+    }
+
     pub fn get_raid_progression_statistics(
         &self,
         criteria: RaidProgressionCriteria,
@@ -266,6 +527,36 @@ impl Repository {
         Ok(range)
     }
 
+    /// Encounters grouped into raid runs, consecutive gates of one raid cleared by the same party.
+    pub fn get_raid_runs(&self, criteria: RaidRunCriteria) -> Result<RaidRunStatistics> {
+        let connection = self.0.get()?;
+        let raid = criteria.raid.clone();
+        let (params, query) = build_raid_runs_query(criteria);
+        let rows = connection
+            .prepare_cached(&query)?
+            .query_map(params_from_iter(params), map_raid_run_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let encounters = rows
+            .into_iter()
+            .filter_map(|(mut encounter, max_hp)| {
+                let gate = boss_to_raid_map(&encounter.boss_name, max_hp)?;
+                let (raid_name, gate_index) = RAID_DEFINITIONS.gate_position(&gate)?;
+                if !raid.is_empty() && raid_name != raid {
+                    return None;
+                }
+
+                encounter.raid = raid_name.to_string();
+                encounter.raid_gates = RAID_DEFINITIONS.gate_order[raid_name].len();
+                encounter.gate = gate;
+                encounter.gate_index = gate_index;
+                Some(encounter)
+            })
+            .collect::<Vec<_>>();
+
+        Ok(build_raid_runs(encounters))
+    }
+
     pub fn delete_all_uncleared_encounters(&self, keep_favorites: bool) -> Result<()> {
         let connection = self.0.get()?;
 
@@ -323,6 +614,391 @@ impl Repository {
         Ok(encounter)
     }
 
+    pub fn export_encounter(&self, id: &str, app_version: &str) -> Result<EncounterExport> {
+        let encounter = self.get_encounter(id)?;
+        let mut inspect_snapshots = HashMap::new();
+        for name in encounter.entities.keys() {
+            if let Some((_, snapshot)) = self.get_inspect_snapshot(id, name)? {
+                inspect_snapshots.insert(name.clone(), snapshot);
+            }
+        }
+
+        let timeline = self.get_encounter_events(id, &[])?;
+
+        Ok(EncounterExport {
+            version: ENCOUNTER_EXPORT_VERSION,
+            app_version: app_version.to_string(),
+            exported_at: Utc::now().timestamp_millis(),
+            encounter,
+            inspect_snapshots,
+            timeline,
+        })
+    }
+
+    /// Timeline of encounter `id`, only the given kinds unless `kinds` is empty.
+    pub fn get_encounter_events(
+        &self,
+        id: &str,
+        kinds: &[TimelineEventKind],
+    ) -> Result<Vec<TimelineEvent>> {
+        let connection = self.0.get()?;
+        encounter_events(&connection, id, kinds)
+    }
+
+    /// Best split of every phase of `boss` across all clears on `difficulty`, compared against
+    /// pull `encounter_id` when given.
+    pub fn get_phase_splits(
+        &self,
+        boss: &str,
+        difficulty: &str,
+        encounter_id: Option<i32>,
+    ) -> Result<PhaseSplitStatistics> {
+        let connection = self.0.get()?;
+        let read_split = |row: &rusqlite::Row| {
+            std::result::Result::Ok((
+                row.get::<_, i32>("encounter_id")?,
+                PhaseSplit {
+                    name: row.get("name")?,
+                    end: row.get("timestamp")?,
+                },
+            ))
+        };
+
+        let mut clears: Vec<(i32, Vec<PhaseSplit>)> = Vec::new();
+        let mut statement = connection.prepare_cached(SELECT_CLEARED_ENCOUNTER_SPLITS)?;
+        for row in statement.query_map(params![boss, difficulty], read_split)? {
+            let (id, split) = row?;
+            match clears.last_mut() {
+                Some((last_id, splits)) if *last_id == id => splits.push(split),
+                _ => clears.push((id, vec![split])),
+            }
+        }
+
+        let phases = best_splits(&clears);
+        let best_clear = clears
+            .iter()
+            .filter_map(|(id, splits)| splits.last().map(|split| (split.end, *id)))
+            .min();
+        let pull = match encounter_id {
+            Some(id) => {
+                let splits = connection
+                    .prepare_cached(SELECT_ENCOUNTER_SPLITS)?
+                    .query_map(params![id], read_split)?
+                    .map(|row| row.map(|(_, split)| split))
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                Some(compare_splits(id, &splits, &phases))
+            }
+            None => None,
+        };
+
+        Ok(PhaseSplitStatistics {
+            boss_name: boss.to_string(),
+            difficulty: difficulty.to_string(),
+            clears: clears.len() as i32,
+            sum_of_best: (!phases.is_empty())
+                .then(|| phases.iter().map(|phase| phase.duration).sum()),
+            phases,
+            best_clear: best_clear.map(|(end, _)| end),
+            best_clear_id: best_clear.map(|(_, id)| id),
+            pull,
+        })
+    }
+
+    /// Boss hp log of the fastest clear of `boss` on `difficulty`.
+    pub fn get_fastest_clear(&self, boss: &str, difficulty: &str) -> Result<Option<PaceReference>> {
+        let connection = self.0.get()?;
+        let mut statement = connection.prepare_cached(SELECT_FASTEST_CLEAR)?;
+
+        let reference = statement
+            .query_row(params![boss, difficulty], |row| {
+                let CompressedJson(mut boss_hp_log): CompressedJson<
+                    HashMap<String, Vec<BossHpLog>>,
+                > = row.get("boss_hp_log")?;
+
+                std::result::Result::Ok(PaceReference {
+                    encounter_id: row.get("id")?,
+                    duration: row.get("duration")?,
+                    hp_log: boss_hp_log.remove(boss).unwrap_or_default(),
+                })
+            })
+            .optional()?;
+
+        Ok(reference.filter(|reference| !reference.hp_log.is_empty()))
+    }
+
+    /// Hash and contents of the inspect snapshot stored for `name` in encounter `id`.
+    pub fn get_inspect_snapshot(
+        &self,
+        id: &str,
+        name: &str,
+    ) -> Result<Option<(String, serde_json::Value)>> {
+        let connection = self.0.get()?;
+        let mut statement = connection.prepare_cached(SELECT_ENTITY_INSPECT_SNAPSHOT)?;
+
+        let snapshot = statement
+            .query_row(params![id, name], |row| {
+                let CompressedJson(snapshot) = row.get("data")?;
+                std::result::Result::Ok((row.get("hash")?, snapshot))
+            })
+            .optional()?;
+
+        Ok(snapshot)
+    }
+
+    /// Compares the inspect snapshots of a character in two encounters. `name` is the name in
+    /// `before_encounter`, the character is found in `after_encounter` by its identity so a
+    /// rename in between doesn't matter.
+    pub fn diff_inspect_snapshots(
+        &self,
+        name: &str,
+        before_encounter: i32,
+        after_encounter: i32,
+    ) -> Result<InspectSnapshotDiff> {
+        let connection = self.0.get()?;
+        let (region, character_id): (String, u64) = connection
+            .prepare_cached(SELECT_ENTITY_IDENTITY)?
+            .query_row(params![before_encounter, name], |row| {
+                std::result::Result::Ok((row.get(0)?, row.get(1)?))
+            })
+            .optional()?
+            .with_context(|| format!("{name} is not in encounter {before_encounter}"))?;
+
+        let get = |id: i32| -> Result<(String, serde_json::Value)> {
+            let map_snapshot = |row: &rusqlite::Row| {
+                let CompressedJson(snapshot): CompressedJson<serde_json::Value> =
+                    row.get("data")?;
+                std::result::Result::Ok((row.get::<_, String>("hash")?, snapshot))
+            };
+            let snapshot = if character_id > 0 {
+                connection
+                    .prepare_cached(SELECT_CHARACTER_INSPECT_SNAPSHOT)?
+                    .query_row(params![id, region, character_id], map_snapshot)
+            } else {
+                // players stored without a character id can only be matched by name
+                connection
+                    .prepare_cached(SELECT_ENTITY_INSPECT_SNAPSHOT)?
+                    .query_row(params![id, name], map_snapshot)
+            }
+            .optional()?;
+            snapshot.with_context(|| format!("no inspect snapshot of {name} in encounter {id}"))
+        };
+        let (before_hash, before) = get(before_encounter)?;
+        let (after_hash, after) = get(after_encounter)?;
+
+        Ok(InspectSnapshotDiff {
+            name: name.to_string(),
+            before_encounter,
+            after_encounter,
+            identical: before_hash == after_hash,
+            changes: diff_json(&before, &after),
+        })
+    }
+
+    /// Inserts an exported encounter unless an encounter with the same start time, boss and local
+    /// player is already stored.
+    pub fn import_encounter(&self, export: EncounterExport) -> Result<ImportedEncounter> {
+        let encounter = &export.encounter;
+        let existing: Option<i64> = {
+            let connection = self.0.get()?;
+            let mut statement = connection.prepare_cached(SELECT_MATCHING_ENCOUNTER)?;
+            statement
+                .query_row(
+                    params![
+                        encounter.fight_start,
+                        encounter.current_boss_name,
+                        encounter.local_player
+                    ],
+                    |row| row.get(0),
+                )
+                .optional()?
+        };
+
+        if let Some(id) = existing {
+            return Ok(ImportedEncounter {
+                id,
+                duplicate: true,
+            });
+        }
+
+        let favorite = encounter.favorite;
+        let id = self.insert_data(export.into_insert_args())?;
+        if favorite {
+            self.toggle_encounter_favorite(id as i32)?;
+        }
+
+        Ok(ImportedEncounter {
+            id,
+            duplicate: false,
+        })
+    }
+
+    /// Ids of the encounters matching a bulk export selection, newest first.
+    pub fn get_encounter_ids(&self, selection: EncounterSelection) -> Result<Vec<i32>> {
+        let connection = self.0.get()?;
+        let (params, query) = match selection {
+            EncounterSelection::Search { search, mut filter } => {
+                if filter.sort.is_empty() {
+                    filter.sort = "e.fight_start".to_string();
+                }
+                if filter.order.is_empty() {
+                    filter.order = "desc".to_string();
+                }
+                let aliases = search_aliases(&connection, &search)?;
+                let (mut params, query, _) =
+                    prepare_get_encounter_preview_query(search, filter, &aliases);
+                // no limit, no offset
+                params.push("-1".to_string());
+                params.push("0".to_string());
+                (params, query)
+            }
+            EncounterSelection::Character { criteria } => {
+                build_character_statistics_query(criteria)
+            }
+        };
+
+        let ids = connection
+            .prepare(&query)?
+            .query_map(params_from_iter(params), |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(ids)
+    }
+
+    /// Support buff summaries stored with the entities of an encounter, keyed by name.
+    pub fn get_support_buffs(&self, id: &str) -> Result<HashMap<String, SupportBuffs>> {
+        let connection = self.0.get()?;
+        let mut statement = connection.prepare_cached(SELECT_ENTITY_SUPPORT_BUFFS)?;
+        let buffs = statement
+            .query_map(params![id], |row| {
+                std::result::Result::Ok((
+                    row.get::<_, String>(0)?,
+                    SupportBuffs {
+                        buff: row.get::<_, Option<f64>>(1)?.unwrap_or_default(),
+                        brand: row.get::<_, Option<f64>>(2)?.unwrap_or_default(),
+                        identity: row.get::<_, Option<f64>>(3)?.unwrap_or_default(),
+                        hyper: row.get::<_, Option<f64>>(4)?.unwrap_or_default(),
+                    },
+                ))
+            })?
+            .collect::<Result<_, _>>()?;
+
+        Ok(buffs)
+    }
+
+    /// [`Repository::get_encounter`] and [`Repository::get_support_buffs`] for several encounters
+    /// at once, in the order of `ids`. Unknown ids are skipped.
+    pub fn get_encounters_with_support_buffs(
+        &self,
+        ids: &[i32],
+    ) -> Result<Vec<(i32, Encounter, HashMap<String, SupportBuffs>)>> {
+        let connection = self.0.get()?;
+        let ids_json = serde_json::to_string(ids)?;
+
+        let mut versions = HashMap::new();
+        let mut encounters: HashMap<i32, Encounter> = connection
+            .prepare_cached(SELECT_FROM_ENCOUNTER_JOIN_PREVIEW_BY_IDS)?
+            .query_map(params![ids_json], |row| {
+                let (encounter, version) = map_encounter(row)?;
+                std::result::Result::Ok((row.get::<_, i32>("id")?, encounter, version))
+            })?
+            .map(|row| {
+                row.map(|(id, encounter, version)| {
+                    versions.insert(id, version);
+                    (id, encounter)
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        let mut statement = connection.prepare_cached(SELECT_ENTITIES_BY_ENCOUNTERS)?;
+        let mut rows = statement.query(params![ids_json])?;
+        while let Some(row) = rows.next()? {
+            let id: i32 = row.get("encounter_id")?;
+            if let (Some(encounter), Some(version)) = (encounters.get_mut(&id), versions.get(&id)) {
+                let entity = map_entity(row, version)?;
+                encounter.entities.insert(entity.name.to_string(), entity);
+            }
+        }
+
+        let mut statement = connection.prepare_cached(SELECT_SYNC_LOGS_BY_ENCOUNTERS)?;
+        let mut rows = statement.query(params![ids_json])?;
+        while let Some(row) = rows.next()? {
+            if let Some(encounter) = encounters.get_mut(&row.get::<_, i32>(0)?)
+                && encounter.sync.is_none()
+            {
+                encounter.sync = row.get(1)?;
+            }
+        }
+
+        let mut support_buffs: HashMap<i32, HashMap<String, SupportBuffs>> = HashMap::new();
+        let mut statement = connection.prepare_cached(SELECT_ENTITY_SUPPORT_BUFFS_BY_ENCOUNTERS)?;
+        let mut rows = statement.query(params![ids_json])?;
+        while let Some(row) = rows.next()? {
+            support_buffs.entry(row.get(0)?).or_default().insert(
+                row.get(1)?,
+                SupportBuffs {
+                    buff: row.get::<_, Option<f64>>(2)?.unwrap_or_default(),
+                    brand: row.get::<_, Option<f64>>(3)?.unwrap_or_default(),
+                    identity: row.get::<_, Option<f64>>(4)?.unwrap_or_default(),
+                    hyper: row.get::<_, Option<f64>>(5)?.unwrap_or_default(),
+                },
+            );
+        }
+
+        Ok(ids
+            .iter()
+            .filter_map(|id| {
+                let mut encounter = encounters.remove(id)?;
+                normalize_encounter_damage_totals(&mut encounter);
+                Some((*id, encounter, support_buffs.remove(id).unwrap_or_default()))
+            })
+            .collect())
+    }
+
+    /// Copies the encounters, entities, previews and sync logs of another `encounters.db` into
+    /// this database. The other file is left untouched, a temporary copy of it is migrated to the
+    /// current schema and merged instead. Encounters that are already stored (same start time, boss
+    /// and local player) are skipped, but a favorite on the skipped copy is carried over.
+    pub fn merge_database(&self, path: &Path, app_version: &str) -> Result<DatabaseMergeSummary> {
+        if !path.is_file() {
+            anyhow::bail!("{} does not exist", path.display());
+        }
+
+        let copy = std::env::temp_dir().join(format!("loa-logs-merge-{}.db", uuid::Uuid::new_v4()));
+        let summary = self.merge_database_copy(path, &copy, app_version);
+        let _ = std::fs::remove_file(&copy);
+
+        summary
+    }
+
+    fn merge_database_copy(
+        &self,
+        path: &Path,
+        copy: &Path,
+        app_version: &str,
+    ) -> Result<DatabaseMergeSummary> {
+        let source = rusqlite::Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("could not open {}", path.display()))?;
+        source
+            .backup(MAIN_DB, copy, None)
+            .with_context(|| format!("could not copy {}", path.display()))?;
+        drop(source);
+
+        let pool = r2d2::Pool::builder()
+            .max_size(1)
+            .build(SqliteConnectionManager::file(copy))?;
+        Migrator::new(pool, app_version).run()?;
+
+        let mut connection = self.0.get()?;
+        connection.execute(
+            ATTACH_MERGE_SOURCE,
+            params![copy.to_string_lossy().into_owned()],
+        )?;
+        let summary = merge_attached_database(&mut connection);
+        connection.execute(DETACH_MERGE_SOURCE, [])?;
+
+        summary
+    }
+
     pub fn get_last_encounter_id(&self) -> Result<Option<i32>> {
         let connection = self.0.get()?;
         let mut statement = connection.prepare_cached(GET_TOP_ENCOUNTER_ID)?;
@@ -384,7 +1060,10 @@ impl Repository {
         calculate_entities(&mut args)?;
         let buffs = compute_support_buffs(&args.encounter, &args.party_info);
         self.insert_entities(&transaction, &args, buffs, last_insert_id)?;
+        self.insert_events(&transaction, &args.timeline, last_insert_id)?;
+        self.insert_splits(&transaction, &args, last_insert_id)?;
         self.insert_encounter_preview(&transaction, args, last_insert_id)?;
+        transaction.execute(UPSERT_CHARACTER_IDENTITIES, params![last_insert_id])?;
 
         transaction.commit()?;
 
@@ -472,6 +1151,7 @@ impl Repository {
             json!(misc),
             DB_VERSION,
             compress_json(boss_hp_log)?,
+            compress_json(&stats.stagger)?,
         ];
 
         let mut statement = transaction.prepare_cached(INSERT_ENCOUNTER)?;
@@ -480,6 +1160,49 @@ impl Repository {
         Ok(transaction.last_insert_rowid())
     }
 
+    fn insert_events(
+        &self,
+        transaction: &Transaction,
+        timeline: &[TimelineEvent],
+        encounter_id: i64,
+    ) -> Result<()> {
+        let mut statement = transaction.prepare_cached(INSERT_ENCOUNTER_EVENT)?;
+        for event in timeline {
+            statement.execute(params![
+                encounter_id,
+                event.time,
+                event.kind.as_ref(),
+                event.entity,
+                event.value
+            ])?;
+        }
+
+        Ok(())
+    }
+
+    fn insert_splits(
+        &self,
+        transaction: &Transaction,
+        args: &InsertEncounterArgs,
+        encounter_id: i64,
+    ) -> Result<()> {
+        let encounter = &args.encounter;
+        let boss = &encounter.current_boss_name;
+        let Some(hp_log) = args.boss_hp_log.get(boss) else {
+            return Ok(());
+        };
+
+        let splits = encounter_phase_splits(
+            boss,
+            hp_log,
+            encounter.entities.get(boss).and_then(|boss| boss.hp_bars),
+            &args.timeline,
+            args.raid_clear
+                .then_some(encounter.last_combat_packet - encounter.fight_start),
+        );
+        store_phase_splits(transaction, encounter_id, &splits)
+    }
+
     fn insert_entities(
         &self,
         transaction: &Transaction,
@@ -487,9 +1210,14 @@ impl Repository {
         buffs: HashMap<String, SupportBuffs>,
         encounter_id: i64,
     ) -> Result<()> {
-        let InsertEncounterArgs { encounter, .. } = args;
+        let InsertEncounterArgs {
+            encounter,
+            inspect_snapshots,
+            ..
+        } = args;
 
         let mut statement = transaction.prepare_cached(INSERT_ENTITY)?;
+        let mut insert_snapshot = transaction.prepare_cached(INSERT_INSPECT_SNAPSHOT)?;
 
         for (_name, entity) in encounter.entities.iter() {
             if !should_insert_entity(entity, &encounter.local_player) {
@@ -511,6 +1239,17 @@ impl Repository {
 
             let support_buffs = buffs.get(&entity.name);
 
+            // players keep the same gear across most encounters, store each snapshot only once
+            let snapshot_hash = match inspect_snapshots.get(&entity.name) {
+                Some(snapshot) => {
+                    let snapshot = canonical_json(snapshot);
+                    let hash = inspect_snapshot_hash(&snapshot);
+                    insert_snapshot.execute(params![hash, compress_json(&snapshot)?])?;
+                    Some(hash)
+                }
+                None => None,
+            };
+
             let params = params![
                 entity.name,
                 encounter_id,
@@ -552,7 +1291,9 @@ impl Repository {
                 entity.damage_stats.rdps_damage_received_support,
                 entity.damage_stats.rdps_damage_given,
                 entity.damage_stats.rdps,
-                entity.damage_stats.ndps
+                entity.damage_stats.ndps,
+                snapshot_hash,
+                json!(entity.battle_items)
             ];
 
             statement.execute(params)?;
@@ -631,10 +1372,119 @@ impl Repository {
     }
 }
 
-#[derive(Clone)]
-struct CharacterStatisticsRow {
-    id: i32,
+fn merge_attached_database(connection: &mut rusqlite::Connection) -> Result<DatabaseMergeSummary> {
+    let transaction = connection.transaction()?;
+
+    // column order differs between databases depending on which migrations added them
+    let encounter_columns = merge_columns(&transaction, "encounter", "id")?;
+    let entity_columns = merge_columns(&transaction, "entity", "encounter_id")?;
+    let preview_columns = merge_columns(&transaction, "encounter_preview", "id")?;
+    let insert_encounter = format!(
+        "INSERT INTO main.encounter ({encounter_columns})
+         SELECT {encounter_columns} FROM merge_source.encounter WHERE id = ?"
+    );
+    let insert_entities = format!(
+        "INSERT INTO main.entity (encounter_id, {entity_columns})
+         SELECT ?, {entity_columns} FROM merge_source.entity WHERE encounter_id = ?"
+    );
+    let insert_preview = format!(
+        "INSERT INTO main.encounter_preview (id, {preview_columns})
+         SELECT ?, {preview_columns} FROM merge_source.encounter_preview WHERE id = ?"
+    );
+
+    let previews = transaction
+        .prepare(SELECT_MERGE_SOURCE_PREVIEWS)?
+        .query_map([], |row| {
+            std::result::Result::Ok((
+                row.get::<_, i64>("id")?,
+                row.get::<_, Option<i64>>("fight_start")?,
+                row.get::<_, Option<String>>("current_boss")?,
+                row.get::<_, Option<String>>("local_player")?,
+                row.get::<_, bool>("favorite")?,
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut summary = DatabaseMergeSummary::default();
+    for (source_id, fight_start, current_boss, local_player, favorite) in previews {
+        let existing: Option<i64> = transaction
+            .prepare_cached(SELECT_MATCHING_ENCOUNTER)?
+            .query_row(params![fight_start, current_boss, local_player], |row| {
+                row.get(0)
+            })
+            .optional()?;
+
+        if let Some(id) = existing {
+            if favorite {
+                transaction.execute(SET_ENCOUNTER_FAVORITE, params![id])?;
+            }
+            summary.duplicates += 1;
+            continue;
+        }
+
+        if transaction
+            .prepare_cached(&insert_encounter)?
+            .execute(params![source_id])?
+            == 0
+        {
+            warn!("skipping merged preview {source_id} without an encounter");
+            continue;
+        }
+        let id = transaction.last_insert_rowid();
+
+        transaction
+            .prepare_cached(&insert_entities)?
+            .execute(params![id, source_id])?;
+        transaction
+            .prepare_cached(&insert_preview)?
+            .execute(params![id, source_id])?;
+        transaction
+            .prepare_cached(INSERT_MERGED_SYNC_LOGS)?
+            .execute(params![id, source_id])?;
+        transaction
+            .prepare_cached(INSERT_MERGED_ENCOUNTER_EVENTS)?
+            .execute(params![id, source_id])?;
+        transaction
+            .prepare_cached(INSERT_MERGED_ENCOUNTER_SPLITS)?
+            .execute(params![id, source_id])?;
+        transaction
+            .prepare_cached(UPSERT_CHARACTER_IDENTITIES)?
+            .execute(params![id])?;
+
+        summary.imported += 1;
+    }
+
+    transaction.execute(INSERT_MERGED_INSPECT_SNAPSHOTS, [])?;
+    transaction.commit()?;
+
+    Ok(summary)
+}
+
+/// Quoted, comma separated columns of `table` present in both databases, except `skip`.
+fn merge_columns(transaction: &Transaction, table: &str, skip: &str) -> Result<String> {
+    let mut statement = transaction.prepare(SELECT_TABLE_COLUMNS)?;
+    let source: BTreeSet<String> = statement
+        .query_map(params![table, "merge_source"], |row| row.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+    let columns = statement
+        .query_map(params![table, "main"], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?
+        .into_iter()
+        .filter(|column| column != skip && source.contains(column))
+        .map(|column| format!("\"{column}\""))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    Ok(columns)
+}
+
+#[derive(Clone)]
+struct CharacterStatisticsRow {
+    id: i32,
     fight_start: i64,
+    local_player: String,
+    class_id: i32,
+    spec: Option<String>,
     boss_name: String,
     raid_name: Option<String>,
     duration: i64,
@@ -670,6 +1520,7 @@ struct RaidProgressionRow {
     boss_max_hp: Option<i64>,
     boss_hp_log: HashMap<String, Vec<BossHpLog>>,
     player_name: String,
+    character_id: u64,
     class_id: i32,
     class_name: String,
     spec: Option<String>,
@@ -687,6 +1538,22 @@ struct RaidProgressionRow {
     support_hyper: Option<f32>,
 }
 
+#[derive(Clone)]
+struct TeammateRow {
+    character_id: u64,
+    class_name: String,
+    pull: TeammatePull,
+}
+
+#[derive(Clone)]
+struct LoadoutRow {
+    snapshot: LoadoutSnapshot,
+    cleared: bool,
+    my_dps: i64,
+    my_rdps: Option<i64>,
+    ark_passive_data: Option<String>,
+}
+
 #[derive(Clone)]
 struct RaidProgressionPullAggregate {
     id: i32,
@@ -726,10 +1593,203 @@ struct RaidProgressionPlayerAggregate {
     last_seen: i64,
 }
 
+fn encounter_events(
+    connection: &rusqlite::Connection,
+    id: &str,
+    kinds: &[TimelineEventKind],
+) -> Result<Vec<TimelineEvent>> {
+    let mut query = SELECT_ENCOUNTER_EVENTS.to_string();
+    if !kinds.is_empty() {
+        let placeholders = "?,".repeat(kinds.len());
+        query.push_str(&format!(
+            " AND kind IN ({})",
+            placeholders.trim_end_matches(',')
+        ));
+    }
+    query.push_str(" ORDER BY timestamp, rowid");
+
+    let params = std::iter::once(id).chain(kinds.iter().map(|kind| kind.as_ref()));
+    let mut statement = connection.prepare(&query)?;
+    let events = statement
+        .query_map(params_from_iter(params), |row| {
+            let kind: String = row.get("kind")?;
+            let kind = kind.parse().map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    1,
+                    rusqlite::types::Type::Text,
+                    Box::new(e),
+                )
+            })?;
+
+            std::result::Result::Ok(TimelineEvent {
+                time: row.get("timestamp")?,
+                kind,
+                entity: row.get("entity")?,
+                value: row.get("value")?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(events)
+}
+
+/// Phase splits of a pull of `boss`. Clears end their last phase at the boss kill, or at
+/// `clear_time` when the kill is missing from the timeline.
+fn encounter_phase_splits(
+    boss: &str,
+    hp_log: &[BossHpLog],
+    hp_bars: Option<u32>,
+    timeline: &[TimelineEvent],
+    clear_time: Option<i64>,
+) -> Vec<PhaseSplit> {
+    let kill = clear_time.map(|clear_time| {
+        timeline
+            .iter()
+            .find(|event| {
+                event.kind == TimelineEventKind::BossKill && event.entity.as_deref() == Some(boss)
+            })
+            .map_or(clear_time, |event| event.time)
+    });
+
+    phase_splits(
+        RAID_DEFINITIONS.phase_splits(boss),
+        hp_log,
+        hp_bars,
+        timeline,
+        kill,
+    )
+}
+
+fn store_phase_splits(
+    connection: &rusqlite::Connection,
+    encounter_id: i64,
+    splits: &[PhaseSplit],
+) -> Result<()> {
+    let mut statement = connection.prepare_cached(INSERT_ENCOUNTER_SPLIT)?;
+    for (phase, split) in splits.iter().enumerate() {
+        statement.execute(params![encounter_id, phase, split.name, split.end])?;
+    }
+
+    Ok(())
+}
+
+/// Computes the splits of pulls saved before splits were stored from their boss hp log, once.
+/// Pulls are marked as computed even when they have no splits so they are not looked at again.
+pub(crate) fn backfill_phase_splits(connection: &rusqlite::Connection) -> Result<()> {
+    let pulls = connection
+        .prepare(SELECT_ENCOUNTERS_WITHOUT_COMPUTED_SPLITS)?
+        .query_map([], |row| {
+            let boss: String = row.get("current_boss")?;
+            let CompressedJson(mut boss_hp_log): CompressedJson<HashMap<String, Vec<BossHpLog>>> =
+                row.get("boss_hp_log")?;
+            if boss_hp_log.is_empty() {
+                let misc_str: String = row.get("misc").unwrap_or_default();
+                boss_hp_log = serde_json::from_str::<EncounterMisc>(&misc_str)
+                    .ok()
+                    .and_then(|misc| misc.boss_hp_log)
+                    .unwrap_or_default();
+            }
+            let cleared: Option<bool> = row.get("cleared")?;
+            let fight_start: i64 = row.get("fight_start")?;
+            let last_combat_packet: i64 = row.get("last_combat_packet")?;
+
+            std::result::Result::Ok((
+                row.get::<_, i64>("id")?,
+                boss_hp_log.remove(&boss).unwrap_or_default(),
+                boss,
+                row.get::<_, Option<u32>>("hp_bars")?,
+                cleared
+                    .unwrap_or_default()
+                    .then_some(last_combat_packet - fight_start),
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    if pulls.is_empty() {
+        return Ok(());
+    }
+
+    info!("computing phase splits of {} pulls", pulls.len());
+    let mut mark_computed = connection.prepare(UPDATE_ENCOUNTER_SPLITS_COMPUTED)?;
+    for (id, hp_log, boss, hp_bars, clear_time) in pulls {
+        if !hp_log.is_empty() {
+            let timeline = encounter_events(connection, &id.to_string(), &[])?;
+            let splits = encounter_phase_splits(&boss, &hp_log, hp_bars, &timeline, clear_time);
+            store_phase_splits(connection, id, &splits)?;
+        }
+        mark_computed.execute(params![id])?;
+    }
+
+    Ok(())
+}
+
+fn character_names(connection: &rusqlite::Connection, name: &str) -> Result<Vec<CharacterName>> {
+    let names = connection
+        .prepare_cached(SELECT_CHARACTER_NAMES)?
+        .query_map(params![name], |row| {
+            std::result::Result::Ok(CharacterName {
+                character_id: row.get("character_id")?,
+                name: row.get("name")?,
+                first_seen: row.get("first_seen")?,
+                last_seen: row.get("last_seen")?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(names)
+}
+
+// other names of every searched word that is a known character name
+fn search_aliases(
+    connection: &rusqlite::Connection,
+    search: &str,
+) -> Result<HashMap<String, Vec<String>>> {
+    let mut aliases = HashMap::new();
+    if search.len() <= 2 {
+        return Ok(aliases);
+    }
+
+    for word in search.split_whitespace() {
+        let names = character_names(connection, word)?
+            .into_iter()
+            .map(|name| name.name)
+            .filter(|name| !name.eq_ignore_ascii_case(word))
+            .collect::<Vec<_>>();
+        if !names.is_empty() {
+            aliases.insert(word.to_string(), names);
+        }
+    }
+
+    Ok(aliases)
+}
+
+fn character_statistics_rows(
+    connection: &rusqlite::Connection,
+    criteria: CharacterStatisticsCriteria,
+) -> Result<Vec<CharacterStatisticsRow>> {
+    let mode = criteria.mode.clone();
+    let boss_to_raid = criteria.boss_to_raid.clone();
+    let (params, query) = build_character_statistics_query(criteria);
+    let mut rows = connection
+        .prepare_cached(&query)?
+        .query_map(params_from_iter(params), map_character_statistics_row)?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if mode == "support" {
+        populate_support_contribution_denominators(connection, &mut rows);
+    }
+
+    for row in rows.iter_mut() {
+        row.raid_name = boss_to_raid.get(&row.boss_name).cloned();
+    }
+
+    Ok(rows)
+}
+
 fn build_character_statistics_query(
     criteria: CharacterStatisticsCriteria,
 ) -> (Vec<String>, String) {
-    let mut params = vec![criteria.character.name];
+    let mut params = vec![criteria.character.name.clone(), criteria.character.name];
     let min_duration = if criteria.min_duration > 0 {
         criteria.min_duration
     } else {
@@ -738,7 +1798,7 @@ fn build_character_statistics_query(
     params.push((min_duration * 1000).to_string());
 
     let mut filters = vec![
-        "e.local_player = ?".to_string(),
+        LOCAL_PLAYER_IDENTITY_FILTER.to_string(),
         "e.duration > ?".to_string(),
         "e.difficulty IS NOT NULL AND e.difficulty != ''".to_string(),
     ];
@@ -794,6 +1854,7 @@ fn build_character_statistics_query(
         "SELECT
             e.id,
             e.fight_start,
+            e.local_player,
             e.current_boss,
             e.duration,
             e.difficulty,
@@ -801,6 +1862,8 @@ fn build_character_statistics_query(
             e.my_dps,
             e.my_rdps,
             e.my_ndps,
+            le.class_id,
+            le.spec,
             le.unbuffed_dps,
             le.rdps_damage_given,
             enc.total_damage_dealt,
@@ -819,6 +1882,134 @@ fn build_character_statistics_query(
     (params, query)
 }
 
+fn build_loadout_progression_query(criteria: LoadoutProgressionCriteria) -> (Vec<String>, String) {
+    let mut params = vec![criteria.character.name.clone(), criteria.character.name];
+    let min_duration = if criteria.min_duration > 0 {
+        criteria.min_duration
+    } else {
+        10
+    };
+    params.push((min_duration * 1000).to_string());
+
+    let mut filters = vec![
+        LOCAL_PLAYER_IDENTITY_FILTER.to_string(),
+        "e.duration > ?".to_string(),
+        "e.difficulty IS NOT NULL AND e.difficulty != ''".to_string(),
+    ];
+
+    let (range_start, range_end) = reset_window_for_range(criteria.range.as_str());
+    if let Some(start) = criteria.start_time.or(range_start) {
+        filters.push("e.fight_start >= ?".to_string());
+        params.push(start.to_string());
+    }
+
+    if let Some(end) = criteria.end_time.or(range_end) {
+        filters.push("e.fight_start <= ?".to_string());
+        params.push(end.to_string());
+    }
+
+    if !criteria.difficulty.is_empty() {
+        filters.push("e.difficulty = ?".to_string());
+        params.push(criteria.difficulty);
+    }
+
+    if !criteria.bosses.is_empty() {
+        let placeholders = "?,".repeat(criteria.bosses.len());
+        let placeholders = placeholders.trim_end_matches(',');
+        filters.push(format!("e.current_boss IN ({})", placeholders));
+        params.extend(criteria.bosses);
+    }
+
+    let query = format!(
+        "SELECT
+            e.id,
+            e.fight_start,
+            e.current_boss,
+            e.difficulty,
+            e.cleared,
+            e.my_dps,
+            e.my_rdps,
+            le.gear_score,
+            le.combat_power,
+            le.loadout_hash,
+            le.ark_passive_data
+        FROM encounter_preview e
+        JOIN entity le ON le.encounter_id = e.id AND le.name = e.local_player
+        WHERE {}
+        ORDER BY e.fight_start ASC",
+        filters.join(" AND ")
+    );
+
+    (params, query)
+}
+
+fn build_support_synergy_query(criteria: SupportSynergyCriteria) -> (Vec<String>, String) {
+    let min_duration = if criteria.min_duration > 0 {
+        criteria.min_duration
+    } else {
+        10
+    };
+    let mut params = vec![(min_duration * 1000).to_string()];
+    let mut filters = vec![
+        "e.local_player IS NOT NULL".to_string(),
+        "e.duration > ?".to_string(),
+        "json_extract(enc.misc, '$.contributionSplits') IS NOT NULL".to_string(),
+    ];
+
+    let (range_start, range_end) = reset_window_for_range(criteria.range.as_str());
+    if let Some(start) = criteria.start_time.or(range_start) {
+        filters.push("e.fight_start >= ?".to_string());
+        params.push(start.to_string());
+    }
+
+    if let Some(end) = criteria.end_time.or(range_end) {
+        filters.push("e.fight_start <= ?".to_string());
+        params.push(end.to_string());
+    }
+
+    if !criteria.difficulty.is_empty() {
+        filters.push("e.difficulty = ?".to_string());
+        params.push(criteria.difficulty);
+    }
+
+    if !criteria.bosses.is_empty() {
+        let placeholders = "?,".repeat(criteria.bosses.len());
+        let placeholders = placeholders.trim_end_matches(',');
+        filters.push(format!("e.current_boss IN ({})", placeholders));
+        params.extend(criteria.bosses);
+    }
+
+    if criteria.cleared_only {
+        filters.push("e.cleared = 1".to_string());
+    }
+
+    let query = format!(
+        "SELECT
+            e.fight_start,
+            e.duration,
+            e.cleared,
+            e.local_player,
+            json_extract(enc.misc, '$.contributionSplits') AS contribution_splits,
+            (
+                SELECT json_group_array(json_object(
+                    'name', p.name,
+                    'characterId', IFNULL(p.character_id, 0),
+                    'classId', IFNULL(p.class_id, 0),
+                    'spec', p.spec
+                ))
+                FROM entity p
+                WHERE p.encounter_id = e.id AND p.entity_type = 'PLAYER'
+            ) AS players
+        FROM encounter_preview e
+        JOIN encounter enc ON enc.id = e.id
+        WHERE {}
+        ORDER BY e.fight_start ASC",
+        filters.join(" AND ")
+    );
+
+    (params, query)
+}
+
 fn build_raid_progression_query(criteria: RaidProgressionCriteria) -> (Vec<String>, String) {
     let min_duration = if criteria.min_duration > 0 {
         criteria.min_duration
@@ -875,6 +2066,7 @@ fn build_raid_progression_query(criteria: RaidProgressionCriteria) -> (Vec<Strin
             enc.boss_hp_log,
             enc.misc,
             p.name AS player_name,
+            p.character_id,
             p.class_id,
             p.class AS class_name,
             p.spec,
@@ -902,49 +2094,181 @@ fn build_raid_progression_query(criteria: RaidProgressionCriteria) -> (Vec<Strin
     (params, query)
 }
 
-fn build_raid_progression_range_query(
-    criteria: RaidProgressionRangeCriteria,
-) -> (Vec<String>, String) {
-    let min_duration = if criteria.min_duration > 0 {
-        criteria.min_duration
-    } else {
-        10
-    };
-    let mut select_params = Vec::new();
-    let mut where_params = vec![(min_duration * 1000).to_string()];
-    let mut filters = vec![
-        "e.duration > ?".to_string(),
-        "e.difficulty IS NOT NULL AND e.difficulty != ''".to_string(),
-    ];
+fn build_raid_runs_query(criteria: RaidRunCriteria) -> (Vec<String>, String) {
+    let mut params = Vec::new();
+    let mut filters = vec!["e.difficulty IS NOT NULL AND e.difficulty != ''".to_string()];
 
-    add_raid_progression_difficulty_filter(criteria.difficulty, &mut filters, &mut where_params);
+    let (range_start, range_end) = reset_window_for_range(criteria.range.as_str());
+    if let Some(start) = criteria.start_time.or(range_start) {
+        filters.push("e.fight_start >= ?".to_string());
+        params.push(start.to_string());
+    }
 
-    if !criteria.bosses.is_empty() {
-        let placeholders = "?,".repeat(criteria.bosses.len());
-        let placeholders = placeholders.trim_end_matches(',');
-        filters.push(format!("e.current_boss IN ({})", placeholders));
-        where_params.extend(criteria.bosses);
+    if let Some(end) = criteria.end_time.or(range_end) {
+        filters.push("e.fight_start <= ?".to_string());
+        params.push(end.to_string());
     }
 
-    let first_clear = if criteria.last_gate_bosses.is_empty() {
-        "MIN(CASE WHEN e.cleared THEN e.fight_start END) AS first_clear".to_string()
-    } else {
-        let placeholders = "?,".repeat(criteria.last_gate_bosses.len());
-        let placeholders = placeholders.trim_end_matches(',');
-        select_params.extend(criteria.last_gate_bosses);
-        format!(
-            "MIN(CASE WHEN e.cleared AND e.current_boss IN ({}) THEN e.fight_start END) AS first_clear",
-            placeholders
-        )
-    };
+    add_raid_progression_difficulty_filter(criteria.difficulty, &mut filters, &mut params);
+
+    if !criteria.local_player.is_empty() {
+        filters.push("e.local_player = ?".to_string());
+        params.push(criteria.local_player);
+    }
 
     let query = format!(
         "SELECT
-            MIN(e.fight_start) AS first_pull,
-            {}
-        FROM encounter_preview e
-        WHERE {}",
-        first_clear,
+            e.id,
+            e.fight_start,
+            e.current_boss,
+            e.duration,
+            e.difficulty,
+            e.cleared,
+            e.local_player,
+            boss.max_hp AS boss_max_hp,
+            (
+                SELECT json_group_array(p.name)
+                FROM entity p
+                WHERE p.encounter_id = e.id AND p.entity_type = 'PLAYER'
+            ) AS players
+        FROM encounter_preview e
+        LEFT JOIN entity boss ON boss.encounter_id = e.id AND boss.name = e.current_boss
+        WHERE {}
+        ORDER BY e.fight_start ASC",
+        filters.join(" AND ")
+    );
+
+    (params, query)
+}
+
+fn build_teammates_query(
+    criteria: &TeammateCriteria,
+    character_id: Option<u64>,
+) -> (Vec<String>, String) {
+    let mut params = Vec::new();
+    let mut filters = vec![
+        "p.character_id NOT IN (
+            SELECT le.character_id
+            FROM encounter_preview le_e
+            JOIN entity le ON le.encounter_id = le_e.id AND le.name = le_e.local_player
+            WHERE le.character_id > 0
+        )"
+        .to_string(),
+    ];
+
+    let (range_start, range_end) = reset_window_for_range(criteria.range.as_str());
+    let start = criteria.start_time.or(range_start);
+    let end = criteria.end_time.or(range_end);
+    let range_filters = |alias: &str, params: &mut Vec<String>| {
+        let mut filters = Vec::new();
+        if let Some(start) = start {
+            filters.push(format!("{alias}.fight_start >= ?"));
+            params.push(start.to_string());
+        }
+        if let Some(end) = end {
+            filters.push(format!("{alias}.fight_start <= ?"));
+            params.push(end.to_string());
+        }
+        filters
+    };
+    filters.extend(range_filters("e", &mut params));
+
+    if let Some(character_id) = character_id {
+        filters.push("p.character_id = ?".to_string());
+        params.push(character_id.to_string());
+    } else if criteria.min_pulls > 1 {
+        // pulls are counted over the same range as the rows
+        let mut shared_filters = vec!["t.character_id > 0".to_string()];
+        shared_filters.extend(range_filters("te", &mut params));
+        filters.push(format!(
+            "p.character_id IN (
+                SELECT t.character_id
+                FROM encounter_preview te
+                JOIN entity t ON t.encounter_id = te.id
+                    AND t.entity_type = 'PLAYER'
+                    AND t.name != te.local_player
+                WHERE {}
+                GROUP BY t.character_id
+                HAVING COUNT(*) >= ?
+            )",
+            shared_filters.join(" AND ")
+        ));
+        params.push(criteria.min_pulls.to_string());
+    }
+
+    let query = format!(
+        "SELECT
+            e.id,
+            e.fight_start,
+            e.current_boss,
+            e.duration,
+            e.difficulty,
+            e.cleared,
+            e.local_player,
+            p.character_id,
+            p.name,
+            p.class_id,
+            p.class AS class_name,
+            p.spec,
+            p.gear_score,
+            p.dps,
+            p.rdps
+        FROM encounter_preview e
+        JOIN entity p ON p.encounter_id = e.id
+            AND p.entity_type = 'PLAYER'
+            AND p.character_id > 0
+            AND p.name != e.local_player
+        WHERE {}
+        ORDER BY e.fight_start ASC",
+        filters.join(" AND ")
+    );
+
+    (params, query)
+}
+
+fn build_raid_progression_range_query(
+    criteria: RaidProgressionRangeCriteria,
+) -> (Vec<String>, String) {
+    let min_duration = if criteria.min_duration > 0 {
+        criteria.min_duration
+    } else {
+        10
+    };
+    let mut select_params = Vec::new();
+    let mut where_params = vec![(min_duration * 1000).to_string()];
+    let mut filters = vec![
+        "e.duration > ?".to_string(),
+        "e.difficulty IS NOT NULL AND e.difficulty != ''".to_string(),
+    ];
+
+    add_raid_progression_difficulty_filter(criteria.difficulty, &mut filters, &mut where_params);
+
+    if !criteria.bosses.is_empty() {
+        let placeholders = "?,".repeat(criteria.bosses.len());
+        let placeholders = placeholders.trim_end_matches(',');
+        filters.push(format!("e.current_boss IN ({})", placeholders));
+        where_params.extend(criteria.bosses);
+    }
+
+    let first_clear = if criteria.last_gate_bosses.is_empty() {
+        "MIN(CASE WHEN e.cleared THEN e.fight_start END) AS first_clear".to_string()
+    } else {
+        let placeholders = "?,".repeat(criteria.last_gate_bosses.len());
+        let placeholders = placeholders.trim_end_matches(',');
+        select_params.extend(criteria.last_gate_bosses);
+        format!(
+            "MIN(CASE WHEN e.cleared AND e.current_boss IN ({}) THEN e.fight_start END) AS first_clear",
+            placeholders
+        )
+    };
+
+    let query = format!(
+        "SELECT
+            MIN(e.fight_start) AS first_pull,
+            {}
+        FROM encounter_preview e
+        WHERE {}",
+        first_clear,
         filters.join(" AND ")
     );
 
@@ -992,6 +2316,9 @@ fn map_character_statistics_row(row: &rusqlite::Row) -> rusqlite::Result<Charact
     std::result::Result::Ok(CharacterStatisticsRow {
         id: row.get("id")?,
         fight_start: row.get("fight_start")?,
+        local_player: row.get("local_player").unwrap_or_default(),
+        class_id: row.get("class_id").unwrap_or_default(),
+        spec: row.get("spec").unwrap_or_default(),
         boss_name: row.get("current_boss")?,
         raid_name: None,
         duration: row.get("duration")?,
@@ -1010,6 +2337,91 @@ fn map_character_statistics_row(row: &rusqlite::Row) -> rusqlite::Result<Charact
     })
 }
 
+fn map_raid_run_row(row: &rusqlite::Row) -> rusqlite::Result<(RaidRunEncounter, i64)> {
+    let JsonColumn(players): JsonColumn<Vec<String>> = row.get("players")?;
+
+    std::result::Result::Ok((
+        RaidRunEncounter {
+            id: row.get("id")?,
+            fight_start: row.get("fight_start")?,
+            duration: row.get("duration")?,
+            boss_name: row.get("current_boss")?,
+            difficulty: row.get("difficulty")?,
+            cleared: row.get("cleared")?,
+            local_player: row
+                .get::<_, Option<String>>("local_player")?
+                .unwrap_or_default(),
+            players,
+            ..Default::default()
+        },
+        row.get::<_, Option<i64>>("boss_max_hp")?
+            .unwrap_or_default(),
+    ))
+}
+
+fn map_teammate_row(row: &rusqlite::Row) -> rusqlite::Result<TeammateRow> {
+    std::result::Result::Ok(TeammateRow {
+        character_id: row.get("character_id")?,
+        class_name: row
+            .get::<_, Option<String>>("class_name")?
+            .unwrap_or_default(),
+        pull: TeammatePull {
+            id: row.get("id")?,
+            fight_start: row.get("fight_start")?,
+            boss_name: row.get("current_boss")?,
+            difficulty: row.get("difficulty")?,
+            duration: row.get("duration")?,
+            cleared: row.get("cleared")?,
+            local_player: row
+                .get::<_, Option<String>>("local_player")?
+                .unwrap_or_default(),
+            name: row.get("name")?,
+            class_id: row.get::<_, Option<i32>>("class_id")?.unwrap_or_default(),
+            spec: row.get("spec")?,
+            gear_score: row.get::<_, Option<f32>>("gear_score")?.unwrap_or_default(),
+            dps: row.get::<_, Option<i64>>("dps")?.unwrap_or_default(),
+            rdps: positive(row.get("rdps")?),
+        },
+    })
+}
+
+fn map_loadout_row(row: &rusqlite::Row) -> rusqlite::Result<LoadoutRow> {
+    std::result::Result::Ok(LoadoutRow {
+        snapshot: LoadoutSnapshot {
+            encounter_id: row.get("id")?,
+            fight_start: row.get("fight_start")?,
+            boss_name: row.get("current_boss")?,
+            difficulty: row.get("difficulty")?,
+            gear_score: row.get::<_, Option<f32>>("gear_score")?.unwrap_or_default(),
+            combat_power: row.get("combat_power")?,
+            loadout_hash: row.get("loadout_hash")?,
+        },
+        cleared: row.get("cleared")?,
+        my_dps: row.get::<_, Option<i64>>("my_dps")?.unwrap_or_default(),
+        my_rdps: positive(row.get("my_rdps")?),
+        ark_passive_data: row
+            .get::<_, Option<String>>("ark_passive_data")?
+            .filter(|data| data != "null"),
+    })
+}
+
+fn map_synergy_row(row: &rusqlite::Row) -> rusqlite::Result<SynergyEncounter> {
+    let JsonColumn(players): JsonColumn<Vec<SynergyPlayer>> = row.get("players")?;
+    let JsonColumn(contribution_splits): JsonColumn<Vec<ContributionSplit>> =
+        row.get("contribution_splits")?;
+
+    std::result::Result::Ok(SynergyEncounter {
+        fight_start: row.get("fight_start")?,
+        duration: row.get("duration")?,
+        cleared: row.get("cleared")?,
+        local_player: row
+            .get::<_, Option<String>>("local_player")?
+            .unwrap_or_default(),
+        players,
+        contribution_splits,
+    })
+}
+
 fn map_raid_progression_row(row: &rusqlite::Row) -> rusqlite::Result<RaidProgressionRow> {
     let misc_str: String = row.get("misc").unwrap_or_default();
     let misc = serde_json::from_str::<EncounterMisc>(misc_str.as_str())
@@ -1060,6 +2472,7 @@ fn map_raid_progression_row(row: &rusqlite::Row) -> rusqlite::Result<RaidProgres
         boss_max_hp: row.get("boss_max_hp").unwrap_or_default(),
         boss_hp_log,
         player_name: row.get("player_name")?,
+        character_id: row.get("character_id").unwrap_or_default(),
         class_id: row.get("class_id").unwrap_or_default(),
         class_name: row.get("class_name").unwrap_or_default(),
         spec: row.get("spec").unwrap_or_default(),
@@ -1107,7 +2520,8 @@ fn build_raid_progression_statistics(
     last_gate_bosses: &[String],
 ) -> RaidProgressionStatistics {
     let mut pulls_by_id: BTreeMap<i32, Vec<RaidProgressionRow>> = BTreeMap::new();
-    let mut players_by_name: BTreeMap<String, RaidProgressionPlayerAggregate> = BTreeMap::new();
+    // keyed by character id so renamed players stay one player
+    let mut players_by_identity: BTreeMap<String, RaidProgressionPlayerAggregate> = BTreeMap::new();
 
     for mut row in rows {
         row.gate = boss_to_raid
@@ -1131,8 +2545,8 @@ fn build_raid_progression_statistics(
                 .copied()
                 .unwrap_or_default();
             let support_contribution = progression_support_contribution(row, rows);
-            players_by_name
-                .entry(row.player_name.clone())
+            players_by_identity
+                .entry(progression_player_key(row))
                 .and_modify(|player| {
                     update_progression_player(player, row, deaths, support_contribution)
                 })
@@ -1142,7 +2556,7 @@ fn build_raid_progression_statistics(
 
     let summary = build_progression_summary(&pulls, last_gate_bosses);
     let gates = build_progression_gates(&pulls);
-    let mut players = players_by_name
+    let mut players = players_by_identity
         .into_values()
         .map(build_progression_player)
         .collect::<Vec<_>>();
@@ -1525,6 +2939,14 @@ fn normalize_death_elapsed(row: &RaidProgressionRow, death_time: i64) -> Option<
     })
 }
 
+fn progression_player_key(row: &RaidProgressionRow) -> String {
+    if row.character_id > 0 {
+        row.character_id.to_string()
+    } else {
+        format!("name:{}", row.player_name)
+    }
+}
+
 fn new_progression_player(
     row: &RaidProgressionRow,
     deaths: i32,
@@ -1593,6 +3015,9 @@ fn update_progression_player(
         player.support_hyper_values.push(support_hyper);
     }
     player.total_deaths += deaths;
+    if row.fight_start >= player.last_seen {
+        player.name = row.player_name.clone();
+    }
     player.last_seen = player.last_seen.max(row.fight_start);
     if player.spec.is_none() {
         player.spec = row.spec.clone();
@@ -1668,10 +3093,10 @@ fn progression_support_party_damage(row: &RaidProgressionRow, rows: &[RaidProgre
 fn populate_support_contribution_denominators(
     connection: &rusqlite::Connection,
     rows: &mut [CharacterStatisticsRow],
-    support_name: &str,
 ) {
     for row in rows.iter_mut().filter(|row| row.rdps_damage_given > 0) {
-        match support_contribution_denominator(connection, row.id, support_name) {
+        // the support may have played under an older name
+        match support_contribution_denominator(connection, row.id, &row.local_player) {
             std::result::Result::Ok(damage) if damage > 0 => row.support_party_damage = damage,
             std::result::Result::Ok(_) => {}
             Err(err) => warn!(
@@ -1859,64 +3284,93 @@ fn build_character_statistics(
     }
 }
 
-fn build_trends(rows: &[CharacterStatisticsRow]) -> Vec<CharacterStatisticsTrend> {
-    const WEEK_MS: i64 = 7 * 24 * 60 * 60 * 1000;
-    let mut buckets: BTreeMap<i64, Vec<CharacterStatisticsRow>> = BTreeMap::new();
-    for row in rows {
-        let bucket = row.fight_start - row.fight_start.rem_euclid(WEEK_MS);
-        buckets.entry(bucket).or_default().push(row.clone());
+fn build_roster_statistics(
+    characters: Vec<(CharacterInfo, &WeeklyReset, Vec<CharacterStatisticsRow>)>,
+    mode: &str,
+    damage_type: &str,
+) -> RosterStatistics {
+    let metric_damage_type = if mode == "support" {
+        "dps"
+    } else {
+        damage_type
+    };
+    let mut weeks: BTreeMap<i64, (RosterWeek, BTreeSet<String>)> = BTreeMap::new();
+    let mut specs: BTreeMap<(i32, Option<String>), Vec<(&str, &CharacterStatisticsRow)>> =
+        BTreeMap::new();
+    let mut raids: BTreeMap<
+        (String, Option<String>),
+        Vec<(&CharacterInfo, &CharacterStatisticsRow)>,
+    > = BTreeMap::new();
+
+    for (character, reset, rows) in characters.iter() {
+        for row in rows {
+            let (week, week_characters) = weeks
+                .entry(reset.last_reset(row.fight_start))
+                .or_insert_with_key(|start_time| {
+                    (
+                        RosterWeek {
+                            start_time: *start_time,
+                            ..Default::default()
+                        },
+                        BTreeSet::new(),
+                    )
+                });
+            week.attempts += 1;
+            week.clears += row.cleared as i32;
+            week.raid_time += row.duration;
+            week_characters.insert(character.name.clone());
+
+            let class_id = if row.class_id != 0 {
+                row.class_id
+            } else {
+                character.class_id
+            };
+            specs
+                .entry((class_id, row.spec.clone()))
+                .or_default()
+                .push((character.name.as_str(), row));
+
+            raids
+                .entry((
+                    row.raid_name
+                        .clone()
+                        .unwrap_or_else(|| row.boss_name.clone()),
+                    row.difficulty.clone(),
+                ))
+                .or_default()
+                .push((character, row));
+        }
     }
 
-    buckets
-        .into_iter()
-        .map(|(start_time, bucket_rows)| {
-            let attempts = bucket_rows.len() as i32;
-            let cleared_rows: Vec<_> = bucket_rows
-                .iter()
-                .filter(|row| row.cleared && row.my_dps > 0)
-                .cloned()
-                .collect();
-            let clears = bucket_rows.iter().filter(|row| row.cleared).count() as i32;
-            CharacterStatisticsTrend {
-                start_time,
-                attempts,
-                clears,
-                median_dps: median_i64(cleared_rows.iter().map(|row| row.my_dps)),
-                best_dps: cleared_rows.iter().map(|row| row.my_dps).max(),
-                support: support_summary(&cleared_rows),
-            }
+    let weeks = weeks
+        .into_values()
+        .map(|(mut week, week_characters)| {
+            week.characters = week_characters.len() as i32;
+            week
         })
-        .collect()
-}
-
-fn build_raid_rows(rows: &[CharacterStatisticsRow]) -> Vec<RaidStatisticsRow> {
-    let mut grouped: BTreeMap<(String, Option<String>), Vec<CharacterStatisticsRow>> =
-        BTreeMap::new();
-    for row in rows {
-        grouped
-            .entry((
-                row.raid_name
-                    .clone()
-                    .unwrap_or_else(|| row.boss_name.clone()),
-                row.difficulty.clone(),
-            ))
-            .or_default()
-            .push(row.clone());
-    }
+        .collect();
 
-    let mut raids: Vec<_> = grouped
+    let mut specs: Vec<_> = specs
         .into_iter()
-        .map(|((boss_name, difficulty), rows)| {
+        .map(|((class_id, spec), rows)| {
             let attempts = rows.len() as i32;
+            let clears = rows.iter().filter(|(_, row)| row.cleared).count() as i32;
             let cleared_rows: Vec<_> = rows
                 .iter()
-                .filter(|row| row.cleared && row.my_dps > 0)
-                .cloned()
+                .filter(|(_, row)| row.cleared)
+                .map(|(_, row)| *row)
                 .collect();
-            let clears = rows.iter().filter(|row| row.cleared).count() as i32;
-            RaidStatisticsRow {
-                boss_name,
-                difficulty,
+            let mut names: Vec<String> = Vec::new();
+            for (name, _) in rows.iter() {
+                if !names.iter().any(|n| n == name) {
+                    names.push(name.to_string());
+                }
+            }
+
+            RosterSpecPerformance {
+                class_id,
+                spec,
+                characters: names,
                 attempts,
                 clears,
                 clear_rate: percent(clears, attempts),
@@ -1938,56 +3392,405 @@ fn build_raid_rows(rows: &[CharacterStatisticsRow]) -> Vec<RaidStatisticsRow> {
                     .iter()
                     .filter_map(|row| damage_value(row, "rdps"))
                     .max(),
-                median_ndps: median_i64(
-                    cleared_rows
-                        .iter()
-                        .filter_map(|row| damage_value(row, "ndps")),
-                ),
-                best_ndps: cleared_rows
-                    .iter()
-                    .filter_map(|row| damage_value(row, "ndps"))
-                    .max(),
-                median_duration: median_i64(cleared_rows.iter().map(|row| row.duration)),
-                last_clear: rows
-                    .iter()
-                    .filter(|row| row.cleared)
-                    .map(|row| row.fight_start)
-                    .max(),
-                support: support_summary(&cleared_rows),
             }
         })
         .collect();
+    specs.sort_by_key(|spec| Reverse(spec.attempts));
 
-    raids.sort_by_key(|row| {
-        (
-            Reverse(row.last_clear.unwrap_or_default()),
-            row.boss_name.clone(),
-        )
-    });
-    raids
-}
+    let raids = raids
+        .into_iter()
+        .map(|((raid, difficulty), rows)| {
+            let attempts = rows.len() as i32;
+            let clears = rows.iter().filter(|(_, row)| row.cleared).count() as i32;
+            let clear_times = || {
+                rows.iter()
+                    .filter(|(_, row)| row.cleared)
+                    .map(|(_, row)| row.fight_start)
+            };
 
-fn damage_value(row: &CharacterStatisticsRow, damage_type: &str) -> Option<i64> {
-    match damage_type {
-        "rdps" => positive(row.my_rdps),
-        "ndps" => positive(row.my_ndps),
-        _ => positive(Some(row.my_dps)),
-    }
-}
+            let mut raid_characters: Vec<RosterRaidCharacter> = Vec::new();
+            for (character, row) in rows.iter() {
+                let index = match raid_characters
+                    .iter()
+                    .position(|c| c.name == character.name)
+                {
+                    Some(index) => index,
+                    None => {
+                        raid_characters.push(RosterRaidCharacter {
+                            name: character.name.clone(),
+                            class_id: character.class_id,
+                            ..Default::default()
+                        });
+                        raid_characters.len() - 1
+                    }
+                };
+                let entry = &mut raid_characters[index];
+                entry.attempts += 1;
+                if row.cleared {
+                    entry.clears += 1;
+                    entry.first_clear = Some(
+                        entry
+                            .first_clear
+                            .map_or(row.fight_start, |time| time.min(row.fight_start)),
+                    );
+                    entry.last_clear = Some(
+                        entry
+                            .last_clear
+                            .map_or(row.fight_start, |time| time.max(row.fight_start)),
+                    );
+                }
+            }
+            for entry in raid_characters.iter_mut() {
+                let damage = rows
+                    .iter()
+                    .filter(|(character, row)| character.name == entry.name && row.cleared)
+                    .filter_map(|(_, row)| damage_value(row, metric_damage_type))
+                    .collect::<Vec<_>>();
+                entry.best_dps = damage.iter().copied().max();
+                entry.median_dps = median_i64(damage.into_iter());
+            }
+            raid_characters.sort_by_key(|c| (Reverse(c.clears), c.first_clear, c.name.clone()));
 
-fn support_summary(rows: &[CharacterStatisticsRow]) -> Option<SupportStatisticsSummary> {
-    let support_rows: Vec<_> = rows
-        .iter()
-        .filter(|row| {
-            positive_f32(row.support_ap).is_some()
-                || positive_f32(row.support_brand).is_some()
-                || positive_f32(row.support_identity).is_some()
-                || positive_f32(row.support_hyper).is_some()
+            RosterRaidProgression {
+                raid,
+                difficulty,
+                attempts,
+                clears,
+                clear_rate: percent(clears, attempts),
+                first_clear: clear_times().min(),
+                last_clear: clear_times().max(),
+                characters: raid_characters,
+            }
         })
         .collect();
 
-    if support_rows.is_empty() {
-        return None;
+    RosterStatistics {
+        characters: characters
+            .into_iter()
+            .map(|(character, _, rows)| {
+                build_character_statistics(character, rows, mode, damage_type)
+            })
+            .collect(),
+        weeks,
+        specs,
+        raids,
+    }
+}
+
+/// Splits `rows` wherever the loadout hash or ark passives change, pulls without a stored
+/// loadout stay with the loadout before them.
+fn build_loadout_progression(mut rows: Vec<LoadoutRow>) -> LoadoutProgression {
+    rows.sort_by_key(|row| row.snapshot.fight_start);
+    let mut segments: Vec<Vec<&LoadoutRow>> = vec![Vec::new()];
+    let mut changes: Vec<LoadoutChange> = Vec::new();
+    // ark passives are compared on their own, they can change without the loadout hash
+    let mut last_loadout: Option<&LoadoutRow> = None;
+    let mut last_ark_passive: Option<&LoadoutRow> = None;
+
+    for row in rows.iter() {
+        let loadout_changed = row.snapshot.loadout_hash.is_some()
+            && last_loadout
+                .is_some_and(|last| last.snapshot.loadout_hash != row.snapshot.loadout_hash);
+        let ark_passive_changed = row.ark_passive_data.is_some()
+            && last_ark_passive.is_some_and(|last| last.ark_passive_data != row.ark_passive_data);
+
+        if let Some(previous) = last_loadout
+            .filter(|_| loadout_changed)
+            .or(last_ark_passive.filter(|_| ark_passive_changed))
+        {
+            let previous_loadout_hash =
+                last_loadout.and_then(|last| last.snapshot.loadout_hash.clone());
+            changes.push(LoadoutChange {
+                encounter_id: row.snapshot.encounter_id,
+                fight_start: row.snapshot.fight_start,
+                loadout_hash: row
+                    .snapshot
+                    .loadout_hash
+                    .clone()
+                    .or_else(|| previous_loadout_hash.clone()),
+                previous_loadout_hash,
+                gear_score: row.snapshot.gear_score,
+                previous_gear_score: previous.snapshot.gear_score,
+                combat_power: row.snapshot.combat_power,
+                previous_combat_power: previous.snapshot.combat_power,
+                ark_passive_changed,
+                bosses: Vec::new(),
+            });
+            segments.push(Vec::new());
+        }
+
+        if row.snapshot.loadout_hash.is_some() {
+            last_loadout = Some(row);
+        }
+        if row.ark_passive_data.is_some() {
+            last_ark_passive = Some(row);
+        }
+        if let Some(segment) = segments.last_mut() {
+            segment.push(row);
+        }
+    }
+
+    for (index, change) in changes.iter_mut().enumerate() {
+        let before = loadout_performance(&segments[index]);
+        let after = loadout_performance(&segments[index + 1]);
+        change.bosses = before
+            .into_iter()
+            .filter_map(|((boss_name, difficulty), before)| {
+                let after = after.get(&(boss_name.clone(), difficulty.clone()))?.clone();
+                Some(LoadoutBossComparison {
+                    dps_change: relative_change(before.median_dps, after.median_dps),
+                    rdps_change: relative_change(before.median_rdps, after.median_rdps),
+                    boss_name,
+                    difficulty,
+                    before,
+                    after,
+                })
+            })
+            .collect();
+    }
+
+    LoadoutProgression {
+        timeline: rows.into_iter().map(|row| row.snapshot).collect(),
+        changes,
+    }
+}
+
+fn loadout_performance(
+    rows: &[&LoadoutRow],
+) -> BTreeMap<(String, Option<String>), LoadoutPerformance> {
+    let mut grouped: BTreeMap<(String, Option<String>), Vec<&LoadoutRow>> = BTreeMap::new();
+    for row in rows
+        .iter()
+        .copied()
+        .filter(|row| row.cleared && row.my_dps > 0)
+    {
+        grouped
+            .entry((
+                row.snapshot.boss_name.clone(),
+                row.snapshot.difficulty.clone(),
+            ))
+            .or_default()
+            .push(row);
+    }
+
+    grouped
+        .into_iter()
+        .map(|(key, rows)| {
+            let performance = LoadoutPerformance {
+                clears: rows.len() as i32,
+                median_dps: median_i64(rows.iter().map(|row| row.my_dps)),
+                median_rdps: median_i64(rows.iter().filter_map(|row| row.my_rdps)),
+            };
+            (key, performance)
+        })
+        .collect()
+}
+
+/// Change from `before` to `after` in percent.
+fn relative_change(before: Option<i64>, after: Option<i64>) -> Option<f32> {
+    let before = positive(before)?;
+    let after = after?;
+    Some((after - before) as f32 / before as f32 * 100.0)
+}
+
+fn build_teammate(character_id: u64, rows: &[TeammateRow]) -> Teammate {
+    let mut rows = rows.iter().collect::<Vec<_>>();
+    rows.sort_by_key(|row| row.pull.fight_start);
+
+    let mut names: Vec<TeammateName> = Vec::new();
+    for row in &rows {
+        match names.iter_mut().find(|name| name.name == row.pull.name) {
+            Some(name) => name.last_seen = row.pull.fight_start,
+            None => names.push(TeammateName {
+                name: row.pull.name.clone(),
+                first_seen: row.pull.fight_start,
+                last_seen: row.pull.fight_start,
+            }),
+        }
+    }
+
+    let mut bosses: BTreeMap<(String, Option<String>), Vec<&TeammatePull>> = BTreeMap::new();
+    for row in &rows {
+        bosses
+            .entry((row.pull.boss_name.clone(), row.pull.difficulty.clone()))
+            .or_default()
+            .push(&row.pull);
+    }
+    let bosses = bosses
+        .into_iter()
+        .map(|((boss_name, difficulty), pulls)| {
+            let clears = pulls.iter().filter(|pull| pull.cleared).collect::<Vec<_>>();
+            TeammateBoss {
+                boss_name,
+                difficulty,
+                pulls: pulls.len() as i32,
+                clears: clears.len() as i32,
+                median_dps: median_i64(clears.iter().filter_map(|pull| positive(Some(pull.dps)))),
+                median_rdps: median_i64(clears.iter().filter_map(|pull| pull.rdps)),
+            }
+        })
+        .collect();
+
+    let latest = rows.last();
+    let shared_pulls = rows.len() as i32;
+    let shared_clears = rows.iter().filter(|row| row.pull.cleared).count() as i32;
+
+    Teammate {
+        character_id,
+        name: latest.map(|row| row.pull.name.clone()).unwrap_or_default(),
+        names,
+        class_id: latest.map(|row| row.pull.class_id).unwrap_or_default(),
+        class: latest.map(|row| row.class_name.clone()).unwrap_or_default(),
+        spec: rows.iter().rev().find_map(|row| row.pull.spec.clone()),
+        max_gear_score: rows
+            .iter()
+            .map(|row| row.pull.gear_score)
+            .fold(0.0, f32::max),
+        shared_pulls,
+        shared_clears,
+        clear_rate: if shared_pulls > 0 {
+            shared_clears as f32 / shared_pulls as f32
+        } else {
+            0.0
+        },
+        first_seen: rows
+            .first()
+            .map(|row| row.pull.fight_start)
+            .unwrap_or_default(),
+        last_seen: latest.map(|row| row.pull.fight_start).unwrap_or_default(),
+        bosses,
+    }
+}
+
+fn build_trends(rows: &[CharacterStatisticsRow]) -> Vec<CharacterStatisticsTrend> {
+    const WEEK_MS: i64 = 7 * 24 * 60 * 60 * 1000;
+    let mut buckets: BTreeMap<i64, Vec<CharacterStatisticsRow>> = BTreeMap::new();
+    for row in rows {
+        let bucket = row.fight_start - row.fight_start.rem_euclid(WEEK_MS);
+        buckets.entry(bucket).or_default().push(row.clone());
+    }
+
+    buckets
+        .into_iter()
+        .map(|(start_time, bucket_rows)| {
+            let attempts = bucket_rows.len() as i32;
+            let cleared_rows: Vec<_> = bucket_rows
+                .iter()
+                .filter(|row| row.cleared && row.my_dps > 0)
+                .cloned()
+                .collect();
+            let clears = bucket_rows.iter().filter(|row| row.cleared).count() as i32;
+            CharacterStatisticsTrend {
+                start_time,
+                attempts,
+                clears,
+                median_dps: median_i64(cleared_rows.iter().map(|row| row.my_dps)),
+                best_dps: cleared_rows.iter().map(|row| row.my_dps).max(),
+                support: support_summary(&cleared_rows),
+            }
+        })
+        .collect()
+}
+
+fn build_raid_rows(rows: &[CharacterStatisticsRow]) -> Vec<RaidStatisticsRow> {
+    let mut grouped: BTreeMap<(String, Option<String>), Vec<CharacterStatisticsRow>> =
+        BTreeMap::new();
+    for row in rows {
+        grouped
+            .entry((
+                row.raid_name
+                    .clone()
+                    .unwrap_or_else(|| row.boss_name.clone()),
+                row.difficulty.clone(),
+            ))
+            .or_default()
+            .push(row.clone());
+    }
+
+    let mut raids: Vec<_> = grouped
+        .into_iter()
+        .map(|((boss_name, difficulty), rows)| {
+            let attempts = rows.len() as i32;
+            let cleared_rows: Vec<_> = rows
+                .iter()
+                .filter(|row| row.cleared && row.my_dps > 0)
+                .cloned()
+                .collect();
+            let clears = rows.iter().filter(|row| row.cleared).count() as i32;
+            RaidStatisticsRow {
+                boss_name,
+                difficulty,
+                attempts,
+                clears,
+                clear_rate: percent(clears, attempts),
+                median_dps: median_i64(
+                    cleared_rows
+                        .iter()
+                        .filter_map(|row| damage_value(row, "dps")),
+                ),
+                best_dps: cleared_rows
+                    .iter()
+                    .filter_map(|row| damage_value(row, "dps"))
+                    .max(),
+                median_rdps: median_i64(
+                    cleared_rows
+                        .iter()
+                        .filter_map(|row| damage_value(row, "rdps")),
+                ),
+                best_rdps: cleared_rows
+                    .iter()
+                    .filter_map(|row| damage_value(row, "rdps"))
+                    .max(),
+                median_ndps: median_i64(
+                    cleared_rows
+                        .iter()
+                        .filter_map(|row| damage_value(row, "ndps")),
+                ),
+                best_ndps: cleared_rows
+                    .iter()
+                    .filter_map(|row| damage_value(row, "ndps"))
+                    .max(),
+                median_duration: median_i64(cleared_rows.iter().map(|row| row.duration)),
+                last_clear: rows
+                    .iter()
+                    .filter(|row| row.cleared)
+                    .map(|row| row.fight_start)
+                    .max(),
+                support: support_summary(&cleared_rows),
+            }
+        })
+        .collect();
+
+    raids.sort_by_key(|row| {
+        (
+            Reverse(row.last_clear.unwrap_or_default()),
+            row.boss_name.clone(),
+        )
+    });
+    raids
+}
+
+fn damage_value(row: &CharacterStatisticsRow, damage_type: &str) -> Option<i64> {
+    match damage_type {
+        "rdps" => positive(row.my_rdps),
+        "ndps" => positive(row.my_ndps),
+        _ => positive(Some(row.my_dps)),
+    }
+}
+
+fn support_summary(rows: &[CharacterStatisticsRow]) -> Option<SupportStatisticsSummary> {
+    let support_rows: Vec<_> = rows
+        .iter()
+        .filter(|row| {
+            positive_f32(row.support_ap).is_some()
+                || positive_f32(row.support_brand).is_some()
+                || positive_f32(row.support_identity).is_some()
+                || positive_f32(row.support_hyper).is_some()
+        })
+        .collect();
+
+    if support_rows.is_empty() {
+        return None;
     }
 
     Some(SupportStatisticsSummary {
@@ -2301,7 +4104,7 @@ pub fn compute_support_buffs(
 mod tests {
     use std::collections::BTreeMap;
 
-    use crate::{data::AssetPreloader, database::Database};
+    use crate::{data::preload_test_assets, database::Database};
     use chrono::Utc;
     use hashbrown::HashSet;
     use rand::{Rng, rngs::ThreadRng, seq::IndexedRandom};
@@ -2433,6 +4236,9 @@ mod tests {
             CharacterStatisticsRow {
                 id: 1,
                 fight_start: 1_000,
+                local_player: String::new(),
+                class_id: 0,
+                spec: None,
                 boss_name: "Boss".to_string(),
                 raid_name: None,
                 duration: 300_000,
@@ -2452,6 +4258,9 @@ mod tests {
             CharacterStatisticsRow {
                 id: 2,
                 fight_start: 2_000,
+                local_player: String::new(),
+                class_id: 0,
+                spec: None,
                 boss_name: "Boss".to_string(),
                 raid_name: None,
                 duration: 240_000,
@@ -2471,6 +4280,9 @@ mod tests {
             CharacterStatisticsRow {
                 id: 3,
                 fight_start: 3_000,
+                local_player: String::new(),
+                class_id: 0,
+                spec: None,
                 boss_name: "Boss".to_string(),
                 raid_name: None,
                 duration: 30_000,
@@ -2548,6 +4360,9 @@ mod tests {
         let row = CharacterStatisticsRow {
             id: 1,
             fight_start: 1_000,
+            local_player: "Support".to_string(),
+            class_id: 0,
+            spec: None,
             boss_name: "Boss".to_string(),
             raid_name: None,
             duration: 300_000,
@@ -2816,6 +4631,9 @@ mod tests {
             CharacterStatisticsRow {
                 id: 1,
                 fight_start: 1_000,
+                local_player: String::new(),
+                class_id: 0,
+                spec: None,
                 boss_name: "Dark Mountain Predator".to_string(),
                 raid_name: Some("Valtan G1".to_string()),
                 duration: 120_000,
@@ -2835,6 +4653,9 @@ mod tests {
             CharacterStatisticsRow {
                 id: 2,
                 fight_start: 2_000,
+                local_player: String::new(),
+                class_id: 0,
+                spec: None,
                 boss_name: "Leader Lugaru".to_string(),
                 raid_name: Some("Valtan G1".to_string()),
                 duration: 180_000,
@@ -2902,6 +4723,7 @@ mod tests {
             boss_max_hp: None,
             boss_hp_log: HashMap::new(),
             player_name: name.to_string(),
+            character_id: 0,
             class_id: 102,
             class_name: "Class".to_string(),
             spec: Some("Spec".to_string()),
@@ -2923,8 +4745,7 @@ mod tests {
     #[test]
     fn should_insert_encounter() {
         let version = "1.14.0";
-        let current_dir = std::env::current_dir().unwrap();
-        AssetPreloader::new(&current_dir).unwrap();
+        preload_test_assets();
         let database = Database::memory(version).unwrap();
 
         let repository = database.create_repository();
@@ -2966,146 +4787,1083 @@ mod tests {
             .unwrap();
 
         assert_eq!(
-            actual_encounter.current_boss_name,
-            expected_encounter.current_boss_name
+            actual_encounter.current_boss_name,
+            expected_encounter.current_boss_name
+        );
+        assert_eq!(actual_encounter.duration, expected_encounter.duration);
+        assert_eq!(actual_encounter.difficulty, expected_encounter.difficulty);
+        assert_eq!(actual_encounter.cleared, expected_encounter.cleared);
+        assert_eq!(
+            actual_encounter.boss_only_damage,
+            expected_encounter.boss_only_damage
+        );
+
+        assert!(actual_encounter.encounter_damage_stats.dps > 0);
+        assert_eq!(
+            actual_encounter.encounter_damage_stats.dps,
+            expected_encounter.encounter_damage_stats.dps
+        );
+        assert!(actual_encounter.encounter_damage_stats.top_damage_dealt > 0);
+        assert_eq!(
+            actual_encounter.encounter_damage_stats.top_damage_dealt,
+            expected_encounter.encounter_damage_stats.top_damage_dealt
+        );
+        assert_eq!(
+            actual_encounter.encounter_damage_stats.top_damage_taken,
+            expected_encounter.encounter_damage_stats.top_damage_taken
+        );
+        assert!(actual_encounter.encounter_damage_stats.total_damage_dealt > 0);
+        assert_eq!(
+            actual_encounter.encounter_damage_stats.total_damage_dealt,
+            expected_encounter.encounter_damage_stats.total_damage_dealt
+        );
+        assert_eq!(
+            actual_encounter.encounter_damage_stats.total_damage_taken,
+            expected_encounter.encounter_damage_stats.total_damage_taken
+        );
+        assert_eq!(
+            actual_encounter
+                .encounter_damage_stats
+                .total_effective_shielding,
+            expected_encounter
+                .encounter_damage_stats
+                .total_effective_shielding
+        );
+        assert!(actual_encounter.encounter_damage_stats.boss_hp_log.len() > 0);
+        assert_eq!(
+            actual_encounter
+                .encounter_damage_stats
+                .stagger
+                .map(|stagger| stagger.checks),
+            expected_encounter
+                .encounter_damage_stats
+                .stagger
+                .map(|stagger| stagger.checks)
+        );
+
+        let actual_misc = actual_encounter.encounter_damage_stats.misc.unwrap();
+        assert!(actual_misc.raid_clear.filter(|pr| *pr).is_some());
+        assert!(actual_misc.region.filter(|pr| !pr.is_empty()).is_some());
+        assert!(actual_misc.version.filter(|pr| !pr.is_empty()).is_some());
+        assert!(actual_misc.party_info.filter(|pr| !pr.is_empty()).is_some());
+
+        let mut actual: Vec<_> = actual_encounter.entities.values().collect();
+        actual.sort_by_key(|e| &e.name);
+
+        let mut expected: Vec<_> = expected_encounter.entities.values().collect();
+        expected.sort_by_key(|e| &e.name);
+
+        for (actual, expected) in actual.iter().zip(expected.iter()) {
+            assert_eq!(actual.name, expected.name);
+
+            if actual.entity_type == EntityType::Boss {
+                assert!(actual.damage_stats.damage_dealt > 0);
+                assert!(actual.damage_stats.damage_taken > 0);
+                continue;
+            }
+
+            assert_eq!(actual.gear_score, expected.gear_score);
+            assert!(actual.spec.is_some());
+            assert_eq!(actual.spec, expected.spec);
+            assert!(actual.skill_stats.casts > 0);
+            assert!(actual.skill_stats.crits > 0);
+            assert!(actual.skill_stats.hits > 0);
+
+            for (_, skill) in actual.skills.iter() {
+                assert!(skill.dps > 0);
+                assert!(skill.hits > 0);
+                assert!(skill.crits > 0);
+                assert!(skill.total_damage > 0);
+                assert!(skill.cast_log.len() > 0);
+            }
+
+            assert!(actual.combat_power.filter(|pr| *pr > 1500.0).is_some());
+            assert!(actual.loadout_hash.is_some());
+            assert!(
+                actual
+                    .engraving_data
+                    .as_ref()
+                    .filter(|pr| pr.len() > 1)
+                    .is_some()
+            );
+            assert!(actual.ark_passive_active.unwrap());
+            assert!(
+                actual
+                    .ark_passive_data
+                    .as_ref()
+                    .filter(|pr| pr.enlightenment.is_some()
+                        && pr.leap.is_some()
+                        && pr.evolution.is_some())
+                    .is_some()
+            );
+
+            assert!(actual.damage_stats.unbuffed_damage > 0);
+            assert!(actual.damage_stats.unbuffed_dps > 0);
+            assert!(actual.damage_stats.damage_dealt > 0);
+            assert!(actual.damage_stats.hyper_awakening_damage > 0);
+            assert!(actual.damage_stats.dps > 0);
+            assert!(actual.damage_stats.dps_average.len() > 0);
+            assert!(actual.damage_stats.dps_rolling_10s_avg.len() > 0);
+            assert!(actual.damage_stats.damage_taken > 0);
+            assert!(actual.damage_stats.shields_given > 0);
+            assert!(!actual.damage_stats.shields_given_by.is_empty());
+            assert!(actual.damage_stats.shields_received > 0);
+            assert!(!actual.damage_stats.shields_received_by.is_empty());
+            assert!(actual.damage_stats.damage_absorbed > 0);
+            assert!(!actual.damage_stats.damage_absorbed_by.is_empty());
+            assert!(actual.damage_stats.damage_absorbed_on_others > 0);
+            assert!(!actual.damage_stats.damage_absorbed_on_others_by.is_empty());
+        }
+
+        let preview = paged.encounters.first().unwrap();
+
+        assert_eq!(preview.duration, expected_encounter.duration);
+        assert_eq!(preview.classes.len(), 8);
+        assert_eq!(preview.names.len(), 8);
+        assert_eq!(preview.fight_start, expected_encounter.fight_start);
+        assert_eq!(preview.difficulty, expected_encounter.difficulty);
+        assert_eq!(preview.boss_name, expected_encounter.current_boss_name);
+        assert_eq!(preview.cleared, true);
+        assert_eq!(preview.cleared, expected_encounter.cleared);
+
+        assert!(preview.support_ap.is_some());
+        assert!(preview.support_brand.is_some());
+        assert!(preview.support_identity.is_some());
+        assert!(preview.support_hyper.is_some());
+
+        {
+            let local_player_name = &expected_encounter.local_player;
+            let local_player = expected_encounter.entities.get(local_player_name).unwrap();
+            assert!(preview.my_dps > 0);
+            assert_eq!(preview.my_dps, local_player.damage_stats.dps);
+        }
+    }
+
+    #[test]
+    fn exported_encounter_imports_once_with_original_stats() {
+        let source = test_repository();
+        let target = test_repository();
+
+        let id = insert_encounter(&source, 0, |_| {}).0.to_string();
+        let original = source.get_encounter(&id).unwrap();
+        let export = source.export_encounter(&id, VERSION).unwrap();
+
+        let path = std::env::temp_dir().join(format!("{}.loalog", uuid::Uuid::new_v4()));
+        export.write(&path).unwrap();
+        let export = EncounterExport::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let local = &original.entities[&original.local_player];
+        let exported_local = &export.encounter.entities[&original.local_player];
+        assert_eq!(exported_local.combat_power, local.combat_power);
+        assert_eq!(exported_local.engraving_data, local.engraving_data);
+        assert_eq!(exported_local.loadout_hash, local.loadout_hash);
+        assert!(exported_local.ark_passive_data.is_some());
+        assert!(export.clone().into_insert_args().player_info.is_none());
+
+        let with_rdps_valid = |rdps_valid| {
+            let mut export = export.clone();
+            export
+                .encounter
+                .encounter_damage_stats
+                .misc
+                .as_mut()
+                .unwrap()
+                .rdps_valid = rdps_valid;
+            export.into_insert_args().rdps_valid
+        };
+        assert!(with_rdps_valid(None));
+        assert!(!with_rdps_valid(Some(false)));
+
+        let imported = target.import_encounter(export.clone()).unwrap();
+        assert!(!imported.duplicate);
+        let duplicate = target.import_encounter(export).unwrap();
+        assert!(duplicate.duplicate);
+        assert_eq!(duplicate.id, imported.id);
+
+        let actual = target.get_encounter(&imported.id.to_string()).unwrap();
+        assert_eq!(actual.fight_start, original.fight_start);
+        assert_eq!(actual.last_combat_packet, original.last_combat_packet);
+        assert_eq!(actual.duration, original.duration);
+        assert_eq!(
+            actual.encounter_damage_stats.dps,
+            original.encounter_damage_stats.dps
+        );
+        assert_eq!(
+            actual.encounter_damage_stats.boss_hp_log.len(),
+            original.encounter_damage_stats.boss_hp_log.len()
+        );
+
+        let actual_misc = actual.encounter_damage_stats.misc.unwrap();
+        let original_misc = original.encounter_damage_stats.misc.unwrap();
+        assert_eq!(actual_misc.ntp_fight_start, original_misc.ntp_fight_start);
+        assert_eq!(actual_misc.party_info, original_misc.party_info);
+
+        for (name, expected) in original.entities.iter() {
+            let entity = actual.entities.get(name).unwrap();
+            assert_eq!(entity.damage_stats.dps, expected.damage_stats.dps);
+            assert_eq!(entity.damage_stats.rdps, expected.damage_stats.rdps);
+            assert_eq!(
+                entity.damage_stats.unbuffed_dps,
+                expected.damage_stats.unbuffed_dps
+            );
+            assert_eq!(
+                entity.damage_stats.dps_average,
+                expected.damage_stats.dps_average
+            );
+            assert_eq!(entity.combat_power, expected.combat_power);
+            assert_eq!(entity.engraving_data, expected.engraving_data);
+            assert_eq!(entity.loadout_hash, expected.loadout_hash);
+            for (skill_id, skill) in expected.skills.iter() {
+                assert_eq!(entity.skills[skill_id].cast_log, skill.cast_log);
+            }
+        }
+    }
+
+    #[test]
+    fn merges_other_database_skipping_duplicates_and_keeping_favorites() {
+        preload_test_assets();
+        let directory = std::env::temp_dir().join(format!("merge-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let source_path = directory.join("source.db");
+
+        let target = test_repository();
+        let shared = build_args(VERSION);
+        target.insert_data(shared.clone()).unwrap();
+
+        {
+            let source = Database::new(source_path.clone(), VERSION)
+                .unwrap()
+                .create_repository();
+            let duplicate = source.insert_data(shared.clone()).unwrap();
+            source.toggle_encounter_favorite(duplicate as i32).unwrap();
+
+            let (other, _) = insert_encounter(&source, 60_000, |_| {});
+            source
+                .insert_sync_logs(InsertSyncLogsArgs {
+                    encounter: other,
+                    upstream: "upstream".to_string(),
+                    failed: false,
+                })
+                .unwrap();
+        }
+
+        let source_bytes = std::fs::read(&source_path).unwrap();
+        let summary = target.merge_database(&source_path, VERSION).unwrap();
+        assert_eq!(std::fs::read(&source_path).unwrap(), source_bytes);
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(summary.imported, 1);
+        assert_eq!(summary.duplicates, 1);
+        assert_eq!(target.get_encounter_count().unwrap(), 2);
+
+        let original = target.get_encounter("1").unwrap();
+        assert!(original.favorite);
+
+        let merged = target.get_encounter("2").unwrap();
+        assert_eq!(merged.fight_start, shared.encounter.fight_start + 60_000);
+        assert_eq!(merged.sync.as_deref(), Some("upstream"));
+        assert_eq!(merged.entities.len(), original.entities.len());
+    }
+
+    #[test]
+    fn exports_stored_encounter_to_csv() {
+        let repository = test_repository();
+        let (id, _) = insert_encounter(&repository, 0, |_| {});
+        let encounter = repository.get_encounter(&id.to_string()).unwrap();
+        let top = encounter
+            .entities
+            .values()
+            .filter(|e| e.entity_type == EntityType::Player)
+            .max_by_key(|e| e.damage_stats.damage_dealt)
+            .unwrap();
+
+        let directory = std::env::temp_dir().join(format!("csv-{}", uuid::Uuid::new_v4()));
+        let summary = crate::database::csv_export::export_csv(
+            &repository,
+            EncounterSelection::Search {
+                search: String::new(),
+                filter: SearchFilter::default(),
+            },
+            &directory,
+        )
+        .unwrap();
+        let players = std::fs::read_to_string(&summary.players_path).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(summary.encounters, 1);
+        let mut lines = players.lines();
+        assert_eq!(
+            lines.next().unwrap(),
+            crate::database::csv_export::PLAYER_COLUMNS.join(",")
+        );
+        let row = lines.next().unwrap().split(',').collect::<Vec<_>>();
+        assert_eq!(row[0], id.to_string());
+        assert_eq!(row[1], encounter.fight_start.to_string());
+        assert_eq!(row[7], top.name);
+        assert_eq!(row[13], top.damage_stats.damage_dealt.to_string());
+        assert_eq!(row[6], (top.name == encounter.local_player).to_string());
+        assert_eq!(lines.count() as i32 + 1, summary.player_rows);
+
+        let (second, _) = insert_encounter(&repository, 60 * 60_000, |_| {});
+        let batch = repository
+            .get_encounters_with_support_buffs(&[second, -1, id])
+            .unwrap();
+        assert_eq!(
+            batch.iter().map(|(id, ..)| *id).collect::<Vec<_>>(),
+            vec![second, id]
+        );
+        let (_, batched, support_buffs) = &batch[1];
+        assert_eq!(batched.fight_start, encounter.fight_start);
+        assert_eq!(batched.sync, encounter.sync);
+        assert_eq!(
+            batched.encounter_damage_stats.dps,
+            encounter.encounter_damage_stats.dps
+        );
+        assert_eq!(batched.entities.len(), encounter.entities.len());
+        for (name, expected) in encounter.entities.iter() {
+            assert_eq!(
+                batched.entities[name].damage_stats.dps,
+                expected.damage_stats.dps
+            );
+        }
+        assert_eq!(
+            support_buffs.len(),
+            repository.get_support_buffs(&id.to_string()).unwrap().len()
+        );
+    }
+
+    #[test]
+    fn stores_timeline_and_filters_it_by_kind() {
+        let repository = test_repository();
+
+        let event = |time, kind, entity: Option<&str>, value| TimelineEvent {
+            time,
+            kind,
+            entity: entity.map(str::to_string),
+            value,
+        };
+        let mut args = build_args(VERSION);
+        args.timeline = vec![
+            event(0, TimelineEventKind::RaidStart, None, None),
+            event(5_000, TimelineEventKind::Death, Some("Alpha"), None),
+            event(7_000, TimelineEventKind::Revive, Some("Alpha"), None),
+            event(9_000, TimelineEventKind::PhaseTransition, None, Some(1)),
+        ];
+        let id = repository.insert_data(args.clone()).unwrap().to_string();
+
+        assert_eq!(
+            repository.get_encounter_events(&id, &[]).unwrap(),
+            args.timeline
+        );
+        assert_eq!(
+            repository
+                .get_encounter_events(&id, &[TimelineEventKind::Death, TimelineEventKind::Revive])
+                .unwrap(),
+            args.timeline[1..3]
+        );
+        assert_eq!(
+            repository.export_encounter(&id, VERSION).unwrap().timeline,
+            args.timeline
+        );
+    }
+
+    #[test]
+    fn stores_phase_splits_and_compares_pulls_to_sum_of_best() {
+        let repository = test_repository();
+
+        let insert = |log: &[(i32, f32)], kill: Option<i64>| {
+            insert_encounter(&repository, 0, |args| {
+                let boss = args.encounter.current_boss_name.clone();
+                let log = log
+                    .iter()
+                    .map(|(time, p)| BossHpLog::new(*time, 0, *p))
+                    .collect();
+                args.boss_hp_log = HashMap::from([(boss, log)]);
+                args.raid_clear = kill.is_some();
+                args.encounter.last_combat_packet =
+                    args.encounter.fight_start + kill.unwrap_or(60_000);
+            })
+            .0
+        };
+        let first = insert(&[(10, 0.7), (20, 0.5), (30, 0.2)], Some(100_000));
+        let second = insert(&[(15, 0.7), (20, 0.4), (35, 0.1)], Some(90_000));
+        let wipe = insert(&[(12, 0.7), (30, 0.6)], None);
+        // pulls stored before splits were computed get them from their boss hp log, once
+        {
+            let connection = repository.0.get().unwrap();
+            connection
+                .execute(
+                    "DELETE FROM encounter_split WHERE encounter_id IN (?1, ?2)",
+                    params![first, wipe],
+                )
+                .unwrap();
+            connection
+                .execute(
+                    "UPDATE encounter_preview SET splits_computed = 0 WHERE id IN (?1, ?2)",
+                    params![first, wipe],
+                )
+                .unwrap();
+            backfill_phase_splits(&connection).unwrap();
+            let pending: i64 = connection
+                .query_row(
+                    "SELECT COUNT(*) FROM encounter_preview WHERE splits_computed = 0",
+                    [],
+                    |row| row.get(0),
+                )
+                .unwrap();
+            assert_eq!(pending, 0);
+        }
+
+        let splits = repository
+            .get_phase_splits("Mordum, the Abyssal Punisher", "Hard", Some(wipe))
+            .unwrap();
+
+        let best = |name: &str, duration, encounter_id| BestPhaseSplit {
+            name: name.to_string(),
+            duration,
+            encounter_id,
+        };
+        assert_eq!(splits.clears, 2);
+        assert_eq!(
+            splits.phases,
+            vec![
+                best("75%", 10_000, first),
+                best("50%", 5_000, second),
+                best("25%", 10_000, first),
+                best("Kill", 55_000, second),
+            ]
+        );
+        assert_eq!(splits.sum_of_best, Some(80_000));
+        assert_eq!(splits.best_clear, Some(90_000));
+        assert_eq!(splits.best_clear_id, Some(second));
+        assert_eq!(
+            splits.pull,
+            Some(PullPhaseSplits {
+                id: wipe,
+                phases: vec![PullPhaseSplit {
+                    name: "75%".to_string(),
+                    end: 12_000,
+                    duration: 12_000,
+                    best: Some(10_000),
+                    delta: Some(2_000),
+                }],
+                time_lost: 2_000,
+            })
+        );
+    }
+
+    #[test]
+    fn loads_fastest_clear_as_pace_reference() {
+        let repository = test_repository();
+
+        let insert = |duration: i64, cleared: bool| {
+            insert_encounter(&repository, 0, |args| {
+                args.raid_clear = cleared;
+                args.encounter.last_combat_packet = args.encounter.fight_start + duration;
+            })
+            .0
+        };
+        insert(60_000, false);
+        let fastest = insert(600_000, true);
+        insert(700_000, true);
+
+        let reference = repository
+            .get_fastest_clear("Mordum, the Abyssal Punisher", "Hard")
+            .unwrap()
+            .unwrap();
+        assert_eq!(reference.encounter_id, fastest);
+        assert_eq!(reference.duration, 600_000);
+        assert!(!reference.hp_log.is_empty());
+        assert!(
+            repository
+                .get_fastest_clear("Mordum, the Abyssal Punisher", "Nightmare")
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn groups_consecutive_pulls_into_raid_runs() {
+        let repository = test_repository();
+
+        let insert = |offset: i64, cleared: bool| {
+            insert_encounter(&repository, offset, |args| args.raid_clear = cleared).0
+        };
+        insert(0, false);
+        let clear = insert(20 * 60_000, true);
+        // long after the clear and the same gate again
+        insert(3 * 60 * 60_000, true);
+
+        let statistics = repository
+            .get_raid_runs(RaidRunCriteria {
+                range: "all".to_string(),
+                raid: "Act 3: Mordum".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+
+        assert_eq!(statistics.runs.len(), 2);
+        let run = &statistics.runs[0];
+        assert_eq!(run.pulls, 2);
+        assert!(!run.full_clear);
+        assert_eq!(run.gates.len(), 1);
+        assert_eq!(run.gates[0].gate, "Act 3: Mordum G3");
+        assert_eq!(run.gates[0].encounter_id, clear);
+        assert_eq!(run.idle_time, 5 * 60_000);
+        assert_eq!(statistics.full_clears, 0);
+
+        let other_raid = repository
+            .get_raid_runs(RaidRunCriteria {
+                range: "all".to_string(),
+                raid: "Aegir".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(other_raid.runs.is_empty());
+    }
+
+    #[test]
+    fn tracks_gates_cleared_since_the_weekly_reset() {
+        let repository = test_repository();
+
+        let insert = |offset: i64, cleared: bool| {
+            insert_encounter(&repository, offset, |args| args.raid_clear = cleared)
+        };
+        // last week
+        insert(-8 * 24 * 60 * 60_000, true);
+        insert(0, false);
+        let (clear, generated_name) = insert(1000, true);
+        // local characters skip names with digits
+        let name = "Alpha".to_string();
+        let connection = repository.0.get().unwrap();
+        connection
+            .execute(
+                "UPDATE entity SET name = ?1 WHERE name = ?2",
+                params![name, generated_name],
+            )
+            .unwrap();
+        connection
+            .execute(
+                "UPDATE encounter_preview SET local_player = ?1 WHERE local_player = ?2",
+                params![name, generated_name],
+            )
+            .unwrap();
+        drop(connection);
+
+        let tracker = repository.get_weekly_tracker().unwrap();
+
+        let character = tracker
+            .iter()
+            .find(|character| character.character.name == name)
+            .unwrap();
+        assert_eq!(character.region.as_deref(), Some("EUC"));
+        assert_eq!(
+            character.next_reset - character.week_start,
+            7 * 24 * 60 * 60_000
         );
-        assert_eq!(actual_encounter.duration, expected_encounter.duration);
-        assert_eq!(actual_encounter.difficulty, expected_encounter.difficulty);
-        assert_eq!(actual_encounter.cleared, expected_encounter.cleared);
+        assert_eq!(character.raids.len(), 1);
+        assert_eq!(character.raids[0].raid, "Act 3: Mordum");
+        assert_eq!(character.raids[0].gate_count, 3);
         assert_eq!(
-            actual_encounter.boss_only_damage,
-            expected_encounter.boss_only_damage
+            character.raids[0].gates,
+            vec![WeeklyGateClear {
+                gate: "Act 3: Mordum G3".to_string(),
+                boss_name: "Mordum, the Abyssal Punisher".to_string(),
+                difficulty: "Hard".to_string(),
+                encounter_id: clear,
+                cleared_at: character.raids[0].gates[0].cleared_at,
+            }]
         );
+    }
 
-        assert!(actual_encounter.encounter_damage_stats.dps > 0);
-        assert_eq!(
-            actual_encounter.encounter_damage_stats.dps,
-            expected_encounter.encounter_damage_stats.dps
-        );
-        assert!(actual_encounter.encounter_damage_stats.top_damage_dealt > 0);
-        assert_eq!(
-            actual_encounter.encounter_damage_stats.top_damage_dealt,
-            expected_encounter.encounter_damage_stats.top_damage_dealt
+    #[test]
+    fn aggregates_shared_pulls_by_teammate_character_id() {
+        let repository = test_repository();
+
+        let insert = |offset: i64, cleared: bool| {
+            insert_encounter(&repository, offset, |args| args.raid_clear = cleared).0
+        };
+        let wipe = insert(0, false);
+        let clear = insert(60 * 60_000, true);
+
+        let connection = repository.0.get().unwrap();
+        connection
+            .execute(
+                "UPDATE entity SET name = 'Élise' WHERE name = 'Player2' AND encounter_id = ?",
+                [clear],
+            )
+            .unwrap();
+        let dps: i64 = connection
+            .query_row("SELECT dps FROM entity WHERE name = 'Élise'", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        drop(connection);
+
+        let teammates = repository
+            .get_teammates(TeammateCriteria {
+                range: "all".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(
+            teammates
+                .iter()
+                .all(|teammate| teammate.character_id != 101)
         );
+
+        let teammate = teammates
+            .iter()
+            .find(|teammate| teammate.character_id == 102)
+            .unwrap();
+        assert_eq!(teammate.name, "Élise");
         assert_eq!(
-            actual_encounter.encounter_damage_stats.top_damage_taken,
-            expected_encounter.encounter_damage_stats.top_damage_taken
+            teammate
+                .names
+                .iter()
+                .map(|name| name.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Player2", "Élise"]
         );
-        assert!(actual_encounter.encounter_damage_stats.total_damage_dealt > 0);
+        assert_eq!((teammate.shared_pulls, teammate.shared_clears), (2, 1));
+        assert_eq!(teammate.clear_rate, 0.5);
+        assert_eq!(teammate.bosses.len(), 1);
+        assert_eq!(teammate.bosses[0].pulls, 2);
+        assert_eq!(teammate.bosses[0].median_dps, Some(dps));
+
+        let searched = repository
+            .get_teammates(TeammateCriteria {
+                range: "all".to_string(),
+                search: "player2".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(searched.len(), 1);
+        assert_eq!(searched[0].shared_pulls, 2);
+        // case is folded beyond ascii
+        let searched = repository
+            .get_teammates(TeammateCriteria {
+                range: "all".to_string(),
+                search: "élise".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(searched.len(), 1);
+        assert_eq!(searched[0].character_id, 102);
+
+        let regulars = |min_pulls| {
+            repository
+                .get_teammates(TeammateCriteria {
+                    range: "all".to_string(),
+                    min_pulls,
+                    ..Default::default()
+                })
+                .unwrap()
+                .len()
+        };
+        assert_eq!(regulars(2), teammates.len());
+        assert_eq!(regulars(3), 0);
+
+        let details = repository.get_teammate_details(102).unwrap();
         assert_eq!(
-            actual_encounter.encounter_damage_stats.total_damage_dealt,
-            expected_encounter.encounter_damage_stats.total_damage_dealt
+            details.pulls.iter().map(|pull| pull.id).collect::<Vec<_>>(),
+            vec![clear, wipe]
         );
+        assert!(repository.get_teammate_details(101).is_err());
+    }
+
+    #[test]
+    fn resolves_renamed_local_characters_through_character_id() {
+        let repository = test_repository();
+
+        let insert =
+            |offset: i64| insert_encounter(&repository, offset, |args| args.raid_clear = true);
+        let (before, generated_name) = insert(0);
+        let (after, _) = insert(60 * 60_000);
+        // character ids are only unique within a region
+        let (elsewhere, _) = insert_encounter(&repository, 2 * 60 * 60_000, |args| {
+            args.raid_clear = true;
+            args.region = Some("NAE".to_string());
+        });
+
+        // local characters skip names with digits, rename before building the identities
+        let connection = repository.0.get().unwrap();
+        for (id, name) in [(before, "Alpha"), (after, "Beta"), (elsewhere, "Gamma")] {
+            connection
+                .execute(
+                    "UPDATE entity SET name = ?1 WHERE name = ?2 AND encounter_id = ?3",
+                    params![name, generated_name, id],
+                )
+                .unwrap();
+            connection
+                .execute(
+                    "UPDATE encounter_preview SET local_player = ?1, players = REPLACE(players, ?2, ?1)
+                    WHERE id = ?3",
+                    params![name, generated_name, id],
+                )
+                .unwrap();
+        }
+        connection
+            .execute("DELETE FROM character_identity", [])
+            .unwrap();
+        for id in [before, after, elsewhere] {
+            connection
+                .execute(UPSERT_CHARACTER_IDENTITIES, params![id])
+                .unwrap();
+        }
+        drop(connection);
+
+        let names = repository.get_character_names("ALPHA").unwrap();
         assert_eq!(
-            actual_encounter.encounter_damage_stats.total_damage_taken,
-            expected_encounter.encounter_damage_stats.total_damage_taken
+            names
+                .iter()
+                .map(|name| name.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Beta", "Alpha"]
         );
+        assert!(names.iter().all(|name| name.character_id == 101));
+
+        let mut characters = repository
+            .get_local_characters()
+            .unwrap()
+            .into_iter()
+            .map(|character| character.name)
+            .collect::<Vec<_>>();
+        characters.sort();
+        assert_eq!(characters, vec!["Beta", "Gamma"]);
+
+        let statistics = repository
+            .get_character_statistics(CharacterStatisticsCriteria {
+                character: CharacterInfo {
+                    name: "Alpha".to_string(),
+                    ..Default::default()
+                },
+                range: "all".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(statistics.summary.attempts, 2);
+
+        let search = |search: &str, local_player: &str| {
+            repository
+                .get_encounter_preview(GetEncounterPreviewArgs {
+                    page: 1,
+                    page_size: 10,
+                    search: search.to_string(),
+                    filter: SearchFilter {
+                        min_duration: 0,
+                        sort: "id".to_string(),
+                        order: "desc".to_string(),
+                        local_player: local_player.to_string(),
+                        ..Default::default()
+                    },
+                })
+                .unwrap()
+                .total_encounters
+        };
+        assert_eq!(search("", "Beta"), 2);
+        assert_eq!(search("", "alpha"), 2);
+        assert_eq!(search("Beta", ""), 2);
+    }
+
+    #[test]
+    fn aggregates_roster_statistics_across_local_characters() {
+        let repository = test_repository();
+
+        let insert = |offset: i64, cleared: bool| {
+            insert_encounter(&repository, offset, |args| args.raid_clear = cleared)
+        };
+        let (wipe, generated_name) = insert(0, false);
+        let (clear, _) = insert(30 * 60_000, true);
+        let (renamed_clear, _) = insert(60 * 60_000, true);
+
+        let connection = repository.0.get().unwrap();
+        for (id, name) in [(wipe, "Alpha"), (clear, "Alpha"), (renamed_clear, "Beta")] {
+            connection
+                .execute(
+                    "UPDATE entity SET name = ?1 WHERE name = ?2 AND encounter_id = ?3",
+                    params![name, generated_name, id],
+                )
+                .unwrap();
+            connection
+                .execute(
+                    "UPDATE encounter_preview SET local_player = ?1, players = REPLACE(players, ?2, ?1)
+                    WHERE id = ?3",
+                    params![name, generated_name, id],
+                )
+                .unwrap();
+        }
+        connection
+            .execute("DELETE FROM character_identity", [])
+            .unwrap();
+        for id in [wipe, clear, renamed_clear] {
+            connection
+                .execute(UPSERT_CHARACTER_IDENTITIES, params![id])
+                .unwrap();
+        }
+        drop(connection);
+
+        let boss_to_raid = HashMap::from([(
+            "Mordum, the Abyssal Punisher".to_string(),
+            "Act 3: Mordum".to_string(),
+        )]);
+        // local_players.json still lists the character under its old name
+        let statistics = repository
+            .get_roster_statistics(
+                vec![(101, "Alpha".to_string()), (999, "Gamma".to_string())],
+                RosterStatisticsCriteria {
+                    range: "all".to_string(),
+                    boss_to_raid,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        let characters = statistics
+            .characters
+            .iter()
+            .map(|character| {
+                (
+                    character.character.name.as_str(),
+                    character.summary.attempts,
+                    character.summary.clears,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(characters, vec![("Beta", 3, 2), ("Gamma", 0, 0)]);
+
+        assert_eq!(statistics.weeks.iter().map(|w| w.attempts).sum::<i32>(), 3);
+        assert_eq!(statistics.weeks.iter().map(|w| w.clears).sum::<i32>(), 2);
         assert_eq!(
-            actual_encounter
-                .encounter_damage_stats
-                .total_effective_shielding,
-            expected_encounter
-                .encounter_damage_stats
-                .total_effective_shielding
+            statistics.weeks.iter().map(|w| w.raid_time).sum::<i64>(),
+            3 * 15 * 60_000
         );
-        assert!(actual_encounter.encounter_damage_stats.boss_hp_log.len() > 0);
+        assert_eq!(statistics.specs.iter().map(|s| s.attempts).sum::<i32>(), 3);
+
+        assert_eq!(statistics.raids.len(), 1);
+        let raid = &statistics.raids[0];
+        assert_eq!(raid.raid, "Act 3: Mordum");
+        assert_eq!(raid.difficulty.as_deref(), Some("Hard"));
+        assert_eq!((raid.attempts, raid.clears), (3, 2));
+        assert_eq!(raid.characters.len(), 1);
+        assert_eq!(raid.characters[0].name, "Beta");
+        assert_eq!(raid.characters[0].clears, 2);
+        assert_eq!(raid.first_clear, raid.characters[0].first_clear);
+    }
 
-        let actual_misc = actual_encounter.encounter_damage_stats.misc.unwrap();
-        assert!(actual_misc.raid_clear.filter(|pr| *pr).is_some());
-        assert!(actual_misc.region.filter(|pr| !pr.is_empty()).is_some());
-        assert!(actual_misc.version.filter(|pr| !pr.is_empty()).is_some());
-        assert!(actual_misc.party_info.filter(|pr| !pr.is_empty()).is_some());
+    #[test]
+    fn compares_boss_performance_around_loadout_changes() {
+        let repository = test_repository();
+
+        let insert =
+            |offset: i64| insert_encounter(&repository, offset, |args| args.raid_clear = true);
+
+        let pulls = [
+            (Some("first"), 1700.0, 100, r#"{"evolution":[1]}"#),
+            (None, 0.0, 200, r#"{"evolution":[1]}"#),
+            (Some("second"), 1710.0, 300, r#"{"evolution":[1]}"#),
+            (Some("second"), 1710.0, 500, r#"{"evolution":[1]}"#),
+            // ark passive respec without a loadout hash
+            (None, 1710.0, 600, r#"{"evolution":[2]}"#),
+        ];
+        let inserted = (0..pulls.len())
+            .map(|index| insert(index as i64 * 30 * 60_000))
+            .collect::<Vec<_>>();
+        let name = inserted[0].1.clone();
+        let ids = inserted.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+
+        let connection = repository.0.get().unwrap();
+        for (id, (loadout, gear_score, dps, ark_passive)) in ids.iter().zip(pulls) {
+            connection
+                .execute(
+                    "UPDATE entity SET loadout_hash = ?1, gear_score = ?2, ark_passive_data = ?3
+                    WHERE encounter_id = ?4 AND name = ?5",
+                    params![loadout, gear_score, ark_passive, id, name],
+                )
+                .unwrap();
+            connection
+                .execute(
+                    "UPDATE encounter_preview SET my_dps = ?1, my_rdps = ?1 WHERE id = ?2",
+                    params![dps, id],
+                )
+                .unwrap();
+        }
+        drop(connection);
 
-        let mut actual: Vec<_> = actual_encounter.entities.values().collect();
-        actual.sort_by_key(|e| &e.name);
+        let progression = repository
+            .get_loadout_progression(LoadoutProgressionCriteria {
+                character: CharacterInfo {
+                    name,
+                    ..Default::default()
+                },
+                range: "all".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
 
-        let mut expected: Vec<_> = expected_encounter.entities.values().collect();
-        expected.sort_by_key(|e| &e.name);
+        assert_eq!(progression.timeline.len(), 5);
+        assert_eq!(progression.changes.len(), 2);
+        let respec = &progression.changes[1];
+        assert_eq!(respec.encounter_id, ids[4]);
+        assert!(respec.ark_passive_changed);
+        assert_eq!(respec.loadout_hash, respec.previous_loadout_hash);
+
+        let change = &progression.changes[0];
+        assert_eq!(change.encounter_id, ids[2]);
+        assert_eq!(change.previous_loadout_hash.as_deref(), Some("first"));
+        assert_eq!(change.gear_score - change.previous_gear_score, 10.0);
+        assert!(!change.ark_passive_changed);
+
+        assert_eq!(change.bosses.len(), 1);
+        let boss = &change.bosses[0];
+        assert_eq!(boss.boss_name, "Mordum, the Abyssal Punisher");
+        assert_eq!((boss.before.clears, boss.before.median_dps), (2, Some(150)));
+        assert_eq!((boss.after.clears, boss.after.median_dps), (2, Some(400)));
+        assert_eq!(boss.before.median_rdps, Some(150));
+        assert!((boss.dps_change.unwrap() - 166.666).abs() < 0.01);
+    }
 
-        for (actual, expected) in actual.iter().zip(expected.iter()) {
-            assert_eq!(actual.name, expected.name);
+    #[test]
+    fn builds_support_synergy_from_stored_contribution_splits() {
+        let repository = test_repository();
+
+        let (_, local_player) = insert_encounter(&repository, 0, |args| {
+            let local_player = args.encounter.local_player.clone();
+            args.contribution_splits = vec![ContributionSplit {
+                name: local_player.clone(),
+                damage_split_by_name: HashMap::from([
+                    (local_player, 50_000_000),
+                    ("Player4".to_string(), 900_000),
+                    ("Player8".to_string(), 1_800_000),
+                ]),
+                ..Default::default()
+            }];
+        });
 
-            if actual.entity_type == EntityType::Boss {
-                assert!(actual.damage_stats.damage_dealt > 0);
-                assert!(actual.damage_stats.damage_taken > 0);
-                continue;
-            }
+        let synergy = repository
+            .get_support_synergy(SupportSynergyCriteria {
+                range: "all".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
 
-            assert_eq!(actual.gear_score, expected.gear_score);
-            assert!(actual.spec.is_some());
-            assert_eq!(actual.spec, expected.spec);
-            assert!(actual.skill_stats.casts > 0);
-            assert!(actual.skill_stats.crits > 0);
-            assert!(actual.skill_stats.hits > 0);
+        assert_eq!(synergy.len(), 1);
+        assert_eq!(synergy[0].name, local_player);
+        assert_eq!(synergy[0].character_id, 101);
+        let supports = synergy[0]
+            .supports
+            .iter()
+            .map(|support| (support.character_id, support.average_rdps))
+            .collect::<Vec<_>>();
+        assert_eq!(supports, vec![(108, 2000), (104, 1000)]);
+    }
 
-            for (_, skill) in actual.skills.iter() {
-                assert!(skill.dps > 0);
-                assert!(skill.hits > 0);
-                assert!(skill.crits > 0);
-                assert!(skill.total_damage > 0);
-                assert!(skill.cast_log.len() > 0);
-            }
+    #[test]
+    fn stores_each_inspect_snapshot_once_and_diffs_them() {
+        let repository = test_repository();
 
-            assert!(actual.combat_power.filter(|pr| *pr > 1500.0).is_some());
-            assert!(actual.loadout_hash.is_some());
-            assert!(
-                actual
-                    .engraving_data
-                    .as_ref()
-                    .filter(|pr| pr.len() > 1)
-                    .is_some()
-            );
-            assert!(actual.ark_passive_active.unwrap());
-            assert!(
-                actual
-                    .ark_passive_data
-                    .as_ref()
-                    .filter(|pr| pr.enlightenment.is_some()
-                        && pr.leap.is_some()
-                        && pr.evolution.is_some())
-                    .is_some()
-            );
+        let insert = |offset: i64, snapshot: serde_json::Value| {
+            insert_encounter(&repository, offset, |args| {
+                let name = args.encounter.local_player.clone();
+                args.inspect_snapshots.insert(name, snapshot);
+            })
+        };
 
-            assert!(actual.damage_stats.unbuffed_damage > 0);
-            assert!(actual.damage_stats.unbuffed_dps > 0);
-            assert!(actual.damage_stats.damage_dealt > 0);
-            assert!(actual.damage_stats.hyper_awakening_damage > 0);
-            assert!(actual.damage_stats.dps > 0);
-            assert!(actual.damage_stats.dps_average.len() > 0);
-            assert!(actual.damage_stats.dps_rolling_10s_avg.len() > 0);
-            assert!(actual.damage_stats.damage_taken > 0);
-            assert!(actual.damage_stats.shields_given > 0);
-            assert!(!actual.damage_stats.shields_given_by.is_empty());
-            assert!(actual.damage_stats.shields_received > 0);
-            assert!(!actual.damage_stats.shields_received_by.is_empty());
-            assert!(actual.damage_stats.damage_absorbed > 0);
-            assert!(!actual.damage_stats.damage_absorbed_by.is_empty());
-            assert!(actual.damage_stats.damage_absorbed_on_others > 0);
-            assert!(!actual.damage_stats.damage_absorbed_on_others_by.is_empty());
-        }
+        let (first, name) = insert(0, json!({ "gearLevel": 1700.0, "cards": [{ "id": 1 }] }));
+        // same snapshot with its maps iterated in a different order
+        let (second, _) = insert(
+            60_000,
+            json!({ "cards": [{ "id": 1 }], "gearLevel": 1700.0 }),
+        );
+        let (third, _) = insert(
+            120_000,
+            json!({ "gearLevel": 1710.0, "cards": [{ "id": 1 }, { "id": 2 }] }),
+        );
 
-        let preview = paged.encounters.first().unwrap();
+        let stored: i64 = repository
+            .0
+            .get()
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM inspect_snapshot", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(stored, 2);
 
-        assert_eq!(preview.duration, expected_encounter.duration);
-        assert_eq!(preview.classes.len(), 8);
-        assert_eq!(preview.names.len(), 8);
-        assert_eq!(preview.fight_start, expected_encounter.fight_start);
-        assert_eq!(preview.difficulty, expected_encounter.difficulty);
-        assert_eq!(preview.boss_name, expected_encounter.current_boss_name);
-        assert_eq!(preview.cleared, true);
-        assert_eq!(preview.cleared, expected_encounter.cleared);
+        let unchanged = repository
+            .diff_inspect_snapshots(&name, first, second)
+            .unwrap();
+        assert!(unchanged.identical);
+        assert!(unchanged.changes.is_empty());
 
-        assert!(preview.support_ap.is_some());
-        assert!(preview.support_brand.is_some());
-        assert!(preview.support_identity.is_some());
-        assert!(preview.support_hyper.is_some());
+        let changed = repository
+            .diff_inspect_snapshots(&name, second, third)
+            .unwrap();
+        assert!(!changed.identical);
+        assert_eq!(
+            changed.changes,
+            vec![
+                InspectSnapshotChange {
+                    path: "cards[1]".to_string(),
+                    before: None,
+                    after: Some(json!({ "id": 2 })),
+                },
+                InspectSnapshotChange {
+                    path: "gearLevel".to_string(),
+                    before: Some(json!(1700.0)),
+                    after: Some(json!(1710.0)),
+                },
+            ]
+        );
 
-        {
-            let local_player_name = &expected_encounter.local_player;
-            let local_player = expected_encounter.entities.get(local_player_name).unwrap();
-            assert!(preview.my_dps > 0);
-            assert_eq!(preview.my_dps, local_player.damage_stats.dps);
-        }
+        assert!(
+            repository
+                .diff_inspect_snapshots("Nobody", first, third)
+                .is_err()
+        );
+
+        // the character is followed across a rename by its character id
+        repository
+            .0
+            .get()
+            .unwrap()
+            .execute(
+                "UPDATE entity SET name = 'Renamed' WHERE encounter_id = ? AND name = ?",
+                params![third, name],
+            )
+            .unwrap();
+        let renamed = repository
+            .diff_inspect_snapshots(&name, second, third)
+            .unwrap();
+        assert_eq!(renamed.changes, changed.changes);
+        assert!(
+            repository
+                .diff_inspect_snapshots(&name, third, second)
+                .is_err()
+        );
+    }
+
+    const VERSION: &str = "1.14.0";
+
+    fn test_repository() -> Repository {
+        preload_test_assets();
+        Database::memory(VERSION).unwrap().create_repository()
+    }
+
+    /// Stores `build_args` shifted by `offset` ms and changed by `edit`. Returns the encounter id
+    /// and its local player.
+    fn insert_encounter(
+        repository: &Repository,
+        offset: i64,
+        edit: impl FnOnce(&mut InsertEncounterArgs),
+    ) -> (i32, String) {
+        let mut args = build_args(VERSION);
+        args.encounter.fight_start += offset;
+        args.encounter.last_combat_packet += offset;
+        edit(&mut args);
+        let name = args.encounter.local_player.clone();
+        (repository.insert_data(args).unwrap() as i32, name)
     }
 
     fn build_args(version: &str) -> InsertEncounterArgs {
@@ -3863,6 +6621,24 @@ mod tests {
                 unknown_buffs: HashSet::new(),
                 misc: Some(misc.clone()),
                 boss_hp_log: boss_hp_logs.clone(),
+                stagger: Some(EncounterStagger {
+                    log: HashMap::from([(
+                        boss.name.clone(),
+                        vec![
+                            StaggerLog { time: 1, p: 0.5 },
+                            StaggerLog { time: 2, p: 0.0 },
+                        ],
+                    )]),
+                    checks: vec![StaggerCheck {
+                        boss: boss.name.clone(),
+                        start: 1_000,
+                        end: 2_000,
+                        max_stagger: 1_000,
+                        success: true,
+                        contributions: HashMap::from([("Player1".to_string(), 1_000)]),
+                    }],
+                }),
+                pace: None,
             };
 
             let encounter = Encounter {
@@ -3902,6 +6678,8 @@ mod tests {
                 intermission_start: None,
                 intermission_end: None,
                 contribution_splits: vec![],
+                inspect_snapshots: HashMap::new(),
+                timeline: Vec::new(),
             };
 
             insert_args
@@ -3934,6 +6712,7 @@ mod tests {
                 spec: None,
                 loadout_hash: None,
                 combat_power: None,
+                battle_items: Vec::new(),
             }
         }
     }
@@ -4197,7 +6976,8 @@ mod tests {
         for party in parties {
             for (idx, spec) in party.iter().enumerate() {
                 let name = format!("Player{}", player_names.len() + 1);
-                let entity = build_entity_from_spec(&name, spec, idx);
+                let mut entity = build_entity_from_spec(&name, spec, idx);
+                entity.character_id = player_names.len() as u64 + 101;
 
                 entities.insert(name.clone(), (spec.clone(), entity));
                 player_names.push(name);
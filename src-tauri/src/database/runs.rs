@@ -0,0 +1,207 @@
+use hashbrown::{HashMap, HashSet};
+
+use crate::models::{RaidRun, RaidRunGate, RaidRunStatistics};
+
+/// Longest break between two gates of the same run.
+const MAX_RUN_GAP_MS: i64 = 30 * 60 * 1000;
+
+/// A stored encounter with the raid and gate its boss belongs to.
+#[derive(Debug, Clone, Default)]
+pub struct RaidRunEncounter {
+    pub id: i32,
+    pub fight_start: i64,
+    pub duration: i64,
+    pub boss_name: String,
+    pub raid: String,
+    pub gate: String,
+    pub gate_index: usize,
+    pub raid_gates: usize,
+    pub difficulty: String,
+    pub cleared: bool,
+    pub local_player: String,
+    pub players: Vec<String>,
+}
+
+/// Links consecutive encounters of the same raid, local player and party into runs.
+/// `encounters` have to be ordered by fight start.
+pub fn build_raid_runs(encounters: Vec<RaidRunEncounter>) -> RaidRunStatistics {
+    let mut runs: Vec<(RaidRun, RaidRunEncounter)> = Vec::new();
+    let mut open: HashMap<String, usize> = HashMap::new();
+
+    for encounter in encounters {
+        let end = encounter.fight_start + encounter.duration;
+        let index = open
+            .get(&encounter.local_player)
+            .copied()
+            .filter(|&index| continues_run(&runs[index].0, &runs[index].1, &encounter));
+
+        let Some(index) = index else {
+            open.insert(encounter.local_player.clone(), runs.len());
+            runs.push((
+                RaidRun {
+                    raid: encounter.raid.clone(),
+                    difficulty: encounter.difficulty.clone(),
+                    local_player: encounter.local_player.clone(),
+                    start: encounter.fight_start,
+                    end,
+                    active_time: encounter.duration,
+                    pulls: 1,
+                    gates: vec![gate_result(&encounter)],
+                    ..Default::default()
+                },
+                encounter,
+            ));
+            continue;
+        };
+
+        let (run, last) = &mut runs[index];
+        run.end = run.end.max(end);
+        run.active_time += encounter.duration;
+        run.pulls += 1;
+        match run.gates.last_mut() {
+            Some(gate) if gate.gate == encounter.gate => {
+                gate.pulls += 1;
+                gate.active_time += encounter.duration;
+                gate.boss_name = encounter.boss_name.clone();
+                if !gate.cleared {
+                    gate.cleared = encounter.cleared;
+                    gate.encounter_id = encounter.id;
+                }
+            }
+            _ => run.gates.push(gate_result(&encounter)),
+        }
+        *last = encounter;
+    }
+
+    let runs = runs
+        .into_iter()
+        .map(|(mut run, last)| {
+            run.idle_time = (run.end - run.start - run.active_time).max(0);
+            run.full_clear = last.raid_gates > 0
+                && run.gates.iter().filter(|gate| gate.cleared).count() == last.raid_gates;
+            run
+        })
+        .collect::<Vec<_>>();
+
+    let full_clear_times = runs
+        .iter()
+        .filter(|run| run.full_clear)
+        .map(|run| run.end - run.start)
+        .collect::<Vec<_>>();
+    let average = |values: &[i64]| {
+        (!values.is_empty()).then(|| values.iter().sum::<i64>() / values.len() as i64)
+    };
+
+    RaidRunStatistics {
+        full_clears: full_clear_times.len() as i32,
+        average_full_clear_time: average(&full_clear_times),
+        fastest_full_clear_time: full_clear_times.iter().min().copied(),
+        average_active_time: average(&runs.iter().map(|run| run.active_time).collect::<Vec<_>>()),
+        average_idle_time: average(&runs.iter().map(|run| run.idle_time).collect::<Vec<_>>()),
+        runs,
+    }
+}
+
+fn continues_run(run: &RaidRun, last: &RaidRunEncounter, encounter: &RaidRunEncounter) -> bool {
+    let last_gate_cleared = run.gates.last().is_some_and(|gate| gate.cleared);
+    let moves_forward = encounter.gate_index > last.gate_index
+        || (encounter.gate_index == last.gate_index && !last_gate_cleared);
+
+    encounter.raid == run.raid
+        && encounter.difficulty == run.difficulty
+        && moves_forward
+        && encounter.fight_start - run.end <= MAX_RUN_GAP_MS
+        && same_party(&last.players, &encounter.players)
+}
+
+// at least half of the smaller party stayed, unknown parties always match
+fn same_party(before: &[String], after: &[String]) -> bool {
+    if before.is_empty() || after.is_empty() {
+        return true;
+    }
+
+    let before = before.iter().collect::<HashSet<_>>();
+    let shared = after.iter().filter(|name| before.contains(name)).count();
+    shared * 2 >= before.len().min(after.len())
+}
+
+fn gate_result(encounter: &RaidRunEncounter) -> RaidRunGate {
+    RaidRunGate {
+        gate: encounter.gate.clone(),
+        boss_name: encounter.boss_name.clone(),
+        pulls: 1,
+        cleared: encounter.cleared,
+        active_time: encounter.duration,
+        encounter_id: encounter.id,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encounter(id: i32, fight_start: i64, gate_index: usize, cleared: bool) -> RaidRunEncounter {
+        RaidRunEncounter {
+            id,
+            fight_start,
+            duration: 60_000,
+            boss_name: format!("Boss {gate_index}"),
+            raid: "Aegir".to_string(),
+            gate: format!("Aegir G{}", gate_index + 1),
+            gate_index,
+            raid_gates: 2,
+            difficulty: "Hard".to_string(),
+            cleared,
+            local_player: "Alpha".to_string(),
+            players: vec!["Alpha".to_string(), "Beta".to_string()],
+        }
+    }
+
+    #[test]
+    fn links_gates_into_runs_with_idle_time() {
+        let minute = 60_000;
+        let statistics = build_raid_runs(vec![
+            encounter(1, 0, 0, false),
+            encounter(2, 2 * minute, 0, true),
+            encounter(3, 5 * minute, 1, true),
+            // back to the first gate, a new run
+            encounter(4, 10 * minute, 0, true),
+            // too long after the previous gate
+            encounter(5, 60 * minute, 1, false),
+        ]);
+
+        assert_eq!(statistics.runs.len(), 3);
+        let run = &statistics.runs[0];
+        assert!(run.full_clear);
+        assert_eq!(run.pulls, 3);
+        assert_eq!((run.start, run.end), (0, 6 * minute));
+        assert_eq!(run.active_time, 3 * minute);
+        assert_eq!(run.idle_time, 3 * minute);
+        assert_eq!(run.gates.len(), 2);
+        assert_eq!(
+            (
+                run.gates[0].pulls,
+                run.gates[0].cleared,
+                run.gates[0].encounter_id
+            ),
+            (2, true, 2)
+        );
+        assert!(!statistics.runs[1].full_clear);
+        assert_eq!(statistics.full_clears, 1);
+        assert_eq!(statistics.fastest_full_clear_time, Some(6 * minute));
+    }
+
+    #[test]
+    fn splits_runs_when_the_party_changes() {
+        let mut next = encounter(2, 5 * 60_000, 1, true);
+        next.players = vec![
+            "Alpha".to_string(),
+            "Gamma".to_string(),
+            "Delta".to_string(),
+        ];
+
+        let statistics = build_raid_runs(vec![encounter(1, 0, 0, true), next]);
+
+        assert_eq!(statistics.runs.len(), 2);
+    }
+}
@@ -0,0 +1,222 @@
+use crate::models::{
+    BestPhaseSplit, BossHpLog, PhaseSplit, PhaseSplitDefinition, PullPhaseSplit, PullPhaseSplits,
+    TimelineEvent, TimelineEventKind,
+};
+
+/// Splits a pull into `phases`, each phase ends at the first of its conditions reached after
+/// the previous one. Phases without conditions end at `kill`, stops at the first unfinished phase.
+pub fn phase_splits(
+    phases: &[PhaseSplitDefinition],
+    hp_log: &[BossHpLog],
+    hp_bars: Option<u32>,
+    timeline: &[TimelineEvent],
+    kill: Option<i64>,
+) -> Vec<PhaseSplit> {
+    let mut splits = Vec::with_capacity(phases.len());
+    let mut start = 0;
+
+    for phase in phases {
+        let threshold = match (phase.bars, hp_bars) {
+            (Some(bars), Some(total)) if total > 0 => {
+                let bars_percent = bars as f32 / total as f32 * 100.0;
+                Some(phase.percent.map_or(bars_percent, |p| p.max(bars_percent)))
+            }
+            _ => phase.percent,
+        };
+        let hp_end = threshold.and_then(|threshold| {
+            hp_log
+                .iter()
+                .map(|log| (log.time as i64 * 1000, log.p * 100.0))
+                .find(|(time, percent)| *time >= start && *percent <= threshold)
+                .map(|(time, _)| time)
+        });
+        let event_end = |kind: TimelineEventKind, value: Option<i64>| {
+            value.and_then(|value| {
+                timeline
+                    .iter()
+                    .find(|event| {
+                        event.kind == kind && event.value == Some(value) && event.time >= start
+                    })
+                    .map(|event| event.time)
+            })
+        };
+        let trigger_end = event_end(TimelineEventKind::Trigger, phase.trigger);
+        let phase_end = event_end(TimelineEventKind::PhaseTransition, phase.phase);
+        let has_condition = phase.percent.is_some()
+            || phase.bars.is_some()
+            || phase.trigger.is_some()
+            || phase.phase.is_some();
+
+        let end = [hp_end, trigger_end, phase_end].into_iter().flatten().min();
+        let Some(end) = end.or(kill.filter(|_| !has_condition)) else {
+            break;
+        };
+
+        start = end.max(start);
+        splits.push(PhaseSplit {
+            name: phase.name.clone(),
+            end: start,
+        });
+    }
+
+    splits
+}
+
+/// Shortest duration of every phase across `clears`, in the order the phases first appear.
+pub fn best_splits(clears: &[(i32, Vec<PhaseSplit>)]) -> Vec<BestPhaseSplit> {
+    let mut best: Vec<BestPhaseSplit> = Vec::new();
+    for (encounter_id, splits) in clears {
+        for (name, duration) in split_durations(splits) {
+            match best.iter_mut().find(|phase| phase.name == name) {
+                Some(phase) if phase.duration <= duration => {}
+                Some(phase) => {
+                    phase.duration = duration;
+                    phase.encounter_id = *encounter_id;
+                }
+                None => best.push(BestPhaseSplit {
+                    name: name.to_string(),
+                    duration,
+                    encounter_id: *encounter_id,
+                }),
+            }
+        }
+    }
+
+    best
+}
+
+pub fn compare_splits(id: i32, splits: &[PhaseSplit], best: &[BestPhaseSplit]) -> PullPhaseSplits {
+    let phases = splits
+        .iter()
+        .zip(split_durations(splits))
+        .map(|(split, (name, duration))| {
+            let best = best
+                .iter()
+                .find(|phase| phase.name == name)
+                .map(|phase| phase.duration);
+            PullPhaseSplit {
+                name: name.to_string(),
+                end: split.end,
+                duration,
+                best,
+                delta: best.map(|best| duration - best),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    PullPhaseSplits {
+        id,
+        time_lost: phases.iter().filter_map(|phase| phase.delta).sum(),
+        phases,
+    }
+}
+
+fn split_durations(splits: &[PhaseSplit]) -> impl Iterator<Item = (&str, i64)> {
+    splits.iter().scan(0, |start, split| {
+        let duration = split.end - *start;
+        *start = split.end;
+        Some((split.name.as_str(), duration))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn phase(
+        name: &str,
+        percent: Option<f32>,
+        bars: Option<u32>,
+        trigger: Option<i64>,
+        phase: Option<i64>,
+    ) -> PhaseSplitDefinition {
+        PhaseSplitDefinition {
+            name: name.to_string(),
+            percent,
+            bars,
+            trigger,
+            phase,
+        }
+    }
+
+    fn split(name: &str, end: i64) -> PhaseSplit {
+        PhaseSplit {
+            name: name.to_string(),
+            end,
+        }
+    }
+
+    #[test]
+    fn splits_pull_by_phase_transitions_bars_triggers_and_kill() {
+        let phases = [
+            phase("First Boss", None, None, None, Some(1)),
+            phase("Bars", None, Some(50), None, None),
+            phase("Trigger", None, None, Some(12), None),
+            phase("Half", Some(25.0), None, None, None),
+            phase("Kill", None, None, None, None),
+        ];
+        let hp_log = [
+            BossHpLog::new(5, 800, 0.8),
+            BossHpLog::new(10, 500, 0.5),
+            BossHpLog::new(20, 200, 0.2),
+        ];
+        let timeline = [
+            TimelineEvent {
+                time: 4_000,
+                kind: TimelineEventKind::PhaseTransition,
+                entity: None,
+                value: Some(1),
+            },
+            TimelineEvent {
+                time: 15_000,
+                kind: TimelineEventKind::Trigger,
+                entity: None,
+                value: Some(12),
+            },
+        ];
+
+        assert_eq!(
+            phase_splits(&phases, &hp_log, Some(100), &timeline, Some(30_000)),
+            vec![
+                split("First Boss", 4_000),
+                split("Bars", 10_000),
+                split("Trigger", 15_000),
+                split("Half", 20_000),
+                split("Kill", 30_000)
+            ]
+        );
+        assert_eq!(
+            phase_splits(&phases[1..], &hp_log, Some(100), &[], None),
+            vec![split("Bars", 10_000)]
+        );
+        assert_eq!(phase_splits(&phases, &hp_log, Some(100), &[], None), vec![]);
+    }
+
+    #[test]
+    fn compares_pull_against_best_splits() {
+        let clears = [
+            (1, vec![split("P1", 10_000), split("Kill", 30_000)]),
+            (2, vec![split("P1", 12_000), split("Kill", 25_000)]),
+        ];
+        let best = best_splits(&clears);
+        assert_eq!(
+            best,
+            vec![
+                BestPhaseSplit {
+                    name: "P1".to_string(),
+                    duration: 10_000,
+                    encounter_id: 1
+                },
+                BestPhaseSplit {
+                    name: "Kill".to_string(),
+                    duration: 13_000,
+                    encounter_id: 2
+                }
+            ]
+        );
+
+        let pull = compare_splits(3, &[split("P1", 15_000)], &best);
+        assert_eq!(pull.phases[0].delta, Some(5_000));
+        assert_eq!(pull.time_lost, 5_000);
+    }
+}
@@ -0,0 +1,292 @@
+use hashbrown::HashMap;
+use serde::Deserialize;
+
+use crate::models::{ContributionSplit, SupportPairing, SupportSynergy};
+use crate::utils::{is_support_class, is_support_spec};
+
+/// A player of a stored encounter.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SynergyPlayer {
+    pub name: String,
+    pub character_id: u64,
+    pub class_id: u32,
+    pub spec: Option<String>,
+}
+
+impl SynergyPlayer {
+    fn is_support(&self) -> bool {
+        match &self.spec {
+            Some(spec) => is_support_spec(spec),
+            None => is_support_class(&self.class_id),
+        }
+    }
+
+    /// Character id, or the name for players stored without one.
+    fn key(&self) -> (u64, String) {
+        if self.character_id > 0 {
+            (self.character_id, String::new())
+        } else {
+            (0, self.name.clone())
+        }
+    }
+}
+
+/// A stored encounter with its players and rdps contribution breakdown.
+#[derive(Debug, Clone, Default)]
+pub struct SynergyEncounter {
+    pub fight_start: i64,
+    pub duration: i64,
+    pub cleared: bool,
+    pub local_player: String,
+    pub players: Vec<SynergyPlayer>,
+    pub contribution_splits: Vec<ContributionSplit>,
+}
+
+#[derive(Default)]
+struct PairingTotals {
+    pairing: SupportPairing,
+    rdps_sum: f64,
+}
+
+/// Damage the local dps characters received from every support they played with, as
+/// rdps over the whole fight. Supports are told apart by character and spec, pulls where a
+/// support of the same party gave nothing count towards its average.
+pub fn build_support_synergy(mut encounters: Vec<SynergyEncounter>) -> Vec<SupportSynergy> {
+    encounters.sort_by_key(|encounter| encounter.fight_start);
+    type Pairings = HashMap<((u64, String), Option<String>), PairingTotals>;
+    let mut characters: HashMap<(u64, String), (SupportSynergy, Pairings)> = HashMap::new();
+
+    for encounter in encounters {
+        let seconds = encounter.duration as f64 / 1000.0;
+        let Some(local) = encounter
+            .players
+            .iter()
+            .find(|player| player.name == encounter.local_player)
+        else {
+            continue;
+        };
+        let Some(split) = encounter
+            .contribution_splits
+            .iter()
+            .find(|split| split.name == encounter.local_player)
+        else {
+            continue;
+        };
+        if seconds <= 0.0 || local.is_support() {
+            continue;
+        }
+
+        let (character, pairings) = characters.entry(local.key()).or_default();
+        character.character_id = local.character_id;
+        character.name = local.name.clone();
+        character.class_id = local.class_id;
+        character.spec = local.spec.clone();
+        character.pulls += 1;
+
+        for support in encounter
+            .players
+            .iter()
+            .filter(|player| player.name != encounter.local_player && player.is_support())
+        {
+            let damage = split
+                .damage_split_by_name
+                .get(&support.name)
+                .copied()
+                .or_else(|| {
+                    split
+                        .damage_increase_by_entity_skill_group
+                        .get(&support.name)
+                        .map(|groups| groups.values().sum())
+                });
+            let support_party = encounter
+                .contribution_splits
+                .iter()
+                .find(|split| split.name == support.name)
+                .and_then(|split| split.party_number);
+            // without party numbers only supports that show up in the split are in the party
+            let same_party = match (split.party_number, support_party) {
+                (Some(party), Some(support_party)) => party == support_party,
+                _ => damage.is_some(),
+            };
+            if !same_party {
+                continue;
+            }
+            let damage = damage.unwrap_or_default().max(0);
+
+            let rdps = damage as f64 / seconds;
+            let totals = pairings
+                .entry((support.key(), support.spec.clone()))
+                .or_default();
+            totals.rdps_sum += rdps;
+            let pairing = &mut totals.pairing;
+            pairing.character_id = support.character_id;
+            pairing.name = support.name.clone();
+            pairing.class_id = support.class_id;
+            pairing.spec = support.spec.clone();
+            pairing.pulls += 1;
+            pairing.clears += encounter.cleared as i32;
+            pairing.best_rdps = pairing.best_rdps.max(rdps as i64);
+            pairing.total_damage += damage;
+            pairing.last_seen = encounter.fight_start;
+        }
+    }
+
+    let mut synergy = characters
+        .into_values()
+        .map(|(mut character, pairings)| {
+            character.supports = pairings
+                .into_values()
+                .map(|totals| SupportPairing {
+                    average_rdps: (totals.rdps_sum / totals.pairing.pulls as f64) as i64,
+                    ..totals.pairing
+                })
+                .collect();
+            character.supports.sort_by(|a, b| {
+                b.average_rdps
+                    .cmp(&a.average_rdps)
+                    .then(a.name.cmp(&b.name))
+            });
+            character
+        })
+        .collect::<Vec<_>>();
+    synergy.sort_by(|a, b| b.pulls.cmp(&a.pulls).then(a.name.cmp(&b.name)));
+
+    synergy
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player(name: &str, character_id: u64, class_id: u32, spec: &str) -> SynergyPlayer {
+        SynergyPlayer {
+            name: name.to_string(),
+            character_id,
+            class_id,
+            spec: Some(spec.to_string()),
+        }
+    }
+
+    fn encounter(id: i32, players: Vec<SynergyPlayer>, split: &[(&str, i64)]) -> SynergyEncounter {
+        SynergyEncounter {
+            fight_start: id as i64 * 1000,
+            duration: 100_000,
+            cleared: true,
+            local_player: "Alpha".to_string(),
+            players,
+            contribution_splits: vec![ContributionSplit {
+                name: "Alpha".to_string(),
+                damage_split_by_name: split
+                    .iter()
+                    .map(|(name, damage)| (name.to_string(), *damage))
+                    .collect(),
+                ..Default::default()
+            }],
+        }
+    }
+
+    #[test]
+    fn averages_rdps_received_per_support_character_and_spec() {
+        let alpha = player("Alpha", 1, 102, "Mayhem");
+        let bard = player("Bard", 2, 204, "Desperate Salvation");
+        let renamed_bard = player("Harp", 2, 204, "Desperate Salvation");
+        let paladin = player("Pally", 3, 105, "Blessed Aura");
+        let encounters = vec![
+            encounter(
+                1,
+                vec![alpha.clone(), bard, paladin.clone()],
+                &[("Alpha", 5_000_000), ("Bard", 100_000), ("Pally", 50_000)],
+            ),
+            encounter(
+                2,
+                vec![alpha.clone(), renamed_bard],
+                &[("Alpha", 5_000_000), ("Harp", 300_000)],
+            ),
+            // supports in the other party do not contribute
+            encounter(3, vec![alpha, paladin], &[("Alpha", 5_000_000)]),
+        ];
+
+        let synergy = build_support_synergy(encounters);
+
+        assert_eq!(synergy.len(), 1);
+        assert_eq!(synergy[0].name, "Alpha");
+        assert_eq!(synergy[0].pulls, 3);
+        let supports = synergy[0]
+            .supports
+            .iter()
+            .map(|support| {
+                (
+                    support.name.as_str(),
+                    support.pulls,
+                    support.average_rdps,
+                    support.best_rdps,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            supports,
+            vec![("Harp", 2, 2000, 3000), ("Pally", 1, 500, 500)]
+        );
+    }
+
+    #[test]
+    fn counts_pulls_without_damage_from_supports_in_the_party() {
+        let party_split = |name: &str| ContributionSplit {
+            name: name.to_string(),
+            party_number: Some(1),
+            ..Default::default()
+        };
+        let players = vec![
+            player("Alpha", 1, 102, "Mayhem"),
+            player("Bard", 2, 204, "Desperate Salvation"),
+        ];
+        let mut pulls = vec![
+            encounter(
+                1,
+                players.clone(),
+                &[("Alpha", 5_000_000), ("Bard", 100_000)],
+            ),
+            encounter(2, players, &[("Alpha", 5_000_000)]),
+        ];
+        for pull in pulls.iter_mut() {
+            pull.contribution_splits[0].party_number = Some(1);
+            pull.contribution_splits.push(party_split("Bard"));
+        }
+
+        let synergy = build_support_synergy(pulls);
+
+        let bard = &synergy[0].supports[0];
+        assert_eq!(bard.pulls, 2);
+        assert_eq!(bard.average_rdps, 500);
+        assert_eq!(bard.best_rdps, 1000);
+        assert_eq!(bard.last_seen, 2000);
+    }
+
+    #[test]
+    fn falls_back_to_skill_group_damage_increase() {
+        let mut pull = encounter(
+            1,
+            vec![
+                player("Alpha", 1, 102, "Mayhem"),
+                player("Bard", 2, 204, "Desperate Salvation"),
+            ],
+            &[("Alpha", 5_000_000)],
+        );
+        pull.contribution_splits[0]
+            .damage_increase_by_entity_skill_group
+            .insert(
+                "Bard".to_string(),
+                HashMap::from([
+                    ("Sonic Vibration".to_string(), 60_000),
+                    ("Heavenly Tune".to_string(), 40_000),
+                ]),
+            );
+
+        let synergy = build_support_synergy(vec![pull]);
+
+        assert_eq!(synergy[0].supports.len(), 1);
+        assert_eq!(synergy[0].supports[0].average_rdps, 1000);
+        assert_eq!(synergy[0].supports[0].total_damage, 100_000);
+    }
+}
@@ -4,6 +4,8 @@ use flate2::write::GzEncoder;
 use hashbrown::HashMap;
 use semver::Version;
 use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
 use std::cmp::{Ordering, max};
 use std::collections::BTreeMap;
 use std::io::Write;
@@ -11,6 +13,7 @@ use std::str::FromStr;
 
 use crate::constants::{WINDOW_MS, WINDOW_S};
 use crate::data::{ENGRAVING_DATA, GEM_SKILL_MAP};
+use crate::database::queries::LOCAL_PLAYER_IDENTITY_FILTER;
 use crate::database::sql_types::{CompressedJson, JsonColumn};
 use crate::models::*;
 use crate::utils::*;
@@ -38,16 +41,28 @@ pub fn build_sync_candidates_query(force_resync: bool) -> String {
     )
 }
 
+/// `aliases` are the other names of the characters named in `search`, matched alongside them.
 pub fn prepare_get_encounter_preview_query(
     search: String,
     filter: SearchFilter,
+    aliases: &HashMap<String, Vec<String>>,
 ) -> (Vec<String>, String, String) {
     let mut params = vec![];
 
     let join_clause = if search.len() > 2 {
         let escaped_search = search
             .split_whitespace()
-            .map(|word| format!("\"{}\"", word.replace("\"", "")))
+            .map(|word| match aliases.get(word) {
+                Some(names) if !names.is_empty() => format!(
+                    "({})",
+                    std::iter::once(word)
+                        .chain(names.iter().map(String::as_str))
+                        .map(|name| format!("\"{}\"", name.replace("\"", "")))
+                        .collect::<Vec<_>>()
+                        .join(" OR ")
+                ),
+                _ => format!("\"{}\"", word.replace("\"", "")),
+            })
             .collect::<Vec<_>>()
             .join(" ");
         params.push(escaped_search);
@@ -98,11 +113,13 @@ pub fn prepare_get_encounter_preview_query(
         ""
     };
 
+    // renamed characters keep showing their encounters from before the rename
     let local_player_filter = if !filter.local_player.is_empty() {
+        params.push(filter.local_player.clone());
         params.push(filter.local_player);
-        "AND e.local_player = ?"
+        format!("AND {LOCAL_PLAYER_IDENTITY_FILTER}")
     } else {
-        ""
+        String::new()
     };
 
     let query = format!(
@@ -195,6 +212,8 @@ fn stats_for_1_13_5_and_up(
 ) -> rusqlite::Result<EncounterDamageStats> {
     let CompressedJson(boss_hp_log): CompressedJson<HashMap<String, Vec<BossHpLog>>> =
         row.get("boss_hp_log")?;
+    let CompressedJson(stagger): CompressedJson<Option<EncounterStagger>> =
+        row.get("stagger_log")?;
     let CompressedJson(buffs): CompressedJson<HashMap<u32, StatusEffect>> = row.get("buffs")?;
     let CompressedJson(debuffs): CompressedJson<HashMap<u32, StatusEffect>> = row.get("debuffs")?;
     let CompressedJson(applied_shield_buffs): CompressedJson<HashMap<u32, StatusEffect>> =
@@ -216,6 +235,7 @@ fn stats_for_1_13_5_and_up(
         total_effective_shielding,
         applied_shield_buffs,
         boss_hp_log,
+        stagger,
         ..Default::default()
     })
 }
@@ -304,6 +324,7 @@ pub fn map_entity(row: &rusqlite::Row, version: &Version) -> rusqlite::Result<En
     let ark_passive_active: Option<bool> = row.get("ark_passive_active").unwrap_or_default();
     let JsonColumn(ark_passive_data): JsonColumn<Option<ArkPassiveData>> =
         row.get("ark_passive_data")?;
+    let JsonColumn(battle_items): JsonColumn<Vec<BattleItemUse>> = row.get("battle_items")?;
 
     let entity = EncounterEntity {
         name: row.get("name")?,
@@ -326,6 +347,7 @@ pub fn map_entity(row: &rusqlite::Row, version: &Version) -> rusqlite::Result<En
         ark_passive_data,
         loadout_hash: row.get("loadout_hash").unwrap_or_default(),
         combat_power: row.get("combat_power").unwrap_or_default(),
+        battle_items,
         ..Default::default()
     };
 
@@ -714,3 +736,77 @@ where
     let data = encoder.finish()?;
     Ok(data)
 }
+
+/// Copy of `value` with every object's keys sorted. Snapshots are built from hash maps, so this
+/// is what makes equal snapshots serialize to the same bytes.
+pub fn canonical_json(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key.clone(), canonical_json(value)))
+                    .collect(),
+            )
+        }
+        Value::Array(values) => Value::Array(values.iter().map(canonical_json).collect()),
+        _ => value.clone(),
+    }
+}
+
+/// Content hash of a snapshot passed through [`canonical_json`].
+pub fn inspect_snapshot_hash(snapshot: &Value) -> String {
+    format!("{:x}", Sha256::digest(snapshot.to_string()))
+}
+
+/// Every leaf that differs between `before` and `after`. Arrays are compared by index, so an
+/// item added in the middle shows up as changes to all later entries.
+pub fn diff_json(before: &Value, after: &Value) -> Vec<InspectSnapshotChange> {
+    let mut changes = Vec::new();
+    diff_json_at("", Some(before), Some(after), &mut changes);
+    changes
+}
+
+fn diff_json_at(
+    path: &str,
+    before: Option<&Value>,
+    after: Option<&Value>,
+    changes: &mut Vec<InspectSnapshotChange>,
+) {
+    match (before, after) {
+        (Some(Value::Object(a)), Some(Value::Object(b))) => {
+            for (key, value) in a {
+                diff_json_at(&join_path(path, key), Some(value), b.get(key), changes);
+            }
+            for (key, value) in b.iter().filter(|(key, _)| !a.contains_key(*key)) {
+                diff_json_at(&join_path(path, key), None, Some(value), changes);
+            }
+        }
+        (Some(Value::Array(a)), Some(Value::Array(b))) => {
+            for index in 0..max(a.len(), b.len()) {
+                diff_json_at(
+                    &format!("{path}[{index}]"),
+                    a.get(index),
+                    b.get(index),
+                    changes,
+                );
+            }
+        }
+        (a, b) if a != b => changes.push(InspectSnapshotChange {
+            path: path.to_string(),
+            before: a.cloned(),
+            after: b.cloned(),
+        }),
+        _ => {}
+    }
+}
+
+fn join_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{path}.{key}")
+    }
+}
@@ -0,0 +1,128 @@
+use crate::models::{WeeklyGateClear, WeeklyRaid};
+
+/// A cleared encounter with the raid and gate its boss belongs to.
+#[derive(Debug, Clone, Default)]
+pub struct WeeklyClear {
+    pub id: i32,
+    pub fight_start: i64,
+    pub boss_name: String,
+    pub difficulty: String,
+    pub local_player: String,
+    pub raid: String,
+    pub gate: String,
+    pub gate_index: usize,
+    pub gate_count: usize,
+}
+
+/// Gates cleared per raid, keeping the first clear of every gate and difficulty.
+/// `clears` have to be ordered by fight start.
+pub fn build_weekly_raids<'a>(
+    clears: impl IntoIterator<Item = &'a WeeklyClear>,
+) -> Vec<WeeklyRaid> {
+    let mut raids: Vec<(WeeklyRaid, Vec<usize>)> = Vec::new();
+
+    for clear in clears {
+        let index = match raids.iter().position(|(raid, _)| raid.raid == clear.raid) {
+            Some(index) => index,
+            None => {
+                raids.push((
+                    WeeklyRaid {
+                        raid: clear.raid.clone(),
+                        gate_count: clear.gate_count as i32,
+                        gates: Vec::new(),
+                    },
+                    Vec::new(),
+                ));
+                raids.len() - 1
+            }
+        };
+
+        let (raid, gate_indices) = &mut raids[index];
+        if raid
+            .gates
+            .iter()
+            .any(|gate| gate.gate == clear.gate && gate.difficulty == clear.difficulty)
+        {
+            continue;
+        }
+
+        raid.gates.push(WeeklyGateClear {
+            gate: clear.gate.clone(),
+            boss_name: clear.boss_name.clone(),
+            difficulty: clear.difficulty.clone(),
+            encounter_id: clear.id,
+            cleared_at: clear.fight_start,
+        });
+        gate_indices.push(clear.gate_index);
+    }
+
+    let mut raids = raids
+        .into_iter()
+        .map(|(mut raid, gate_indices)| {
+            let mut gates = gate_indices.into_iter().zip(raid.gates).collect::<Vec<_>>();
+            gates.sort_by_key(|(gate_index, gate)| (*gate_index, gate.cleared_at));
+            raid.gates = gates.into_iter().map(|(_, gate)| gate).collect();
+            raid
+        })
+        .collect::<Vec<_>>();
+    raids.sort_by(|a, b| a.raid.cmp(&b.raid));
+
+    raids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clear(id: i32, gate_index: usize, difficulty: &str) -> WeeklyClear {
+        WeeklyClear {
+            id,
+            fight_start: id as i64 * 1000,
+            boss_name: format!("Boss {gate_index}"),
+            difficulty: difficulty.to_string(),
+            local_player: "Alpha".to_string(),
+            raid: "Aegir".to_string(),
+            gate: format!("Aegir G{}", gate_index + 1),
+            gate_index,
+            gate_count: 2,
+        }
+    }
+
+    #[test]
+    fn keeps_first_clear_of_each_gate_and_difficulty() {
+        let mut other_raid = clear(4, 0, "Normal");
+        other_raid.raid = "Act 2: Brelshaza".to_string();
+        let clears = [
+            clear(1, 1, "Hard"),
+            clear(2, 0, "Hard"),
+            clear(3, 1, "Hard"),
+            clear(5, 1, "Normal"),
+            other_raid,
+        ];
+
+        let raids = build_weekly_raids(&clears);
+
+        assert_eq!(raids.len(), 2);
+        assert_eq!(raids[0].raid, "Act 2: Brelshaza");
+        let gates = raids[1]
+            .gates
+            .iter()
+            .map(|gate| {
+                (
+                    gate.gate.as_str(),
+                    gate.difficulty.as_str(),
+                    gate.encounter_id,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            gates,
+            vec![
+                ("Aegir G1", "Hard", 2),
+                ("Aegir G2", "Hard", 1),
+                ("Aegir G2", "Normal", 5)
+            ]
+        );
+        assert_eq!(raids[1].gate_count, 2);
+    }
+}
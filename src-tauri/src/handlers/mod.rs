@@ -8,8 +8,15 @@ use window_vibrancy::{apply_blur, clear_blur};
 
 use crate::app::autostart::{AutoLaunch, AutoLaunchManager};
 use crate::constants::*;
+use crate::context::AppContext;
+use crate::database::backup::BackupInfo;
+use crate::database::csv_export::export_csv;
 use crate::database::models::{GetEncounterPreviewArgs, InsertSyncLogsArgs};
+use crate::database::portable::{EncounterExport, ImportedEncounter};
 use crate::database::{Database, Repository};
+#[cfg(feature = "meter-core")]
+use crate::live::what_if::simulate as simulate_what_if_report;
+use crate::local::LocalPlayerRepository;
 use crate::models::*;
 use crate::settings::{Settings, SettingsManager};
 use crate::shell::ShellManager;
@@ -22,6 +29,12 @@ pub fn generate_handlers() -> Box<dyn Fn(Invoke) -> bool + Send + Sync> {
     Box::new(generate_handler![
         load_encounters_preview,
         load_encounter,
+        get_encounter_events,
+        diff_inspect_snapshots,
+        simulate_what_if,
+        export_encounter,
+        import_encounter,
+        export_encounters_csv,
         get_encounter_count,
         get_last_encounter_version,
         open_most_recent_encounter,
@@ -47,6 +60,11 @@ pub fn generate_handlers() -> Box<dyn Fn(Invoke) -> bool + Send + Sync> {
         disable_aot,
         set_clickthrough,
         optimize_database,
+        merge_database,
+        backup_database,
+        list_backups,
+        restore_database,
+        check_database_integrity,
         check_start_on_boot,
         set_start_on_boot,
         check_loa_running,
@@ -61,9 +79,18 @@ pub fn generate_handlers() -> Box<dyn Fn(Invoke) -> bool + Send + Sync> {
         install_beta_update,
         install_stable_update,
         get_local_characters,
+        get_character_names,
         get_character_statistics,
+        get_roster_statistics,
+        get_loadout_progression,
         get_raid_progression_range,
         get_raid_progression_statistics,
+        get_phase_splits,
+        get_raid_runs,
+        get_weekly_tracker,
+        get_teammates,
+        get_teammate_details,
+        get_support_synergy,
     ])
 }
 
@@ -97,6 +124,18 @@ pub async fn get_local_characters(repository: State<'_, Repository>) -> Result<V
     Ok(characters)
 }
 
+#[command]
+pub async fn get_character_names(
+    repository: State<'_, Repository>,
+    name: String,
+) -> Result<Vec<CharacterName>> {
+    let repository = repository.inner().clone();
+    let names = tauri::async_runtime::spawn_blocking(move || repository.get_character_names(&name))
+        .await
+        .context("character names query task failed")??;
+    Ok(names)
+}
+
 #[command]
 pub async fn get_character_statistics(
     repository: State<'_, Repository>,
@@ -110,6 +149,42 @@ pub async fn get_character_statistics(
     Ok(statistics)
 }
 
+#[command]
+pub async fn get_roster_statistics(
+    repository: State<'_, Repository>,
+    context: State<'_, AppContext>,
+    criteria: RosterStatisticsCriteria,
+) -> Result<RosterStatistics> {
+    let repository = repository.inner().clone();
+    let local_player_path = context.local_player_path.clone();
+    let statistics = tauri::async_runtime::spawn_blocking(move || {
+        let local_info = LocalPlayerRepository::new(local_player_path)?.read()?;
+        let mut players = local_info.local_players.into_iter().collect::<Vec<_>>();
+        players.sort_by_key(|(_, player)| std::cmp::Reverse(player.count));
+        let roster = players
+            .into_iter()
+            .map(|(character_id, player)| (character_id, player.name))
+            .collect();
+        repository.get_roster_statistics(roster, criteria)
+    })
+    .await
+    .context("roster statistics query task failed")??;
+    Ok(statistics)
+}
+
+#[command]
+pub async fn get_loadout_progression(
+    repository: State<'_, Repository>,
+    criteria: LoadoutProgressionCriteria,
+) -> Result<LoadoutProgression> {
+    let repository = repository.inner().clone();
+    let progression =
+        tauri::async_runtime::spawn_blocking(move || repository.get_loadout_progression(criteria))
+            .await
+            .context("loadout progression query task failed")??;
+    Ok(progression)
+}
+
 #[command]
 pub async fn get_raid_progression_statistics(
     repository: State<'_, Repository>,
@@ -146,6 +221,217 @@ pub async fn load_encounter(repository: State<'_, Repository>, id: String) -> Re
     Ok(encounter)
 }
 
+#[command]
+pub async fn get_phase_splits(
+    repository: State<'_, Repository>,
+    boss: String,
+    difficulty: String,
+    encounter_id: Option<i32>,
+) -> Result<PhaseSplitStatistics> {
+    let repository = repository.inner().clone();
+    let splits = tauri::async_runtime::spawn_blocking(move || {
+        repository.get_phase_splits(&boss, &difficulty, encounter_id)
+    })
+    .await
+    .context("phase splits query task failed")??;
+    Ok(splits)
+}
+
+#[command]
+pub async fn get_raid_runs(
+    repository: State<'_, Repository>,
+    criteria: RaidRunCriteria,
+) -> Result<RaidRunStatistics> {
+    let repository = repository.inner().clone();
+    let runs = tauri::async_runtime::spawn_blocking(move || repository.get_raid_runs(criteria))
+        .await
+        .context("raid runs query task failed")??;
+    Ok(runs)
+}
+
+#[command]
+pub async fn get_weekly_tracker(repository: State<'_, Repository>) -> Result<Vec<WeeklyCharacter>> {
+    let repository = repository.inner().clone();
+    let tracker = tauri::async_runtime::spawn_blocking(move || repository.get_weekly_tracker())
+        .await
+        .context("weekly tracker query task failed")??;
+    Ok(tracker)
+}
+
+#[command]
+pub async fn get_teammates(
+    repository: State<'_, Repository>,
+    criteria: TeammateCriteria,
+) -> Result<Vec<Teammate>> {
+    let repository = repository.inner().clone();
+    let teammates =
+        tauri::async_runtime::spawn_blocking(move || repository.get_teammates(criteria))
+            .await
+            .context("teammates query task failed")??;
+    Ok(teammates)
+}
+
+#[command]
+pub async fn get_teammate_details(
+    repository: State<'_, Repository>,
+    character_id: u64,
+) -> Result<TeammateDetails> {
+    let repository = repository.inner().clone();
+    let details =
+        tauri::async_runtime::spawn_blocking(move || repository.get_teammate_details(character_id))
+            .await
+            .context("teammate details query task failed")??;
+    Ok(details)
+}
+
+#[command]
+pub async fn get_support_synergy(
+    repository: State<'_, Repository>,
+    criteria: SupportSynergyCriteria,
+) -> Result<Vec<SupportSynergy>> {
+    let repository = repository.inner().clone();
+    let synergy =
+        tauri::async_runtime::spawn_blocking(move || repository.get_support_synergy(criteria))
+            .await
+            .context("support synergy query task failed")??;
+    Ok(synergy)
+}
+
+#[command]
+pub async fn get_encounter_events(
+    repository: State<'_, Repository>,
+    id: String,
+    kinds: Vec<TimelineEventKind>,
+) -> Result<Vec<TimelineEvent>> {
+    let repository = repository.inner().clone();
+    let events =
+        tauri::async_runtime::spawn_blocking(move || repository.get_encounter_events(&id, &kinds))
+            .await
+            .context("encounter events task failed")??;
+
+    Ok(events)
+}
+
+#[command]
+pub async fn diff_inspect_snapshots(
+    repository: State<'_, Repository>,
+    name: String,
+    before_encounter: i32,
+    after_encounter: i32,
+) -> Result<InspectSnapshotDiff> {
+    let repository = repository.inner().clone();
+    let diff = tauri::async_runtime::spawn_blocking(move || {
+        repository.diff_inspect_snapshots(&name, before_encounter, after_encounter)
+    })
+    .await
+    .context("inspect snapshot diff task failed")??;
+
+    Ok(diff)
+}
+
+#[command]
+pub async fn simulate_what_if(
+    repository: State<'_, Repository>,
+    id: String,
+    name: String,
+    edit: SnapshotEdit,
+) -> Result<WhatIfReport> {
+    let repository = repository.inner().clone();
+    let report = tauri::async_runtime::spawn_blocking(move || -> anyhow::Result<WhatIfReport> {
+        let encounter = repository.get_encounter(&id)?;
+        let entity = encounter
+            .entities
+            .get(&name)
+            .with_context(|| format!("{name} is not in encounter {id}"))?;
+        let (_, snapshot) = repository
+            .get_inspect_snapshot(&id, &name)?
+            .with_context(|| format!("no inspect snapshot of {name} in encounter {id}"))?;
+
+        simulate_what_if_report(entity, snapshot, &edit)
+    })
+    .await
+    .context("what-if simulation task failed")??;
+
+    Ok(report)
+}
+
+#[cfg(not(feature = "meter-core"))]
+fn simulate_what_if_report(
+    _entity: &EncounterEntity,
+    _snapshot: serde_json::Value,
+    _edit: &SnapshotEdit,
+) -> anyhow::Result<WhatIfReport> {
+    anyhow::bail!("what-if projections are only available with the meter core")
+}
+
+#[command]
+pub async fn export_encounter(
+    app_handle: AppHandle,
+    repository: State<'_, Repository>,
+    id: String,
+    path: String,
+) -> Result<()> {
+    let repository = repository.inner().clone();
+    let version = app_handle.package_info().version.to_string();
+    tauri::async_runtime::spawn_blocking(move || {
+        repository
+            .export_encounter(&id, &version)
+            .with_context(|| format!("could not export encounter {id}"))?
+            .write(path.as_ref())
+    })
+    .await
+    .context("encounter export task failed")??;
+
+    Ok(())
+}
+
+#[command]
+pub async fn import_encounter(
+    repository: State<'_, Repository>,
+    path: String,
+) -> Result<ImportedEncounter> {
+    let repository = repository.inner().clone();
+    let imported = tauri::async_runtime::spawn_blocking(move || {
+        let export = EncounterExport::read(path.as_ref())?;
+        repository
+            .import_encounter(export)
+            .context("could not import encounter")
+    })
+    .await
+    .context("encounter import task failed")??;
+
+    if imported.duplicate {
+        info!(
+            "encounter already exists as #{}, skipped import",
+            imported.id
+        );
+    } else {
+        info!("imported encounter #{}", imported.id);
+    }
+
+    Ok(imported)
+}
+
+#[command]
+pub async fn export_encounters_csv(
+    repository: State<'_, Repository>,
+    selection: EncounterSelection,
+    directory: String,
+) -> Result<CsvExportSummary> {
+    let repository = repository.inner().clone();
+    let summary = tauri::async_runtime::spawn_blocking(move || {
+        export_csv(&repository, selection, directory.as_ref())
+    })
+    .await
+    .context("csv export task failed")??;
+    info!(
+        "exported {} encounters to {}",
+        summary.encounters, summary.players_path
+    );
+
+    Ok(summary)
+}
+
 #[command]
 pub fn get_sync_candidates(repository: State<Repository>, force_resync: bool) -> Result<Vec<i32>> {
     let ids = repository
@@ -394,6 +680,7 @@ pub fn get_db_info(
         size,
         total_encounters,
         total_encounters_filtered,
+        integrity: None,
     };
 
     Ok(info)
@@ -407,6 +694,113 @@ pub fn optimize_database(repository: State<Repository>) -> Result<()> {
     Ok(())
 }
 
+#[command]
+pub async fn merge_database(
+    app_handle: AppHandle,
+    repository: State<'_, Repository>,
+    path: String,
+) -> Result<DatabaseMergeSummary> {
+    let repository = repository.inner().clone();
+    let version = app_handle.package_info().version.to_string();
+    let summary = tauri::async_runtime::spawn_blocking(move || {
+        repository
+            .merge_database(path.as_ref(), &version)
+            .with_context(|| format!("could not merge database {path}"))
+    })
+    .await
+    .context("database merge task failed")??;
+    info!(
+        "merged database: {} imported, {} duplicates",
+        summary.imported, summary.duplicates
+    );
+
+    Ok(summary)
+}
+
+#[command]
+pub async fn backup_database(
+    database: State<'_, Database>,
+    context: State<'_, AppContext>,
+    settings_manager: State<'_, SettingsManager>,
+) -> Result<String> {
+    let retention = settings_manager
+        .read()?
+        .map(|s| s.general.backup_retention)
+        .unwrap_or(DEFAULT_BACKUP_RETENTION)
+        .max(1) as usize;
+    let database = database.inner().clone();
+    let directory = context.backups_path.clone();
+    let path = tauri::async_runtime::spawn_blocking(move || database.backup(&directory, retention))
+        .await
+        .context("database backup task failed")?
+        .context("could not back up database")?;
+
+    Ok(path.to_string_lossy().into_owned())
+}
+
+#[command]
+pub fn list_backups(context: State<AppContext>) -> Result<Vec<BackupInfo>> {
+    let backups = crate::database::backup::list_backups(&context.backups_path)
+        .context("could not list backups")?;
+
+    Ok(backups)
+}
+
+#[command]
+pub async fn restore_database(
+    app_handle: AppHandle,
+    database: State<'_, Database>,
+    context: State<'_, AppContext>,
+    path: String,
+) -> Result<()> {
+    let database = database.inner().clone();
+    let directory = context.backups_path.clone();
+    let version = app_handle.package_info().version.to_string();
+    tauri::async_runtime::spawn_blocking(move || {
+        database
+            .restore(path.as_ref(), &version, &directory)
+            .with_context(|| format!("could not restore database from {path}"))
+    })
+    .await
+    .context("database restore task failed")??;
+
+    Ok(())
+}
+
+#[command]
+pub async fn check_database_integrity(
+    database: State<'_, Database>,
+    repository: State<'_, Repository>,
+    min_duration: i64,
+) -> Result<EncounterDbInfo> {
+    let database = database.inner().clone();
+    let repository = repository.inner().clone();
+    let info = tauri::async_runtime::spawn_blocking(move || -> anyhow::Result<EncounterDbInfo> {
+        let (total_encounters, total_encounters_filtered) = repository
+            .get_db_stats(min_duration)
+            .context("could not get db stats")?;
+        let integrity = database
+            .integrity_check()
+            .context("could not check database integrity")?;
+        if integrity != "ok" {
+            warn!("database integrity check failed: {integrity}");
+        }
+
+        Ok(EncounterDbInfo {
+            size: database
+                .get_metadata()
+                .context("could not get db metadata")?,
+            total_encounters,
+            total_encounters_filtered,
+            integrity: Some(integrity),
+        })
+    })
+    .await
+    .context("database integrity task failed")??;
+
+    Ok(info)
+}
+
 #[command]
 pub fn disable_blur(app_handle: AppHandle) -> Result<()> {
     if let Some(meter_window) = app_handle.get_meter_window() {
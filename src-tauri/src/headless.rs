@@ -0,0 +1,97 @@
+//! Runs the live parser without a window: packet captures go in, encounters come out in an
+//! `encounters.db`. Used by the `rebuild-encounters` binary for regression runs and for
+//! reprocessing old captures after parser fixes.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::{Context, Result, bail};
+use log::info;
+use tokio::runtime::Handle;
+
+use crate::api::BanList;
+use crate::data::AssetPreloader;
+use crate::database::Database;
+use crate::live::capture::{CaptureReader, ReplayPacing, replay_capture};
+use crate::live::sink::NullSink;
+use crate::live::{SaveContext, StartArgs};
+use crate::local::LocalPlayerRepository;
+use crate::settings::Settings;
+
+pub struct RebuildArgs {
+    /// replayed in order, each one starting from a fresh parser state
+    pub captures: Vec<PathBuf>,
+    pub database: PathBuf,
+    /// directory containing the `meter-data` folder
+    pub resource_dir: PathBuf,
+    /// local player cache to read and update, a throwaway file is used when unset
+    pub local_players: Option<PathBuf>,
+    pub settings: Option<Settings>,
+}
+
+/// Replays every capture through the live pipeline and writes the encounters they produce to
+/// `args.database`. Blocks until all encounters are saved, so call it from a blocking thread.
+pub fn rebuild_from_captures(args: RebuildArgs, runtime: Handle) -> Result<()> {
+    let RebuildArgs {
+        captures,
+        database,
+        resource_dir,
+        local_players,
+        settings,
+    } = args;
+
+    if captures.is_empty() {
+        bail!("no captures to rebuild");
+    }
+
+    AssetPreloader::new(&resource_dir).context("could not load meter-data")?;
+
+    let first_version = CaptureReader::open(&captures[0])?
+        .header()
+        .app_version
+        .clone();
+    let database = Database::new(database, &first_version)?;
+
+    let scratch_local_players = local_players.is_none().then(|| {
+        std::env::temp_dir().join(format!("loa-logs-rebuild-{}.json", uuid::Uuid::new_v4()))
+    });
+    let local_players = local_players
+        .or_else(|| scratch_local_players.clone())
+        .unwrap_or_default();
+
+    let result = captures.into_iter().try_for_each(|capture| {
+        let replay = replay_capture(&capture, runtime.clone(), ReplayPacing::Unpaced)?;
+        let meter_version = replay.header.app_version.clone();
+        let local_player_repository = LocalPlayerRepository::new(local_players.clone())?;
+        info!(
+            "rebuilding {} (recorded on v{meter_version})",
+            capture.display()
+        );
+
+        crate::live::start(StartArgs {
+            app: None,
+            sink: Arc::new(NullSink),
+            save_context: SaveContext {
+                repository: database.create_repository(),
+                stats_api: None,
+                meter_version,
+            },
+            ipc: replay.ipc,
+            runtime: runtime.clone(),
+            settings: settings.clone(),
+            region: replay.header.region,
+            local_info: local_player_repository.read()?,
+            local_player_repository,
+            heartbeat_api: None,
+            ban_list: BanList::new(),
+            capture: None,
+            replay_clock: Some(replay.clock),
+        })
+    });
+
+    if let Some(path) = scratch_local_players {
+        let _ = std::fs::remove_file(path);
+    }
+
+    result
+}
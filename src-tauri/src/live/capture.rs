@@ -0,0 +1,339 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, bail};
+use chrono::Utc;
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use log::{info, warn};
+use nineveh_formats::ipc::IPCServerToClientMessage;
+use serde::{Deserialize, Serialize};
+use tokio::runtime::Handle;
+
+use crate::live::clock;
+use crate::nineveh::NinevehIPCPair;
+
+/// magic bytes at the start of every (decompressed) capture file
+const CAPTURE_MAGIC: &[u8; 6] = b"LOACAP";
+/// bump whenever the record layout changes; readers reject unknown versions
+pub const CAPTURE_VERSION: u16 = 1;
+pub const CAPTURE_EXTENSION: &str = "loacap";
+/// the live thread is not joined on exit, so flush regularly to keep captures readable
+const FLUSH_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureHeader {
+    pub version: u16,
+    pub app_version: String,
+    pub started_at: i64,
+    pub region: Option<String>,
+}
+
+/// A single recorded IPC message. `ntp_timestamp` is the NTP corrected receive time when the
+/// clock had a fresh sample, `local_timestamp` is always the wall clock receive time.
+#[derive(Debug)]
+pub struct CaptureRecord {
+    pub local_timestamp: i64,
+    pub ntp_timestamp: Option<i64>,
+    pub message: IPCServerToClientMessage,
+}
+
+/// Writes every packet (and connection lifecycle message, so the inspect startup barrier behaves
+/// the same on replay) received from Nineveh to a gzip compressed capture file.
+///
+/// Layout after decompression:
+/// `magic | version: u16 | header_len: u32 | header json | records...`
+/// where each record is
+/// `local_timestamp: i64 | ntp_timestamp: i64 (i64::MIN if unavailable) | len: u32 | message`
+/// and `message` is encoded with the same framing Nineveh uses on the IPC socket.
+pub struct CaptureRecorder {
+    path: PathBuf,
+    writer: GzEncoder<BufWriter<File>>,
+    buffer: Vec<u8>,
+    records: u64,
+    last_flush: Instant,
+}
+
+impl CaptureRecorder {
+    pub fn create(directory: &Path, app_version: &str, region: Option<String>) -> Result<Self> {
+        std::fs::create_dir_all(directory)?;
+        let started_at = Utc::now();
+        let path = directory.join(format!(
+            "{}.{CAPTURE_EXTENSION}",
+            started_at.format("%Y-%m-%d-%H-%M-%S")
+        ));
+        let file = File::create(&path)
+            .with_context(|| format!("could not create capture file {}", path.display()))?;
+        let mut writer = GzEncoder::new(BufWriter::new(file), Compression::fast());
+
+        let header = serde_json::to_vec(&CaptureHeader {
+            version: CAPTURE_VERSION,
+            app_version: app_version.to_string(),
+            started_at: started_at.timestamp_millis(),
+            region,
+        })?;
+        writer.write_all(CAPTURE_MAGIC)?;
+        writer.write_all(&CAPTURE_VERSION.to_le_bytes())?;
+        writer.write_all(&(header.len() as u32).to_le_bytes())?;
+        writer.write_all(&header)?;
+
+        info!("recording packet capture to {}", path.display());
+
+        Ok(Self {
+            path,
+            writer,
+            buffer: Vec::new(),
+            records: 0,
+            last_flush: Instant::now(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn record(
+        &mut self,
+        runtime: &Handle,
+        message: &IPCServerToClientMessage,
+        local_timestamp: i64,
+        ntp_timestamp: Option<i64>,
+    ) -> Result<()> {
+        if matches!(message, IPCServerToClientMessage::HandshakeAck) {
+            return Ok(());
+        }
+
+        self.buffer.clear();
+        runtime
+            .block_on(nineveh_formats::io::write(&mut self.buffer, message))
+            .map_err(|e| anyhow::anyhow!("could not encode capture record: {e}"))?;
+
+        self.writer.write_all(&local_timestamp.to_le_bytes())?;
+        self.writer
+            .write_all(&ntp_timestamp.unwrap_or(i64::MIN).to_le_bytes())?;
+        self.writer
+            .write_all(&(self.buffer.len() as u32).to_le_bytes())?;
+        self.writer.write_all(&self.buffer)?;
+        self.records += 1;
+
+        if self.last_flush.elapsed() >= FLUSH_INTERVAL {
+            self.writer.flush()?;
+            self.last_flush = Instant::now();
+        }
+
+        Ok(())
+    }
+
+    pub fn finish(self) -> Result<()> {
+        let records = self.records;
+        let path = self.path.clone();
+        self.writer.finish()?.flush()?;
+        info!(
+            "finished packet capture {} ({records} records)",
+            path.display()
+        );
+        Ok(())
+    }
+}
+
+/// Sequential reader over a capture file written by [`CaptureRecorder`].
+pub struct CaptureReader {
+    header: CaptureHeader,
+    reader: GzDecoder<BufReader<File>>,
+    buffer: Vec<u8>,
+}
+
+impl CaptureReader {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path)
+            .with_context(|| format!("could not open capture file {}", path.display()))?;
+        let mut reader = GzDecoder::new(BufReader::new(file));
+
+        let mut magic = [0u8; CAPTURE_MAGIC.len()];
+        reader
+            .read_exact(&mut magic)
+            .context("could not read capture header")?;
+        if &magic != CAPTURE_MAGIC {
+            bail!("{} is not a packet capture", path.display());
+        }
+
+        let version = u16::from_le_bytes(read_array(&mut reader)?);
+        if version != CAPTURE_VERSION {
+            bail!("unsupported capture version {version}, expected {CAPTURE_VERSION}");
+        }
+
+        let header_len = u32::from_le_bytes(read_array(&mut reader)?) as usize;
+        let mut header = vec![0u8; header_len];
+        reader.read_exact(&mut header)?;
+        let header: CaptureHeader = serde_json::from_slice(&header)?;
+
+        Ok(Self {
+            header,
+            reader,
+            buffer: Vec::new(),
+        })
+    }
+
+    pub fn header(&self) -> &CaptureHeader {
+        &self.header
+    }
+
+    /// Returns the next record, or `None` once the end of the capture is reached. A truncated
+    /// trailing record (e.g. the app was killed mid-write) is treated as the end of the capture.
+    pub fn next_record(&mut self, runtime: &Handle) -> Result<Option<CaptureRecord>> {
+        let local_timestamp = match read_array(&mut self.reader) {
+            Ok(bytes) => i64::from_le_bytes(bytes),
+            Err(e) if is_eof(&e) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        let (ntp_timestamp, len) = match self.read_record_header() {
+            Ok(record) => record,
+            Err(e) if is_eof(&e) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        self.buffer.resize(len, 0);
+        match self.reader.read_exact(&mut self.buffer) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => {
+                warn!("capture ended with a truncated record");
+                return Ok(None);
+            }
+            Err(e) => return Err(e.into()),
+        }
+
+        let mut slice = self.buffer.as_slice();
+        let message: IPCServerToClientMessage = runtime
+            .block_on(nineveh_formats::io::read(&mut slice))
+            .map_err(|e| anyhow::anyhow!("could not decode capture record: {e}"))?;
+
+        Ok(Some(CaptureRecord {
+            local_timestamp,
+            ntp_timestamp: (ntp_timestamp != i64::MIN).then_some(ntp_timestamp),
+            message,
+        }))
+    }
+
+    fn read_record_header(&mut self) -> Result<(i64, usize)> {
+        let ntp_timestamp = i64::from_le_bytes(read_array(&mut self.reader)?);
+        let len = u32::from_le_bytes(read_array(&mut self.reader)?) as usize;
+        Ok((ntp_timestamp, len))
+    }
+}
+
+/// Receive times of the replayed messages, in the order they were sent to the live loop. The
+/// loop advances it once per message so the parser sees the recorded time instead of the
+/// replay time.
+#[derive(Debug, Clone, Default)]
+pub struct ReplayClock(Arc<Mutex<VecDeque<(i64, Option<i64>)>>>);
+
+impl ReplayClock {
+    fn push(&self, local_timestamp: i64, ntp_timestamp: Option<i64>) {
+        if let Ok(mut times) = self.0.lock() {
+            times.push_back((local_timestamp, ntp_timestamp));
+        }
+    }
+
+    pub fn advance(&self) {
+        let next = self.0.lock().ok().and_then(|mut times| times.pop_front());
+        if let Some((local_timestamp, ntp_timestamp)) = next {
+            clock::set_replay_time(local_timestamp, ntp_timestamp);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplayPacing {
+    /// feed records as fast as the live loop consumes them
+    Unpaced,
+    /// sleep between records so they arrive with their recorded spacing, scaled by the factor
+    Realtime(f64),
+}
+
+/// A capture opened for replay, see [`replay_capture`].
+pub struct CaptureReplay {
+    pub header: CaptureHeader,
+    pub ipc: NinevehIPCPair,
+    pub clock: ReplayClock,
+}
+
+/// Builds an IPC pair that replays a capture instead of talking to Nineveh. Packet actions and
+/// inspect requests sent by the live loop are discarded, and the receiver closes once the
+/// capture is exhausted, which ends `live::start`.
+pub fn replay_capture(path: &Path, runtime: Handle, pacing: ReplayPacing) -> Result<CaptureReplay> {
+    let mut reader = CaptureReader::open(path)?;
+    let header = reader.header().clone();
+    let clock = ReplayClock::default();
+    let replay_clock = clock.clone();
+    let (to_tx, to_rx) = tokio::sync::mpsc::unbounded_channel();
+    let (from_tx, mut from_rx) = tokio::sync::mpsc::unbounded_channel();
+    let path = path.to_path_buf();
+
+    thread::Builder::new()
+        .name("capture-replay".to_string())
+        .spawn(move || {
+            let started = Instant::now();
+            let mut first_timestamp = None;
+            let mut records = 0u64;
+
+            loop {
+                // drain whatever the live loop tried to send back to nineveh
+                while from_rx.try_recv().is_ok() {}
+
+                let record = match reader.next_record(&runtime) {
+                    Ok(Some(record)) => record,
+                    Ok(None) => break,
+                    Err(e) => {
+                        warn!("failed to read capture {}: {e}", path.display());
+                        break;
+                    }
+                };
+
+                if let ReplayPacing::Realtime(speed) = pacing
+                    && speed > 0.0
+                {
+                    let first = *first_timestamp.get_or_insert(record.local_timestamp);
+                    let offset_ms = (record.local_timestamp - first).max(0) as f64 / speed;
+                    let due = started + Duration::from_millis(offset_ms as u64);
+                    let now = Instant::now();
+                    if due > now {
+                        thread::sleep(due - now);
+                    }
+                }
+
+                replay_clock.push(record.local_timestamp, record.ntp_timestamp);
+                if to_tx.send(record.message).is_err() {
+                    break;
+                }
+                records += 1;
+            }
+
+            info!("finished replaying {} ({records} records)", path.display());
+        })?;
+
+    Ok(CaptureReplay {
+        header,
+        ipc: (from_tx, to_rx),
+        clock,
+    })
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn is_eof(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<std::io::Error>()
+        .is_some_and(|e| e.kind() == ErrorKind::UnexpectedEof)
+}
@@ -0,0 +1,107 @@
+//! Wall clock for the live parser.
+//!
+//! While a capture is replayed the clock is pinned to the recorded receive time of the packet
+//! being processed, so a capture rebuilds into the same encounter no matter when or how fast it
+//! is replayed. Outside of replays it is just `Utc::now()`, intervals use [`Tick`].
+
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Clone, Copy)]
+struct ReplayTime {
+    local: i64,
+    ntp: Option<i64>,
+}
+
+thread_local! {
+    static REPLAY_TIME: Cell<Option<ReplayTime>> = const { Cell::new(None) };
+}
+
+pub(crate) fn now() -> DateTime<Utc> {
+    REPLAY_TIME
+        .get()
+        .and_then(|time| DateTime::from_timestamp_millis(time.local))
+        .unwrap_or_else(Utc::now)
+}
+
+/// Pin the clock of the current thread to a recorded packet receive time.
+pub(crate) fn set_replay_time(local: i64, ntp: Option<i64>) {
+    REPLAY_TIME.set(Some(ReplayTime { local, ntp }));
+}
+
+/// NTP corrected time for `local_timestamp_ms` using the offset recorded with the current
+/// packet. Returns `None` when not replaying, so callers fall back to the live NTP clock.
+pub(crate) fn replay_ntp_timestamp(local_timestamp_ms: i64) -> Option<Option<i64>> {
+    REPLAY_TIME.get().map(|time| {
+        time.ntp
+            .map(|ntp| local_timestamp_ms.saturating_add(ntp - time.local))
+    })
+}
+
+/// A point in time to measure intervals from: the recorded packet time while replaying,
+/// monotonic time otherwise so live intervals survive system clock changes.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Tick {
+    Replay(i64),
+    Live(Instant),
+}
+
+pub(crate) fn tick() -> Tick {
+    match REPLAY_TIME.get() {
+        Some(time) => Tick::Replay(time.local),
+        None => Tick::Live(Instant::now()),
+    }
+}
+
+impl Tick {
+    pub(crate) fn elapsed(&self) -> Duration {
+        match (self, tick()) {
+            (Tick::Replay(start), Tick::Replay(now)) => {
+                Duration::from_millis(now.saturating_sub(*start).max(0) as u64)
+            }
+            (Tick::Live(start), _) => start.elapsed(),
+            (Tick::Replay(_), Tick::Live(_)) => Duration::ZERO,
+        }
+    }
+}
+
+/// Throttles periodic work in the live loop. It starts on the first check, so replays count
+/// from the first replayed packet.
+#[derive(Debug, Default)]
+pub(crate) struct Stopwatch {
+    started: Option<Tick>,
+}
+
+impl Stopwatch {
+    /// Whether at least `duration` has passed since the stopwatch was started or restarted.
+    pub(crate) fn has_elapsed(&mut self, duration: Duration) -> bool {
+        self.started.get_or_insert_with(tick).elapsed() >= duration
+    }
+
+    pub(crate) fn restart(&mut self) {
+        self.started = Some(tick());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stopwatch_follows_replayed_packet_times() {
+        let interval = Duration::from_millis(200);
+        let mut stopwatch = Stopwatch::default();
+
+        set_replay_time(1_000, None);
+        assert!(!stopwatch.has_elapsed(interval));
+        set_replay_time(1_199, None);
+        assert!(!stopwatch.has_elapsed(interval));
+        set_replay_time(1_200, None);
+        assert!(stopwatch.has_elapsed(interval));
+
+        stopwatch.restart();
+        assert!(!stopwatch.has_elapsed(interval));
+    }
+}
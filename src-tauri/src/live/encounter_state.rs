@@ -3,13 +3,19 @@ use crate::constants::{DARK_GRENADE_ENTITY_ID, DARK_GRENADE_ENTITY_NAME};
 use crate::data::*;
 use crate::database::Repository;
 use crate::database::models::InsertEncounterArgs;
-use crate::live::entity_tracker::{Entity, EntityTracker, SkillOptionSnapshot};
+use crate::live::clock;
+use crate::live::entity_tracker::{Entity, EntityTracker, InspectSnapshot, SkillOptionSnapshot};
+use crate::live::identity_tracker::IdentityTracker;
+use crate::live::pace::PaceTracker;
 use crate::live::rdps::{
     HitCritMetrics, HitRdpsOutcome, HitRdpsResult, HitStatDamageMetrics, RdpsInvalidReason,
     analyze_hit_rdps, filter_target_effects_for_attacker, resolve_skill_effect_flags,
 };
+use crate::live::sink::EventSink;
 use crate::live::skill_tracker::SkillTracker;
+use crate::live::stagger_tracker::StaggerTracker;
 use crate::live::status_tracker::{StatusEffectDetails, StatusTracker};
+use crate::live::timeline::Timeline;
 use crate::live::utils::*;
 use crate::live::{DEBUG_DUMP_DAMAGE_STATE_JSON, write_debug_json_dump};
 use crate::models::*;
@@ -25,12 +31,17 @@ use meter_defs::types::SkillMoveOptionData;
 use serde::Serialize;
 use serde_json::json;
 use std::cmp::max;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, VecDeque};
 use std::default::Default;
+use std::fmt;
 use std::hash::Hash;
+use std::sync::Arc;
 use std::time::Instant;
-use tauri::{AppHandle, Emitter, Manager};
-use tokio::task;
+use tokio::runtime::Handle;
+use tokio::task::{self, JoinHandle};
+
+const BATTLE_ITEM_REPEAT_WINDOW_MS: i64 = 500;
+const DEATH_RECAP_HITS: usize = 10;
 
 #[derive(Debug, Serialize, Clone, Default)]
 struct StatDamageDump {
@@ -197,9 +208,29 @@ struct DamageHitContext {
     hit_option: HitOption,
 }
 
+/// Everything `save_to_db` needs to persist a finished encounter.
+#[derive(Clone)]
+pub struct SaveContext {
+    pub repository: Repository,
+    /// used to fill in missing player info, skipped when unset (e.g. offline rebuilds)
+    pub stats_api: Option<StatsApi>,
+    pub meter_version: String,
+}
+
+impl fmt::Debug for SaveContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SaveContext")
+            .field("stats_api", &self.stats_api.is_some())
+            .field("meter_version", &self.meter_version)
+            .finish_non_exhaustive()
+    }
+}
+
 #[derive(Debug)]
 pub struct EncounterState {
-    pub app: AppHandle,
+    sink: Arc<dyn EventSink>,
+    save_context: SaveContext,
+    save_tasks: Vec<JoinHandle<()>>,
     pub encounter: Encounter,
     pub resetting: bool,
     pub boss_dead_update: bool,
@@ -212,6 +243,12 @@ pub struct EncounterState {
     cast_log: HashMap<String, HashMap<u32, Vec<i32>>>,
 
     boss_hp_log: HashMap<String, Vec<BossHpLog>>,
+    stagger_tracker: StaggerTracker,
+    identity_tracker: IdentityTracker,
+    // last hits taken by each player, kept for death recaps
+    recent_damage_taken: HashMap<String, VecDeque<DamageTakenEvent>>,
+    timeline: Timeline,
+    pace_tracker: PaceTracker,
 
     pub intermission_start: Option<i64>,
     pub intermission_end: Option<i64>,
@@ -240,6 +277,8 @@ pub struct EncounterState {
 
     pub damage_is_valid: bool,
     player_contributions: HashMap<String, DamageDataAccumulator>,
+    // latest inspect per player name, kept across resets since players are only inspected once
+    inspect_snapshots: HashMap<String, InspectSnapshot>,
     lal_debug_zone_id: u32,
     lal_debug_zone_level: u32,
     lal_debug_end_time_ms: Option<i64>,
@@ -247,9 +286,11 @@ pub struct EncounterState {
 }
 
 impl EncounterState {
-    pub fn new(window: AppHandle) -> EncounterState {
+    pub fn new(sink: Arc<dyn EventSink>, save_context: SaveContext) -> EncounterState {
         EncounterState {
-            app: window,
+            sink,
+            save_context,
+            save_tasks: Vec::new(),
             encounter: Encounter::default(),
             resetting: false,
             raid_clear: false,
@@ -259,6 +300,11 @@ impl EncounterState {
 
             damage_log: HashMap::new(),
             boss_hp_log: HashMap::new(),
+            stagger_tracker: StaggerTracker::new(),
+            identity_tracker: IdentityTracker::new(),
+            recent_damage_taken: HashMap::new(),
+            timeline: Timeline::new(),
+            pace_tracker: PaceTracker::new(),
             cast_log: HashMap::new(),
             intermission_start: None,
             intermission_end: None,
@@ -288,6 +334,7 @@ impl EncounterState {
 
             damage_is_valid: true,
             player_contributions: HashMap::new(),
+            inspect_snapshots: HashMap::new(),
             lal_debug_zone_id: 0,
             lal_debug_zone_level: 0,
             lal_debug_end_time_ms: None,
@@ -295,6 +342,15 @@ impl EncounterState {
         }
     }
 
+    /// NTP corrected time for an event observed at `instant`, if the clock has a fresh sample
+    pub(crate) fn ntp_timestamp(&self, local_timestamp_ms: i64, instant: Instant) -> Option<i64> {
+        if let Some(ntp_timestamp) = clock::replay_ntp_timestamp(local_timestamp_ms) {
+            return ntp_timestamp;
+        }
+        self.ntp_clock
+            .timestamp_for_event(local_timestamp_ms, instant)
+    }
+
     // keep all player entities, reset all stats
     pub fn soft_reset(&mut self, keep_bosses: bool) {
         let entities = std::mem::take(&mut self.encounter.entities);
@@ -308,6 +364,11 @@ impl EncounterState {
         self.damage_log = HashMap::new();
         self.cast_log = HashMap::new();
         self.boss_hp_log = HashMap::new();
+        self.stagger_tracker = StaggerTracker::new();
+        self.identity_tracker.reset();
+        self.recent_damage_taken = HashMap::new();
+        self.timeline = Timeline::new();
+        self.pace_tracker = PaceTracker::new();
         self.intermission_start = None;
         self.intermission_end = None;
         self.party_info = Vec::new();
@@ -677,20 +738,25 @@ impl EncounterState {
             e.name == self.encounter.local_player || e.damage_stats.damage_dealt > 0
         });
 
-        self.app
-            .emit("zone-change", "")
-            .expect("failed to emit zone-change");
+        self.sink.emit("zone-change", "");
 
         self.soft_reset(false);
     }
 
     pub fn on_transit(&mut self, zone_id: u32) {
-        if zone_id == 37545 {
-            // do not reset on kazeros g2-2 for nm/hm
-            if self.raid_difficulty != "The First" {
-                let now = Utc::now().timestamp_millis();
+        if let Some(intermission) = RAID_DEFINITIONS.intermission(zone_id) {
+            // do not reset on intermissions, e.g. kazeros g2-2 for nm/hm
+            if !intermission.clears_raid(&self.raid_difficulty) {
+                let now = clock::now().timestamp_millis();
                 self.intermission_start = Some(now);
                 self.rearm_startup_barrier_on_next_combat = true;
+                self.timeline.record(
+                    self.encounter.fight_start,
+                    now,
+                    TimelineEventKind::Intermission,
+                    None,
+                    None,
+                );
                 info!("starting intermission");
                 for entity in self
                     .encounter
@@ -713,22 +779,25 @@ impl EncounterState {
             return;
         }
 
-        self.app
-            .emit("zone-change", "no-toast")
-            .expect("failed to emit zone-change");
+        self.sink.emit("zone-change", "no-toast");
 
         self.soft_reset(false);
     }
 
     pub fn on_phase_transition(&mut self, phase_code: i32) {
-        self.app
-            .emit("phase-transition", phase_code)
-            .expect("failed to emit phase-transition");
+        self.sink.emit("phase-transition", phase_code);
+        self.timeline.record(
+            self.encounter.fight_start,
+            clock::now().timestamp_millis(),
+            TimelineEventKind::PhaseTransition,
+            None,
+            Some(phase_code as i64),
+        );
 
         match phase_code {
             0 | 2 | 3 | 4 => {
                 if !self.encounter.current_boss_name.is_empty() {
-                    self.lal_debug_end_time_ms = Some(Utc::now().timestamp_millis());
+                    self.lal_debug_end_time_ms = Some(clock::now().timestamp_millis());
                     self.save_to_db(false);
                     self.saved = true;
                 }
@@ -765,6 +834,7 @@ impl EncounterState {
         entity_tracker: &EntityTracker,
     ) {
         self.merge_unresolved_player_entity(&entity);
+        let mut revived = false;
         self.encounter
             .entities
             .entry(entity.name.clone())
@@ -783,7 +853,7 @@ impl EncounterState {
                     player.character_id = entity.character_id;
                 }
                 if hp > 0 {
-                    Self::mark_entity_alive(player, Utc::now().timestamp_millis());
+                    revived = Self::mark_entity_alive(player, clock::now().timestamp_millis());
                 }
             })
             .or_insert_with(|| {
@@ -792,13 +862,23 @@ impl EncounterState {
                 player.max_hp = max_hp;
                 player
             });
+        if revived {
+            self.timeline.record(
+                self.encounter.fight_start,
+                clock::now().timestamp_millis(),
+                TimelineEventKind::Revive,
+                Some(&entity.name),
+                None,
+            );
+        }
         self.resolve_source_aliases_for_player(&entity, entity_tracker);
         self.refresh_encounter_player_damage_totals();
     }
 
-    fn mark_entity_alive(entity: &mut EncounterEntity, timestamp: i64) {
+    // returns whether the entity was dead
+    fn mark_entity_alive(entity: &mut EncounterEntity, timestamp: i64) -> bool {
         if !entity.is_dead {
-            return;
+            return false;
         }
 
         entity.is_dead = false;
@@ -811,6 +891,7 @@ impl EncounterState {
         {
             death.dead_for = Some((timestamp - death.death_time).max(0));
         }
+        true
     }
 
     fn merge_unresolved_player_entity(&mut self, entity: &Entity) {
@@ -1004,10 +1085,26 @@ impl EncounterState {
         }
     }
 
+    fn add_damage_taken_source(sources: &mut Vec<DamageTakenSource>, hit: DamageTakenSource) {
+        match sources
+            .iter_mut()
+            .find(|s| s.skill_id == hit.skill_id && s.source == hit.source)
+        {
+            Some(existing) => {
+                existing.damage += hit.damage;
+                existing.hits += hit.hits;
+            }
+            None => sources.push(hit),
+        }
+    }
+
     fn merge_damage_stats(target: &mut DamageStats, source: DamageStats) {
         target.damage_dealt += source.damage_dealt;
         target.hyper_awakening_damage += source.hyper_awakening_damage;
         target.damage_taken += source.damage_taken;
+        for damage_taken in source.damage_taken_by {
+            Self::add_damage_taken_source(&mut target.damage_taken_by, damage_taken);
+        }
         Self::merge_numeric_map(&mut target.buffed_by, source.buffed_by);
         Self::merge_numeric_map(&mut target.debuffed_by, source.debuffed_by);
         target.buffed_by_support += source.buffed_by_support;
@@ -1349,12 +1446,12 @@ impl EncounterState {
     pub fn on_new_npc(&mut self, entity: Entity, hp: i64, max_hp: i64) {
         let entity_name = entity.name.clone();
 
-        // set intermission end if boss is kazeros g2
+        // set intermission end once the boss of the next part shows up, e.g. kazeros g2
         if self.intermission_end.is_none()
             && self.intermission_start.is_some()
-            && entity_name == "Death Incarnate Kazeros"
+            && RAID_DEFINITIONS.ends_intermission(&entity_name)
         {
-            self.intermission_end = Some(Utc::now().timestamp_millis());
+            self.intermission_end = Some(clock::now().timestamp_millis());
             info!("ending intermission");
         }
 
@@ -1402,13 +1499,14 @@ impl EncounterState {
                 self.encounter.current_boss_name.clone()
             };
 
-            if let Some(difficulty) = adjusted_extreme_aegir_difficulty(
+            if let Some(difficulty) = RAID_DEFINITIONS.adjusted_difficulty(
                 &self.encounter.current_boss_name,
                 &self.raid_difficulty,
                 npc.max_hp,
             ) {
                 self.raid_difficulty = difficulty.to_string();
             }
+            self.load_pace_reference();
         }
     }
 
@@ -1420,6 +1518,11 @@ impl EncounterState {
             .get(&self.encounter.current_boss_name)
             .map(|b| b.current_hp)
             .unwrap_or_default();
+        let recap = self
+            .recent_damage_taken
+            .remove(&dead_entity.name)
+            .map(Vec::from)
+            .unwrap_or_default();
 
         let entity = self
             .encounter
@@ -1443,11 +1546,18 @@ impl EncounterState {
             self.boss_dead_update = true;
         }
 
-        let now = Utc::now().timestamp_millis();
+        let now = clock::now().timestamp_millis();
         entity.current_hp = 0;
         entity.is_dead = true;
         entity.damage_stats.deaths += 1;
         entity.damage_stats.death_time = now;
+        let incapacitations = entity
+            .damage_stats
+            .incapacitations
+            .iter()
+            .filter(|x| x.timestamp <= now && x.timestamp + x.duration > now)
+            .cloned()
+            .collect();
         entity
             .damage_stats
             .death_info
@@ -1455,6 +1565,8 @@ impl EncounterState {
             .push(DeathInfo {
                 death_time: now,
                 dead_for: None,
+                recap,
+                incapacitations,
             });
         // record boss hp at time of death
         entity.damage_stats.boss_hp_at_death = Some(boss_hp);
@@ -1469,10 +1581,23 @@ impl EncounterState {
                 // cap duration to death time if it exceeds it
                 x.duration = x.timestamp - entity.damage_stats.death_time;
             });
+
+        let kind = if entity.entity_type == EntityType::Boss {
+            TimelineEventKind::BossKill
+        } else {
+            TimelineEventKind::Death
+        };
+        self.timeline.record(
+            self.encounter.fight_start,
+            now,
+            kind,
+            Some(&dead_entity.name),
+            None,
+        );
     }
 
     pub fn on_skill_cooldown(&mut self, cooldown_struct: SkillCooldownStruct) {
-        let now = Utc::now().timestamp_millis();
+        let now = clock::now().timestamp_millis();
 
         let cooldown_duration = if cooldown_struct.skill_cooldown_stack_data.has_stacks > 0 {
             (cooldown_struct
@@ -1564,7 +1689,15 @@ impl EncounterState {
             entity.class = get_class_from_id(&source_entity.class_id);
         }
 
-        Self::mark_entity_alive(entity, timestamp);
+        if Self::mark_entity_alive(entity, timestamp) {
+            self.timeline.record(
+                self.encounter.fight_start,
+                timestamp,
+                TimelineEventKind::Revive,
+                Some(&entity.name),
+                None,
+            );
+        }
         entity.skill_stats.casts += 1;
 
         // if skills have different ids but the same name, we group them together
@@ -1663,9 +1796,28 @@ impl EncounterState {
         self.set_ntp_fight_start_from_cache();
 
         self.encounter.boss_only_damage = self.boss_only_damage;
-        self.app
-            .emit("raid-start", timestamp)
-            .expect("failed to emit raid-start");
+        self.sink.emit("raid-start", timestamp);
+        self.timeline.record(
+            timestamp,
+            timestamp,
+            TimelineEventKind::RaidStart,
+            None,
+            None,
+        );
+        self.load_pace_reference();
+    }
+
+    // the boss is not always known yet when the fight starts
+    fn load_pace_reference(&mut self) {
+        if self.encounter.fight_start == 0 {
+            return;
+        }
+
+        self.pace_tracker.load(
+            &self.save_context.repository,
+            &self.encounter.current_boss_name,
+            &self.raid_difficulty,
+        );
     }
 
     fn set_ntp_fight_start_from_cache(&mut self) {
@@ -1677,9 +1829,8 @@ impl EncounterState {
             return;
         };
 
-        if let Some(ntp_fight_start) = self
-            .ntp_clock
-            .timestamp_for_event(self.encounter.fight_start, fight_start_instant)
+        if let Some(ntp_fight_start) =
+            self.ntp_timestamp(self.encounter.fight_start, fight_start_instant)
         {
             self.ntp_fight_start = ntp_fight_start;
         }
@@ -2472,7 +2623,7 @@ impl EncounterState {
         buffered_player_entities: &HashMap<u64, Entity>,
         status_tracker: &mut StatusTracker,
     ) -> HashMap<u64, Vec<StatusEffectDetails>> {
-        let timestamp = Utc::now();
+        let timestamp = clock::now();
         let mut owner_self_effects_by_entity_id = HashMap::new();
 
         for entity in buffered_player_entities.values() {
@@ -3173,6 +3324,13 @@ impl EncounterState {
             .entry(skill_key)
             .and_modify(|s| s.stagger += damage_data.stagger as i64);
         source_entity.damage_stats.stagger += damage_data.stagger as i64;
+        if source_entity.entity_type == EntityType::Player {
+            self.stagger_tracker.on_stagger_hit(
+                dmg_target_entity.id,
+                &source_entity.name,
+                damage_data.stagger as i64,
+            );
+        }
 
         let mut player_totals_stale = source_promoted_to_player;
 
@@ -3405,6 +3563,34 @@ impl EncounterState {
         };
 
         target_entity.damage_stats.damage_taken += damage;
+        if target_entity.entity_type == EntityType::Player {
+            let event = DamageTakenEvent {
+                timestamp,
+                source: source_entity.name.clone(),
+                skill_id: skill_key,
+                skill_name: skill.name.clone(),
+                damage,
+                current_hp: damage_data.target_current_hp,
+            };
+            Self::add_damage_taken_source(
+                &mut target_entity.damage_stats.damage_taken_by,
+                DamageTakenSource {
+                    source: event.source.clone(),
+                    skill_id: event.skill_id,
+                    skill_name: event.skill_name.clone(),
+                    damage,
+                    hits: 1,
+                },
+            );
+            let recent = self
+                .recent_damage_taken
+                .entry_ref(&target_entity.name)
+                .or_default();
+            if recent.len() == DEATH_RECAP_HITS {
+                recent.pop_front();
+            }
+            recent.push_back(event);
+        }
 
         source_entity.skill_stats.hits += 1;
         skill.hits += 1;
@@ -4034,6 +4220,13 @@ impl EncounterState {
                 entity
             });
         entity.skill_stats.counters += 1;
+        self.timeline.record(
+            self.encounter.fight_start,
+            clock::now().timestamp_millis(),
+            TimelineEventKind::Counter,
+            Some(&source_entity.name),
+            None,
+        );
     }
 
     pub fn on_abnormal_move(
@@ -4097,6 +4290,13 @@ impl EncounterState {
             "Player {} will be incapacitated for {}ms",
             victim_entity_state.name, incapacitated_time_ms
         );
+        self.timeline.record(
+            self.encounter.fight_start,
+            timestamp,
+            TimelineEventKind::AbnormalMove,
+            Some(&victim_entity.name),
+            Some(incapacitated_time_ms),
+        );
     }
 
     pub fn on_cc_applied(&mut self, victim_entity: &Entity, status_effect: &StatusEffectDetails) {
@@ -4127,6 +4327,13 @@ impl EncounterState {
             .damage_stats
             .incapacitations
             .push(new_event);
+        self.timeline.record(
+            self.encounter.fight_start,
+            status_effect.timestamp.timestamp_millis(),
+            TimelineEventKind::CrowdControl,
+            Some(&victim_entity.name),
+            Some(duration_ms as i64),
+        );
     }
 
     pub fn on_cc_removed(
@@ -4163,6 +4370,13 @@ impl EncounterState {
                 .damage_stats
                 .incapacitations
                 .sort_by_key(|x| x.timestamp);
+            self.timeline.record(
+                self.encounter.fight_start,
+                status_effect.timestamp.timestamp_millis(),
+                TimelineEventKind::CrowdControl,
+                Some(&victim_entity.name),
+                Some(duration_ms),
+            );
             return;
         }
 
@@ -4186,92 +4400,57 @@ impl EncounterState {
                     timestamp - event.timestamp
                 );
                 event.duration = timestamp - event.timestamp;
+                self.timeline.update_value(
+                    self.encounter.fight_start,
+                    event.timestamp,
+                    TimelineEventKind::CrowdControl,
+                    &victim_entity.name,
+                    event.duration,
+                );
             }
         }
     }
 
-    // pub fn on_identity_gain(&mut self, pkt: &PKTIdentityGaugeChangeNotify) {
-    //     if self.encounter.fight_start == 0 {
-    //         return;
-    //     }
-    //
-    //     if self.encounter.local_player.is_empty() {
-    //         if let Some((_, entity)) = self
-    //             .encounter
-    //             .entities
-    //             .iter()
-    //             .find(|(_, e)| e.id == pkt.player_id)
-    //         {
-    //             self.encounter.local_player.clone_from(&entity.name);
-    //         } else {
-    //             return;
-    //         }
-    //     }
-    //
-    //     if let Some(entity) = self
-    //         .encounter
-    //         .entities
-    //         .get_mut(&self.encounter.local_player)
-    //     {
-    //         self.identity_log
-    //             .entry(entity.name.clone())
-    //             .or_default()
-    //             .push((
-    //                 Utc::now().timestamp_millis(),
-    //                 (
-    //                     pkt.identity_gauge1,
-    //                     pkt.identity_gauge2,
-    //                     pkt.identity_gauge3,
-    //                 ),
-    //             ));
-    //     }
-    // }
-
-    // pub fn on_stagger_change(&mut self, pkt: &PKTParalyzationStateNotify) {
-    //     if self.encounter.current_boss_name.is_empty() || self.encounter.fight_start == 0 {
-    //         return;
-    //     }
-
-    //     if let Some(boss) = self
-    //         .encounter
-    //         .entities
-    //         .get_mut(&self.encounter.current_boss_name)
-    //     {
-    //         let timestamp = Utc::now().timestamp_millis();
-    //         let current_stagger = pkt.paralyzation_point as i32;
-    //         let max_stagger = pkt.paralyzation_max_point as i32;
-    //         if boss.id == pkt.object_id {
-    //             if current_stagger == max_stagger {
-    //                 let staggered_in =
-    //                     (timestamp - self.encounter.encounter_damage_stats.stagger_start) / 1000;
-    //                 self.stagger_intervals
-    //                     .push((staggered_in as i32, max_stagger))
-    //             } else if current_stagger != 0 && self.prev_stagger == 0 {
-    //                 self.encounter.encounter_damage_stats.stagger_start = timestamp;
-    //             }
-
-    //             self.prev_stagger = current_stagger;
-
-    //             let relative_timestamp_s = ((timestamp - self.encounter.fight_start) / 1000) as i32;
-    //             let stagger_percent = (1.0 - (current_stagger as f32 / max_stagger as f32)) * 100.0;
-    //             if let Some(last) = self.stagger_log.last_mut() {
-    //                 if last.0 == relative_timestamp_s {
-    //                     last.1 = stagger_percent;
-    //                 } else {
-    //                     self.stagger_log
-    //                         .push((relative_timestamp_s, stagger_percent));
-    //                 }
-    //             } else {
-    //                 self.stagger_log
-    //                     .push((relative_timestamp_s, stagger_percent));
-    //             }
-
-    //             if max_stagger > self.encounter.encounter_damage_stats.max_stagger {
-    //                 self.encounter.encounter_damage_stats.max_stagger = max_stagger;
-    //             }
-    //         }
-    //     }
-    // }
+    pub fn on_identity_gain(&mut self, entity: &Entity, gauges: (u32, u32, u32), timestamp: i64) {
+        if entity.entity_type != EntityType::Player {
+            return;
+        }
+
+        self.identity_tracker
+            .on_gauge_change(&entity.name, gauges, timestamp);
+    }
+
+    pub fn on_identity_stance(&mut self, entity: &Entity, stance: u8) {
+        if entity.entity_type != EntityType::Player {
+            return;
+        }
+
+        self.identity_tracker.on_stance_change(
+            &entity.name,
+            stance,
+            clock::now().timestamp_millis(),
+        );
+    }
+
+    pub fn on_trigger(&mut self, signal: i64) {
+        self.timeline.record(
+            self.encounter.fight_start,
+            clock::now().timestamp_millis(),
+            TimelineEventKind::Trigger,
+            None,
+            Some(signal),
+        );
+    }
+
+    pub fn on_stagger_change(&mut self, target_entity: &Entity, point: i64, max: i64) {
+        if self.encounter.fight_start == 0 || target_entity.entity_type != EntityType::Boss {
+            return;
+        }
+
+        let relative_timestamp = clock::now().timestamp_millis() - self.encounter.fight_start;
+        self.stagger_tracker
+            .on_stagger_change(target_entity, point, max, relative_timestamp);
+    }
 
     pub fn on_boss_shield(&mut self, target_entity: &Entity, shield: u64) {
         if target_entity.entity_type == EntityType::Boss
@@ -4456,9 +4635,28 @@ impl EncounterState {
         }
     }
 
+    pub fn record_inspect_snapshot(&mut self, name: String, snapshot: InspectSnapshot) {
+        self.inspect_snapshots.insert(name, snapshot);
+    }
+
     // track battle items used in an encounter
-    pub fn on_battle_item_use(&mut self, battle_item_id: &u32) {
-        if self.encounter.fight_start == 0 {}
+    pub fn on_battle_item_use(&mut self, source_entity: &Entity, item: BattleItemUse) {
+        if self.encounter.fight_start == 0 || source_entity.entity_type != EntityType::Player {
+            return;
+        }
+
+        let entity = self
+            .encounter
+            .entities
+            .entry(source_entity.name.clone())
+            .or_insert_with(|| encounter_entity_from_entity(source_entity));
+        // some items spawn several projectiles per throw
+        if entity.battle_items.last().is_some_and(|last| {
+            last.name == item.name && item.timestamp - last.timestamp < BATTLE_ITEM_REPEAT_WINDOW_MS
+        }) {
+            return;
+        }
+        entity.battle_items.push(item);
     }
 
     pub fn save_to_db(&mut self, manual: bool) {
@@ -4491,13 +4689,30 @@ impl EncounterState {
         let damage_log = self.damage_log.clone();
         let cast_log = self.cast_log.clone();
         let boss_hp_log = self.boss_hp_log.clone();
+        for entity in encounter
+            .entities
+            .values_mut()
+            .filter(|e| e.entity_type == EntityType::Player)
+        {
+            if let Some(identity_stats) = self.identity_tracker.identity_stats(
+                &entity.name,
+                entity.class_id,
+                encounter.fight_start,
+                encounter.last_combat_packet,
+            ) {
+                entity.skill_stats.identity_stats = Some(identity_stats);
+            }
+        }
+        encounter.encounter_damage_stats.stagger = self
+            .stagger_tracker
+            .summary(encounter.last_combat_packet - encounter.fight_start);
         let raid_clear = self.raid_clear;
         encounter.cleared = raid_clear;
         let party_info = self.party_info.clone();
         let raid_difficulty = self.raid_difficulty.clone();
         encounter.difficulty = raid_difficulty.clone().into();
         let region = self.region.clone();
-        let meter_version = self.app.app_handle().package_info().version.to_string();
+        let meter_version = self.save_context.meter_version.clone();
 
         self.set_ntp_fight_start_from_cache();
         let ntp_fight_start = self.ntp_fight_start;
@@ -4532,22 +4747,37 @@ impl EncounterState {
         }
 
         let contribution_splits = self.build_contribution_splits();
+        let timeline = self.timeline.events();
+        let inspect_snapshots = self
+            .inspect_snapshots
+            .iter()
+            .filter(|(name, _)| encounter.entities.contains_key(*name))
+            .filter_map(|(name, snapshot)| {
+                serde_json::to_value(snapshot)
+                    .inspect_err(|e| warn!("could not serialize inspect snapshot: {e}"))
+                    .ok()
+                    .map(|value| (name.clone(), value))
+            })
+            .collect();
 
-        let app = self.app.clone();
-        task::spawn(async move {
-            let stats_api = app.state::<StatsApi>();
-            let player_info =
-                if let Some(args) = GetCharacterInfoArgs::new(&encounter, &raid_difficulty) {
-                    info!("fetching player info");
+        let sink = self.sink.clone();
+        let SaveContext {
+            repository,
+            stats_api,
+            ..
+        } = self.save_context.clone();
+        let save_task = task::spawn(async move {
+            let player_info = if let Some(stats_api) = stats_api
+                && let Some(args) = GetCharacterInfoArgs::new(&encounter, &raid_difficulty)
+            {
+                info!("fetching player info");
 
-                    stats_api.get_character_info(args).await
-                } else {
-                    None
-                };
+                stats_api.get_character_info(args).await
+            } else {
+                None
+            };
 
             let _ = task::spawn_blocking(move || {
-                let repository = app.state::<Repository>();
-
                 let args = InsertEncounterArgs {
                     encounter,
                     damage_log,
@@ -4568,6 +4798,8 @@ impl EncounterState {
                     intermission_start,
                     intermission_end,
                     contribution_splits,
+                    inspect_snapshots,
+                    timeline,
                 };
 
                 let encounter_id = repository
@@ -4577,12 +4809,23 @@ impl EncounterState {
                 info!("saved to db");
 
                 if raid_clear {
-                    app.emit("clear-encounter", encounter_id)
-                        .expect("failed to emit clear-encounter");
+                    sink.emit("clear-encounter", encounter_id);
                 }
             })
             .await;
         });
+
+        self.save_tasks.retain(|task| !task.is_finished());
+        self.save_tasks.push(save_task);
+    }
+
+    /// Block until every encounter queued by `save_to_db` has been written.
+    pub fn wait_for_saves(&mut self, runtime: &Handle) {
+        for save_task in self.save_tasks.drain(..) {
+            if let Err(e) = runtime.block_on(save_task) {
+                warn!("encounter save task failed: {e}");
+            }
+        }
     }
 
     pub fn live_snapshot(&self, boss_dead: bool) -> Encounter {
@@ -4618,6 +4861,17 @@ impl EncounterState {
             .map(|(name, entity)| (name.clone(), Self::live_snapshot_entity(entity)))
             .collect();
 
+        let pace = current_boss
+            .as_ref()
+            .filter(|boss| boss.max_hp > 0 && self.encounter.fight_start > 0)
+            .and_then(|boss| {
+                self.pace_tracker.pace(
+                    &boss.name,
+                    boss.current_hp.max(0) as f32 / boss.max_hp as f32 * 100.0,
+                    self.encounter.last_combat_packet - self.encounter.fight_start,
+                )
+            });
+
         Encounter {
             last_combat_packet: self.encounter.last_combat_packet,
             fight_start: self.encounter.fight_start,
@@ -4646,6 +4900,9 @@ impl EncounterState {
                 unknown_buffs: Default::default(),
                 misc: self.encounter.encounter_damage_stats.misc.clone(),
                 boss_hp_log: HashMap::new(),
+                // stagger is streamed through stagger-update events instead
+                stagger: None,
+                pace,
             },
             duration: self.encounter.duration,
             difficulty: self.encounter.difficulty.clone(),
@@ -4685,6 +4942,7 @@ impl EncounterState {
             spec: entity.spec.clone(),
             loadout_hash: entity.loadout_hash.clone(),
             combat_power: entity.combat_power,
+            battle_items: entity.battle_items.clone(),
         }
     }
 
@@ -4693,6 +4951,7 @@ impl EncounterState {
             damage_dealt: stats.damage_dealt,
             hyper_awakening_damage: stats.hyper_awakening_damage,
             damage_taken: stats.damage_taken,
+            damage_taken_by: stats.damage_taken_by.clone(),
             buffed_by: stats.buffed_by.clone(),
             debuffed_by: stats.debuffed_by.clone(),
             buffed_by_support: stats.buffed_by_support,
@@ -4800,30 +5059,202 @@ fn status_effect_is_infinite(status_effect: &StatusEffectDetails) -> bool {
     status_effect.expiration_delay <= 0.0 || status_effect.expiration_delay > 3600.0
 }
 
-fn adjusted_extreme_aegir_difficulty(
-    boss_name: &str,
-    difficulty: &str,
-    max_hp: i64,
-) -> Option<&'static str> {
-    if boss_name != "Aegir, the Oppressor" || difficulty != "Extreme" {
-        return None;
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::DateTime;
+    use serde_json::json;
+
+    use super::{EncounterState, SaveContext};
+    use crate::data::preload_test_assets;
+    use crate::database::Database;
+    use crate::live::entity_tracker::Entity;
+    use crate::live::sink::RecordingSink;
+    use crate::live::status_tracker::StatusEffectDetails;
+    use crate::models::{
+        BattleItemUse, DamageTakenEvent, DamageTakenSource, EntityType, IncapacitatedEvent,
+        IncapacitationEventType, RaidDefinitions, RaidTrigger, TimelineEventKind,
+    };
+
+    fn recording_state() -> (EncounterState, Arc<RecordingSink>) {
+        preload_test_assets();
+        let sink = Arc::new(RecordingSink::default());
+        let database = Database::memory("0.0.0").unwrap();
+        let state = EncounterState::new(
+            sink.clone(),
+            SaveContext {
+                repository: database.create_repository(),
+                stats_api: None,
+                meter_version: "0.0.0".to_string(),
+            },
+        );
+        (state, sink)
     }
 
-    if max_hp > 4_500_000_000_000 {
-        Some("Extreme Nightmare")
-    } else if max_hp > 2_500_000_000_000 {
-        Some("Extreme Hard")
-    } else {
-        Some("Extreme Normal")
+    #[test]
+    fn emits_zone_and_phase_events_through_sink() {
+        let (mut state, sink) = recording_state();
+
+        state.on_transit(1);
+        state.on_phase_transition(1);
+
+        assert_eq!(
+            sink.take(),
+            vec![
+                ("zone-change".to_string(), json!("no-toast")),
+                ("phase-transition".to_string(), json!(1)),
+            ]
+        );
+        assert!(sink.events().is_empty());
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::adjusted_extreme_aegir_difficulty;
+    #[test]
+    fn records_battle_items_once_per_throw_during_fights() {
+        let (mut state, _) = recording_state();
+        let player = Entity {
+            id: 1,
+            name: "Alpha".to_string(),
+            entity_type: EntityType::Player,
+            ..Default::default()
+        };
+        let item = |name: &str, timestamp| BattleItemUse {
+            item_id: None,
+            name: name.to_string(),
+            timestamp,
+        };
+
+        state.on_battle_item_use(&player, item("Flare", 500));
+        state.encounter.fight_start = 1_000;
+        state.on_battle_item_use(&player, item("Dark Grenade", 2_000));
+        state.on_battle_item_use(&player, item("Dark Grenade", 2_100));
+        state.on_battle_item_use(&player, item("Dark Grenade", 9_000));
+
+        assert_eq!(
+            state.encounter.entities["Alpha"].battle_items,
+            vec![item("Dark Grenade", 2_000), item("Dark Grenade", 9_000)]
+        );
+    }
+
+    #[test]
+    fn death_keeps_last_hits_and_running_incapacitations() {
+        let (mut state, _) = recording_state();
+        let player = Entity {
+            id: 1,
+            name: "Alpha".to_string(),
+            entity_type: EntityType::Player,
+            ..Default::default()
+        };
+        let hit = |timestamp| DamageTakenEvent {
+            timestamp,
+            source: "Boss".to_string(),
+            skill_id: 7,
+            skill_name: "Slam".to_string(),
+            damage: 100,
+            current_hp: 0,
+        };
+        let incapacitation = |duration| IncapacitatedEvent {
+            event_type: IncapacitationEventType::FALL_DOWN,
+            timestamp: 0,
+            duration,
+        };
+
+        state.recent_damage_taken.insert(
+            "Alpha".to_string(),
+            [hit(1_000), hit(2_000)].into_iter().collect(),
+        );
+        state.on_death(&player);
+        state
+            .encounter
+            .entities
+            .get_mut("Alpha")
+            .unwrap()
+            .damage_stats
+            .incapacitations = vec![incapacitation(1), incapacitation(i64::MAX / 2)];
+        state.on_death(&player);
+
+        let deaths = state.encounter.entities["Alpha"]
+            .damage_stats
+            .death_info
+            .clone()
+            .unwrap();
+        assert_eq!(deaths[0].recap, vec![hit(1_000), hit(2_000)]);
+        assert!(deaths[0].incapacitations.is_empty());
+        assert!(deaths[1].recap.is_empty());
+        assert_eq!(
+            deaths[1].incapacitations,
+            vec![incapacitation(i64::MAX / 2)]
+        );
+    }
+
+    #[test]
+    fn shortened_crowd_control_updates_its_timeline_event() {
+        let (mut state, _) = recording_state();
+        let player = Entity {
+            id: 1,
+            name: "Alpha".to_string(),
+            entity_type: EntityType::Player,
+            ..Default::default()
+        };
+        let status_effect = StatusEffectDetails {
+            expiration_delay: 5.0,
+            timestamp: DateTime::from_timestamp_millis(3_000).unwrap(),
+            ..Default::default()
+        };
+
+        state.encounter.fight_start = 1_000;
+        state.on_cc_applied(&player, &status_effect);
+        state.on_cc_removed(&player, &status_effect, 4_500);
+
+        let events = state.timeline.events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, TimelineEventKind::CrowdControl);
+        assert_eq!(events[0].time, 2_000);
+        assert_eq!(events[0].value, Some(1_500));
+        assert_eq!(
+            state.encounter.entities["Alpha"]
+                .damage_stats
+                .incapacitations[0]
+                .duration,
+            1_500
+        );
+    }
+
+    #[test]
+    fn merges_damage_taken_by_source_and_skill() {
+        let source = |name: &str, skill_id, damage| DamageTakenSource {
+            source: name.to_string(),
+            skill_id,
+            skill_name: String::new(),
+            damage,
+            hits: 1,
+        };
+        let mut sources = Vec::new();
+        EncounterState::add_damage_taken_source(&mut sources, source("Boss", 1, 10));
+        EncounterState::add_damage_taken_source(&mut sources, source("Boss", 2, 20));
+        EncounterState::add_damage_taken_source(&mut sources, source("Add", 1, 5));
+        EncounterState::add_damage_taken_source(&mut sources, source("Boss", 1, 30));
+
+        assert_eq!(
+            sources,
+            vec![
+                DamageTakenSource {
+                    hits: 2,
+                    ..source("Boss", 1, 40)
+                },
+                source("Boss", 2, 20),
+                source("Add", 1, 5),
+            ]
+        );
+    }
+
+    fn raid_definitions() -> RaidDefinitions {
+        serde_json::from_str(include_str!("../../meter-data/raids.json")).unwrap()
+    }
 
     #[test]
     fn adjusts_extreme_aegir_difficulty_from_boss_hp() {
+        let definitions = raid_definitions();
         let cases = [
             (4_500_000_000_001, "Extreme Nightmare"),
             (4_500_000_000_000, "Extreme Hard"),
@@ -4833,7 +5264,7 @@ mod tests {
 
         for (max_hp, expected) in cases {
             assert_eq!(
-                adjusted_extreme_aegir_difficulty("Aegir, the Oppressor", "Extreme", max_hp),
+                definitions.adjusted_difficulty("Aegir, the Oppressor", "Extreme", max_hp),
                 Some(expected)
             );
         }
@@ -4842,7 +5273,7 @@ mod tests {
     #[test]
     fn does_not_adjust_other_bosses_or_difficulties() {
         assert_eq!(
-            adjusted_extreme_aegir_difficulty(
+            raid_definitions().adjusted_difficulty(
                 "Aegir, the Oppressor",
                 "Extreme Hard",
                 5_000_000_000_000
@@ -4850,4 +5281,43 @@ mod tests {
             None
         );
     }
+
+    #[test]
+    fn resolves_difficulties_and_triggers_from_raid_definitions() {
+        let definitions = raid_definitions();
+        let difficulty = |zone_id, zone_level| {
+            definitions
+                .zone_difficulty(zone_id, zone_level)
+                .map(|difficulty| (difficulty.name.as_str(), difficulty.id))
+        };
+
+        assert_eq!(difficulty(1, 2), Some(("Nightmare", 2)));
+        assert_eq!(difficulty(1, 4), Some(("Solo", 4)));
+        assert_eq!(difficulty(37551, 4), Some(("Matching", 4)));
+        assert_eq!(difficulty(37561, 2), Some(("Level 3", 11)));
+        assert_eq!(difficulty(37562, 3), None);
+        assert_eq!(
+            definitions
+                .raid_difficulty(308417)
+                .map(|difficulty| (difficulty.name.as_str(), difficulty.id)),
+            Some(("Challenge", 8))
+        );
+        assert!(definitions.raid_difficulty(1).is_none());
+        assert_eq!(definitions.trigger(74), Some(RaidTrigger::Clear));
+        assert_eq!(definitions.trigger(77), Some(RaidTrigger::Wipe));
+        assert_eq!(definitions.trigger(27), None);
+        assert!(
+            definitions
+                .intermission(37545)
+                .is_some_and(|intermission| intermission.clears_raid("The First")
+                    && !intermission.clears_raid("Hard"))
+        );
+        assert!(definitions.ends_intermission("Death Incarnate Kazeros"));
+        assert!(!definitions.ends_intermission("Aegir, the Oppressor"));
+        assert_eq!(
+            definitions.hp_gate("Phantom Legion Commander Brelshaza", 100_000_000_001),
+            Some("Act 2: Brelshaza G2")
+        );
+        assert_eq!(definitions.gate_position("Aegir G2"), Some(("Aegir", 1)));
+    }
 }
@@ -1,4 +1,5 @@
 use crate::data::*;
+use crate::live::clock;
 use crate::live::id_tracker::IdTracker;
 use crate::live::inspect_stats::{InspectDerivedStats, derive_inspect_stats};
 use crate::live::party_tracker::PartyTracker;
@@ -18,6 +19,7 @@ use chrono::{DateTime, Utc};
 use hashbrown::{HashMap, HashSet};
 use log::{info, warn};
 use meter_defs::defs::*;
+use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
 use std::rc::Rc;
 use std::sync::Arc;
@@ -60,9 +62,11 @@ pub struct EntityTracker {
 pub struct AppliedInspectResult {
     pub name: String,
     pub info: InspectInfo,
+    pub snapshot: InspectSnapshot,
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 pub struct InspectSnapshot {
     pub gear_level: f32,
     pub stat_pairs: HashMap<u8, i64>,
@@ -77,20 +81,23 @@ pub struct InspectSnapshot {
     pub ark_passive_data: Option<ArkPassiveData>,
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 pub struct InspectAddonValue {
     pub addon_type: u8,
     pub value: u32,
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 pub struct InspectEngraving {
     pub id: u32,
     pub unknown: u16,
     pub level: u16,
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 pub struct InspectItemSnapshot {
     pub unique_id: Option<u64>,
     pub raw_item_id: Option<u32>,
@@ -101,27 +108,31 @@ pub struct InspectItemSnapshot {
     pub has_ark_grid_gem_data: bool,
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 pub struct InspectCardSnapshot {
     pub id: u32,
     pub awakening_level: u32,
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 pub struct InspectStigmaLayoutSnapshot {
     pub stigma_id: u32,
     pub stigma_level: u32,
     pub stigma_rank: u32,
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 pub struct InspectArkGridCoreSnapshot {
     pub core_id: u32,
     pub base_id: u32,
     pub options: Vec<InspectArkGridCoreOptionSnapshot>,
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 pub struct InspectArkGridCoreOptionSnapshot {
     pub willpower_rank: u32,
     pub item_id: u32,
@@ -131,7 +142,8 @@ pub struct InspectArkGridCoreOptionSnapshot {
     pub values: Vec<InspectArkGridCoreValueSnapshot>,
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 pub struct InspectArkGridCoreValueSnapshot {
     pub option_id: u32,
     pub rank: u32,
@@ -482,7 +494,7 @@ impl EntityTracker {
         } else {
             (pc_struct.player_id, StatusEffectTargetType::Local)
         };
-        let timestamp = Utc::now();
+        let timestamp = clock::now();
         for sed in &pc_struct.status_effect_datas {
             let source_entity = self.resolve_status_effect_source_entity(sed);
             let status_effect = self.build_status_effect_with_snapshots(
@@ -569,7 +581,7 @@ impl EntityTracker {
         pkt: PKTPartyStatusEffectAddNotify,
         entities: &HashMap<String, EncounterEntity>,
     ) -> Vec<StatusEffectDetails> {
-        let timestamp = Utc::now();
+        let timestamp = clock::now();
         let mut shields: Vec<StatusEffectDetails> = Vec::new();
         let (target_id, target_type) = if pkt.character_id != 0
             && !self.should_use_party_status_effect_for_character(pkt.character_id)
@@ -802,7 +814,7 @@ impl EntityTracker {
     }
 
     pub fn collect_missing_party_inspects(&mut self, bootstrap_active: bool) -> Vec<String> {
-        let now = Utc::now().timestamp_millis();
+        let now = clock::now().timestamp_millis();
         let local_character_id = self.get_local_character_id();
         let forced_refresh_names = self.forced_refresh_names.clone();
         let bootstrap_refresh_sent_names = self.bootstrap_refresh_sent_names.clone();
@@ -1147,7 +1159,11 @@ impl EntityTracker {
         }
 
         self.forced_refresh_names.remove(&name);
-        Some(AppliedInspectResult { name, info })
+        Some(AppliedInspectResult {
+            name,
+            info,
+            snapshot,
+        })
     }
 
     pub fn get_local_character_id(&self) -> u64 {
@@ -1880,7 +1896,7 @@ impl EntityTracker {
     }
 
     fn build_and_register_status_effects(&mut self, seds: Vec<StatusEffectData>, target_id: u64) {
-        let timestamp = Utc::now();
+        let timestamp = clock::now();
         for sed in seds.into_iter() {
             self.build_and_register_status_effect(&sed, target_id, timestamp, None);
         }
@@ -0,0 +1,267 @@
+use crate::models::{IdentityArcanist, IdentityArtistBard, IdentityGeneric, IdentityLog};
+use hashbrown::HashMap;
+
+const ARCANIST_CLASS_ID: u32 = 202;
+const BARD_CLASS_ID: u32 = 204;
+const ARTIST_CLASS_ID: u32 = 602;
+// identity gauges are sent in hundredths of a percent
+const MAX_IDENTITY_GAUGE: u32 = 10_000;
+
+#[derive(Debug, Default)]
+struct PlayerIdentity {
+    gauges: IdentityLog,
+    stances: Vec<(i64, u8)>,
+}
+
+/// Records identity gauge and stance changes per player and turns them into the
+/// class specific `identity_stats` saved with each player.
+#[derive(Debug, Default)]
+pub struct IdentityTracker {
+    players: HashMap<String, PlayerIdentity>,
+}
+
+impl IdentityTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn on_gauge_change(&mut self, name: &str, gauges: (u32, u32, u32), timestamp: i64) {
+        let player = self.players.entry_ref(name).or_default();
+        if player
+            .gauges
+            .last()
+            .is_some_and(|(_, last)| *last == gauges)
+        {
+            return;
+        }
+        player.gauges.push((timestamp, gauges));
+    }
+
+    pub fn on_stance_change(&mut self, name: &str, stance: u8, timestamp: i64) {
+        let player = self.players.entry_ref(name).or_default();
+        if player
+            .stances
+            .last()
+            .is_some_and(|(_, last)| *last == stance)
+        {
+            return;
+        }
+        player.stances.push((timestamp, stance));
+    }
+
+    /// Drops the history but keeps the last known gauge and stance of every player,
+    /// the next fight starts from them.
+    pub fn reset(&mut self) {
+        for player in self.players.values_mut() {
+            player.gauges.drain(..player.gauges.len().saturating_sub(1));
+            player
+                .stances
+                .drain(..player.stances.len().saturating_sub(1));
+        }
+    }
+
+    /// Serialized identity stats of a player between `fight_start` and `fight_end`.
+    pub fn identity_stats(
+        &self,
+        name: &str,
+        class_id: u32,
+        fight_start: i64,
+        fight_end: i64,
+    ) -> Option<String> {
+        let player = self.players.get(name)?;
+        let fight_end = fight_end.max(fight_start + 1);
+        let gauges = segments(&player.gauges, fight_start, fight_end);
+        let stances = segments(&player.stances, fight_start, fight_end);
+        let duration = (fight_end - fight_start) as f64;
+        let uptime = |active: &dyn Fn(&(i64, i64, (u32, u32, u32))) -> bool| {
+            gauges
+                .iter()
+                .filter(|segment| active(segment))
+                .map(|(from, to, _)| to - from)
+                .sum::<i64>() as f64
+                / duration
+        };
+
+        let stats = match class_id {
+            ARCANIST_CLASS_ID if !gauges.is_empty() => {
+                let mut card_draws: HashMap<u32, u32> = HashMap::new();
+                let mut cards_used = 0;
+                for pair in gauges.windows(2) {
+                    let (_, _, (_, before1, before2)) = pair[0];
+                    let (_, _, (_, after1, after2)) = pair[1];
+                    let (drawn, used) = card_changes([before1, before2], [after1, after2]);
+                    for card in drawn {
+                        *card_draws.entry(card).or_default() += 1;
+                    }
+                    cards_used += used;
+                }
+
+                serde_json::to_string(&IdentityArcanist {
+                    log: per_second(&gauges, fight_start, |(gauge, card1, card2)| {
+                        (percentage(gauge), card1, card2)
+                    }),
+                    average: average_gain(&gauges, duration),
+                    card_draws,
+                    cards_used,
+                    uptime: uptime(&|(_, _, (_, card1, card2))| *card1 != 0 || *card2 != 0),
+                })
+            }
+            BARD_CLASS_ID | ARTIST_CLASS_ID if !gauges.is_empty() => {
+                let bubbles_used = gauges
+                    .windows(2)
+                    .map(|pair| pair[0].2.1.saturating_sub(pair[1].2.1))
+                    .sum();
+
+                serde_json::to_string(&IdentityArtistBard {
+                    log: per_second(&gauges, fight_start, |(gauge, bubbles, _)| {
+                        (percentage(gauge), bubbles)
+                    }),
+                    average: average_gain(&gauges, duration),
+                    bubbles_used,
+                    uptime: uptime(&|(_, _, (_, bubbles, _))| *bubbles > 0),
+                })
+            }
+            ARCANIST_CLASS_ID | BARD_CLASS_ID | ARTIST_CLASS_ID => return None,
+            _ if gauges.is_empty() && stances.is_empty() => return None,
+            _ => {
+                let mut stance_durations: HashMap<u8, i64> = HashMap::new();
+                for (from, to, stance) in stances.iter().filter(|(_, _, stance)| *stance != 0) {
+                    *stance_durations.entry(*stance).or_default() += to - from;
+                }
+                let uptime = if stances.is_empty() {
+                    uptime(&|(_, _, (gauge, _, _))| *gauge >= MAX_IDENTITY_GAUGE)
+                } else {
+                    stance_durations.values().sum::<i64>() as f64 / duration
+                };
+
+                serde_json::to_string(&IdentityGeneric {
+                    log: per_second(&gauges, fight_start, |(gauge, _, _)| percentage(gauge)),
+                    average: average_gain(&gauges, duration),
+                    stance_log: per_second(&stances, fight_start, |stance| stance),
+                    stance_durations,
+                    uptime,
+                })
+            }
+        };
+
+        stats.ok()
+    }
+}
+
+/// Splits `events` into `(from, to, value)` spans covering `start..end`, starting with
+/// the value the player already had at `start`.
+fn segments<T: Copy>(events: &[(i64, T)], start: i64, end: i64) -> Vec<(i64, i64, T)> {
+    let first = events.partition_point(|(timestamp, _)| *timestamp <= start);
+    let mut current = first.checked_sub(1).map(|index| (start, events[index].1));
+    let mut segments = Vec::new();
+
+    for &(timestamp, value) in events[first..]
+        .iter()
+        .take_while(|(timestamp, _)| *timestamp < end)
+    {
+        if let Some((from, previous)) = current {
+            segments.push((from, timestamp, previous));
+        }
+        current = Some((timestamp, value));
+    }
+    if let Some((from, value)) = current {
+        segments.push((from, end, value));
+    }
+
+    segments
+}
+
+// keeps the last value of every second
+fn per_second<T: Copy, U>(
+    segments: &[(i64, i64, T)],
+    fight_start: i64,
+    map: impl Fn(T) -> U,
+) -> Vec<(i32, U)> {
+    let mut log: Vec<(i32, U)> = Vec::with_capacity(segments.len());
+    for &(from, _, value) in segments {
+        let time = ((from - fight_start) / 1000) as i32;
+        match log.last_mut() {
+            Some(last) if last.0 == time => last.1 = map(value),
+            _ => log.push((time, map(value))),
+        }
+    }
+    log
+}
+
+fn percentage(gauge: u32) -> f32 {
+    (gauge.min(MAX_IDENTITY_GAUGE) as f32 / MAX_IDENTITY_GAUGE as f32 * 10_000.0).round() / 100.0
+}
+
+// identity gained per second, in percent
+fn average_gain(segments: &[(i64, i64, (u32, u32, u32))], duration_ms: f64) -> f64 {
+    let gained: u32 = segments
+        .windows(2)
+        .map(|pair| pair[1].2.0.saturating_sub(pair[0].2.0))
+        .sum();
+    gained as f64 / 100.0 / (duration_ms / 1000.0)
+}
+
+// cards that appeared and how many disappeared, empty slots are 0
+fn card_changes(before: [u32; 2], after: [u32; 2]) -> (Vec<u32>, u32) {
+    let mut remaining: Vec<u32> = before.into_iter().filter(|card| *card != 0).collect();
+    let mut drawn = Vec::new();
+    for card in after.into_iter().filter(|card| *card != 0) {
+        match remaining.iter().position(|held| *held == card) {
+            Some(index) => {
+                remaining.swap_remove(index);
+            }
+            None => drawn.push(card),
+        }
+    }
+    (drawn, remaining.len() as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arcanist_counts_draws_and_used_cards() {
+        let mut tracker = IdentityTracker::new();
+        tracker.on_gauge_change("Arc", (5_000, 0, 0), 500);
+        tracker.on_gauge_change("Arc", (10_000, 0, 0), 2_000);
+        tracker.on_gauge_change("Arc", (0, 7, 0), 3_000);
+        tracker.on_gauge_change("Arc", (0, 7, 9), 4_000);
+        tracker.on_gauge_change("Arc", (0, 9, 0), 6_000);
+        tracker.on_gauge_change("Arc", (0, 0, 0), 7_000);
+
+        let stats = tracker
+            .identity_stats("Arc", ARCANIST_CLASS_ID, 1_000, 11_000)
+            .unwrap();
+        let stats: IdentityArcanist = serde_json::from_str(&stats).unwrap();
+
+        assert_eq!(stats.log[0], (0, (50.0, 0, 0)));
+        assert_eq!(stats.card_draws, HashMap::from([(7, 1), (9, 1)]));
+        assert_eq!(stats.cards_used, 2);
+        assert!((stats.uptime - 0.4).abs() < 1e-9);
+        assert!((stats.average - 5.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn stances_are_held_across_resets() {
+        let mut tracker = IdentityTracker::new();
+        tracker.on_stance_change("Glaivier", 1, 0);
+        tracker.reset();
+        tracker.on_stance_change("Glaivier", 2, 4_000);
+        tracker.on_stance_change("Glaivier", 0, 7_000);
+
+        let stats = tracker
+            .identity_stats("Glaivier", 0, 2_000, 12_000)
+            .unwrap();
+        let stats: IdentityGeneric = serde_json::from_str(&stats).unwrap();
+
+        assert_eq!(stats.stance_log, vec![(0, 1), (2, 2), (5, 0)]);
+        assert_eq!(
+            stats.stance_durations,
+            HashMap::from([(1, 2_000), (2, 3_000)])
+        );
+        assert!((stats.uptime - 0.5).abs() < 1e-9);
+        assert!(stats.log.is_empty());
+        assert!(tracker.identity_stats("Nobody", 0, 0, 1_000).is_none());
+    }
+}
@@ -11,6 +11,7 @@ use crate::models::ExternalResourceAddon;
 
 use hashbrown::HashMap;
 use meter_defs::defs::{ItemData, PKTPCInspectResult};
+use serde::{Deserialize, Serialize};
 use std::sync::OnceLock;
 
 const DEFAULT_ARK_PASSIVE_KARMA_EVOLUTION_DAMAGE: f64 = 0.06;
@@ -40,14 +41,16 @@ struct GemLayoutOffsets {
 
 static GEM_LAYOUT_OFFSETS: OnceLock<GemLayoutOffsets> = OnceLock::new();
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 pub struct DerivedAbilityFeature {
     pub feature_type: String,
     pub level: u32,
     pub values: Vec<i64>,
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 pub struct InspectDerivedStats {
     pub stat_pairs: HashMap<u8, i64>,
     pub ally_attack_power_power: f64,
@@ -61,6 +64,7 @@ pub struct InspectDerivedStats {
     pub buff_id_ownership: Vec<u32>,
     pub buff_unique_group_ownership: Vec<u32>,
     pub deferred_addons: Vec<ExternalResourceAddon>,
+    #[serde(skip)]
     pub item_build_debug: Vec<InspectItemBuildDebug>,
 }
 
@@ -398,38 +402,120 @@ fn apply_ark_passives(
     result: &PKTPCInspectResult,
     raw_stat_pairs: &HashMap<u8, i64>,
     derived: &mut InspectDerivedStats,
+) {
+    let nodes = result
+        .ark_passive_tree_data_inspect
+        .ark_passive_node_datas
+        .iter()
+        .flatten()
+        .map(|node| (node.ark_passive_id, node.points.unwrap_or(1)));
+    apply_ark_passive_nodes(nodes, raw_stat_pairs, derived);
+}
+
+/// Swaps what the ark passive nodes `from` added to `derived` for what the nodes `to` add, both
+/// given as `(id, level)` and applied the same way [`derive_inspect_stats`] applies an inspected
+/// tree. Pass whole trees, features shared by unchanged nodes are removed and added back.
+pub fn replace_ark_passive_nodes(
+    derived: &mut InspectDerivedStats,
+    from: &[(u32, u32)],
+    to: &[(u32, u32)],
+    raw_stat_pairs: &HashMap<u8, i64>,
+) {
+    let mut removed = InspectDerivedStats::default();
+    apply_ark_passive_nodes(from.iter().copied(), raw_stat_pairs, &mut removed);
+    let mut added = InspectDerivedStats::default();
+    apply_ark_passive_nodes(to.iter().copied(), raw_stat_pairs, &mut added);
+
+    for (stat_id, value) in removed.stat_pairs {
+        *derived.stat_pairs.entry(stat_id).or_default() -= value;
+    }
+    for (stat_id, value) in added.stat_pairs {
+        *derived.stat_pairs.entry(stat_id).or_default() += value;
+    }
+    derived.ally_attack_power_power +=
+        added.ally_attack_power_power - removed.ally_attack_power_power;
+    derived.ally_identity_damage_power +=
+        added.ally_identity_damage_power - removed.ally_identity_damage_power;
+    derived.ally_brand_power += added.ally_brand_power - removed.ally_brand_power;
+    for (multipliers, removed, added) in [
+        (
+            &mut derived.skill_attack_power_multiplier_by_skill,
+            removed.skill_attack_power_multiplier_by_skill,
+            added.skill_attack_power_multiplier_by_skill,
+        ),
+        (
+            &mut derived.skill_status_effect_multiplier_by_skill,
+            removed.skill_status_effect_multiplier_by_skill,
+            added.skill_status_effect_multiplier_by_skill,
+        ),
+        (
+            &mut derived.skill_group_status_effect_multiplier_by_group,
+            removed.skill_group_status_effect_multiplier_by_group,
+            added.skill_group_status_effect_multiplier_by_group,
+        ),
+    ] {
+        for (key, value) in removed {
+            *multipliers.entry(key).or_default() -= value;
+        }
+        for (key, value) in added {
+            *multipliers.entry(key).or_default() += value;
+        }
+    }
+
+    derived.ability_features.retain(|feature| {
+        !removed.ability_features.iter().any(|removed| {
+            removed.feature_type == feature.feature_type && removed.level == feature.level
+        })
+    });
+    for feature in &added.ability_features {
+        add_ability_feature(
+            &feature.feature_type,
+            feature.level,
+            &feature.values,
+            derived,
+        );
+    }
+    derived
+        .buff_id_ownership
+        .retain(|buff_id| !removed.buff_id_ownership.contains(buff_id));
+    for buff_id in added.buff_id_ownership {
+        push_unique_u32(&mut derived.buff_id_ownership, buff_id);
+    }
+}
+
+fn apply_ark_passive_nodes(
+    nodes: impl IntoIterator<Item = (u32, u32)>,
+    raw_stat_pairs: &HashMap<u8, i64>,
+    derived: &mut InspectDerivedStats,
 ) {
     let mut goddess_blessing_level = 0u32;
     let mut goddess_blessing_values: Option<Vec<i64>> = None;
-    for section in &result.ark_passive_tree_data_inspect.ark_passive_node_datas {
-        for node in section {
-            let Some(passive) = EXTERNAL_ARK_PASSIVE_DATA.get(&node.ark_passive_id) else {
-                continue;
-            };
-            let level = node.points.unwrap_or(1);
-            if let Some(level_data) = passive.levels.get(&level) {
-                for addon in &level_data.addons {
-                    if addon.addon_type == "ability_feature"
-                        && let Some(ability) = EXTERNAL_ABILITY_DATA.get(&addon.key_index)
-                        && let Some(ability_level) = ability.levels.get(&(addon.key_value as u32))
+    for (ark_passive_id, level) in nodes {
+        let Some(passive) = EXTERNAL_ARK_PASSIVE_DATA.get(&ark_passive_id) else {
+            continue;
+        };
+        if let Some(level_data) = passive.levels.get(&level) {
+            for addon in &level_data.addons {
+                if addon.addon_type == "ability_feature"
+                    && let Some(ability) = EXTERNAL_ABILITY_DATA.get(&addon.key_index)
+                    && let Some(ability_level) = ability.levels.get(&(addon.key_value as u32))
+                {
+                    add_ability_feature(
+                        &ability.feature_type,
+                        addon.key_value as u32,
+                        &ability_level.values,
+                        derived,
+                    );
+                    if ability
+                        .feature_type
+                        .eq_ignore_ascii_case("goddess_blessing")
+                        && (addon.key_value as u32) > goddess_blessing_level
                     {
-                        add_ability_feature(
-                            &ability.feature_type,
-                            addon.key_value as u32,
-                            &ability_level.values,
-                            derived,
-                        );
-                        if ability
-                            .feature_type
-                            .eq_ignore_ascii_case("goddess_blessing")
-                            && (addon.key_value as u32) > goddess_blessing_level
-                        {
-                            goddess_blessing_level = addon.key_value as u32;
-                            goddess_blessing_values = Some(ability_level.values.clone());
-                        }
+                        goddess_blessing_level = addon.key_value as u32;
+                        goddess_blessing_values = Some(ability_level.values.clone());
                     }
-                    apply_external_addon(addon, raw_stat_pairs, derived);
                 }
+                apply_external_addon(addon, raw_stat_pairs, derived);
             }
         }
     }
@@ -14,7 +14,7 @@ pub(super) enum Command {
 }
 
 pub struct EventManager {
-    app_handle: AppHandle,
+    app_handle: Option<AppHandle>,
     subscriptions: Mutex<Vec<EventId>>,
     command_tx: UnboundedSender<Command>,
     boss_only_damage: AtomicBool,
@@ -22,7 +22,8 @@ pub struct EventManager {
 }
 
 impl EventManager {
-    pub fn new(app_handle: AppHandle, command_tx: UnboundedSender<Command>) -> Arc<Self> {
+    /// Without an app handle no frontend requests are received, which is what headless runs want.
+    pub fn new(app_handle: Option<AppHandle>, command_tx: UnboundedSender<Command>) -> Arc<Self> {
         let boss_only_damage = AtomicBool::new(true);
         let emit_details = AtomicBool::new(false);
 
@@ -34,6 +35,10 @@ impl EventManager {
             emit_details,
         });
 
+        let Some(app_handle) = app_handle else {
+            return listener;
+        };
+
         let mut subscriptions = vec![];
         let id = app_handle.listen_any("reset-request", Self::on_reset(listener.clone()));
         subscriptions.push(id);
@@ -65,7 +70,9 @@ impl EventManager {
                 return;
             }
             info!("resetting meter");
-            context.app_handle.emit("reset-encounter", "").unwrap();
+            if let Some(app_handle) = &context.app_handle {
+                app_handle.emit("reset-encounter", "").unwrap();
+            }
         }
     }
 
@@ -118,8 +125,11 @@ impl EventManager {
 
 impl Drop for EventManager {
     fn drop(&mut self) {
+        let Some(app_handle) = &self.app_handle else {
+            return;
+        };
         for subscription in self.subscriptions.lock().unwrap().drain(..) {
-            self.app_handle.unlisten(subscription);
+            app_handle.unlisten(subscription);
         }
     }
 }
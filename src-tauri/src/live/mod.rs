@@ -1,30 +1,44 @@
 mod addon_type;
+pub mod capture;
+mod clock;
 mod encounter_state;
 mod entity_tracker;
 mod id_tracker;
+mod identity_tracker;
 mod inspect_stats;
 mod manager;
+mod pace;
 mod party_tracker;
 mod player_stats;
 mod rdps;
+pub mod sink;
 mod skill_tracker;
+mod stagger_tracker;
 mod stat_type;
 mod status_tracker;
+mod timeline;
 mod utils;
+pub mod websocket;
+pub(crate) mod what_if;
 
 use crate::api::{BanList, HeartBeatApi};
+use crate::data::RAID_DEFINITIONS;
 use crate::database::utils::apply_player_info;
+use crate::live::capture::{CaptureRecorder, ReplayClock};
+use crate::live::clock::{Stopwatch, Tick};
 use crate::live::encounter_state::EncounterState;
 use crate::live::entity_tracker::{EntityTracker, get_current_and_max_hp};
 use crate::live::id_tracker::IdTracker;
 use crate::live::manager::{Command, EventManager};
 use crate::live::party_tracker::PartyTracker;
+use crate::live::sink::EventSink;
 use crate::live::status_tracker::{
     StatusEffectDetails, StatusEffectTargetType, StatusEffectType, StatusTracker,
     get_status_effect_value,
 };
+use crate::live::utils::{battle_item_from_item_id, battle_item_from_skill_effect};
 use crate::local::{LocalInfo, LocalPlayer, LocalPlayerRepository};
-use crate::models::{DamageData, EntityType, Identity, TripodIndex};
+use crate::models::{DamageData, EntityType, Identity, RaidTrigger, Stagger, TripodIndex};
 use crate::nineveh::NinevehIPCPair;
 use crate::settings::Settings;
 use crate::utils::{get_class_from_id, is_confirmed_player_entity, is_support_class};
@@ -40,14 +54,17 @@ use nineveh_formats::ipc::{
 use serde::Serialize;
 use std::cell::RefCell;
 use std::rc::Rc;
+use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::time::{Duration, Instant};
-use tauri::{AppHandle, Emitter, Manager};
+use tauri::{AppHandle, Manager};
 use tokio::runtime::Handle;
 use tokio::sync::mpsc::UnboundedReceiver;
 
 use crate::context::AppContext;
 
+pub use crate::live::encounter_state::SaveContext;
+
 // Flip these only when debugging live inspect / attribution issues.
 pub(crate) const DEBUG_TRACE_INSPECT_PACKETS: bool = true;
 pub(crate) const DEBUG_DUMP_DAMAGE_STATE_JSON: bool = false;
@@ -64,14 +81,22 @@ fn set_compute_stat_damage_metrics(enabled: bool) {
 }
 
 pub struct StartArgs {
-    pub app: AppHandle,
+    /// present when running inside the Tauri app, used for frontend commands and region updates
+    pub app: Option<AppHandle>,
+    pub sink: Arc<dyn EventSink>,
+    pub save_context: SaveContext,
     pub ipc: NinevehIPCPair,
     pub runtime: Handle,
     pub settings: Option<Settings>,
+    /// initial region, kept in sync with the app context when `app` is set
+    pub region: Option<String>,
     pub local_info: LocalInfo,
     pub local_player_repository: LocalPlayerRepository,
-    pub heartbeat_api: HeartBeatApi,
+    pub heartbeat_api: Option<HeartBeatApi>,
     pub ban_list: BanList,
+    pub capture: Option<CaptureRecorder>,
+    /// set when `ipc` replays a capture, pins the parser clock to the recorded packet times
+    pub replay_clock: Option<ReplayClock>,
 }
 
 enum LiveEvent {
@@ -97,13 +122,18 @@ pub fn start(args: StartArgs) -> Result<()> {
     info!("live::start");
     let StartArgs {
         app,
+        sink,
+        save_context,
         mut ipc,
         runtime,
         settings,
+        region,
         mut local_info,
         local_player_repository,
         mut heartbeat_api,
         mut ban_list,
+        mut capture,
+        replay_clock,
     } = args;
     let (command_tx, mut command_rx) = tokio::sync::mpsc::unbounded_channel();
     let manager = EventManager::new(app.clone(), command_tx);
@@ -115,17 +145,20 @@ pub fn start(args: StartArgs) -> Result<()> {
         id_tracker.clone(),
         party_tracker.clone(),
     );
-    let mut state = EncounterState::new(app.clone());
+    let mut state = EncounterState::new(sink.clone(), save_context);
+    state.region = region.clone();
+    state.encounter.region = region;
 
     let mut damage_handler = meter_decryption::DamageEncryptionHandler::new();
 
-    let mut last_update = Instant::now();
+    let mut last_update = Stopwatch::default();
     let mut duration = Duration::from_millis(200);
-    let mut last_party_update = Instant::now();
+    let mut last_party_update = Stopwatch::default();
     let party_duration = Duration::from_millis(2000);
-    let mut last_inspect_queue_scan = Instant::now();
+    let mut last_inspect_queue_scan = Stopwatch::default();
     let inspect_queue_scan_duration = Duration::from_millis(200);
-    let mut raid_end_cd = Instant::now();
+    // replays set it on the first event so they count from the recorded time
+    let mut raid_end_cd: Option<Tick> = replay_clock.is_none().then(clock::tick);
     let mut startup_event_seq: i64 = 0;
 
     if let Some(settings) = settings {
@@ -142,7 +175,7 @@ pub fn start(args: StartArgs) -> Result<()> {
         info!("no settings found, using defaults");
     }
 
-    get_and_set_region(&app, &mut state, &mut ban_list);
+    get_and_set_region(app.as_ref(), &mut state, &mut ban_list);
     ban_list.refresh();
 
     let mut party_freeze = false;
@@ -175,13 +208,30 @@ pub fn start(args: StartArgs) -> Result<()> {
                 party_cache = None;
 
                 if saved {
-                    app.emit("save-encounter", "")?;
+                    sink.emit("save-encounter", "");
                 }
                 continue;
             }
             LiveEvent::Nineveh(event) => event,
         };
 
+        if let Some(replay_clock) = &replay_clock {
+            replay_clock.advance();
+        }
+        raid_end_cd.get_or_insert_with(clock::tick);
+
+        if let Some(recorder) = capture.as_mut() {
+            let local_timestamp = clock::now().timestamp_millis();
+            let ntp_timestamp = state.ntp_timestamp(local_timestamp, Instant::now());
+            if let Err(e) = recorder.record(&runtime, &event, local_timestamp, ntp_timestamp) {
+                warn!(
+                    "failed to write packet capture {}, recording stopped: {e}",
+                    recorder.path().display()
+                );
+                capture = None;
+            }
+        }
+
         let (connection_id, packet_id, direction, packet) = match event {
             IPCServerToClientMessage::Connected { connections } => {
                 connection_ids_by_port.clear();
@@ -263,6 +313,7 @@ pub fn start(args: StartArgs) -> Result<()> {
                 if let Some(entity) = state.encounter.entities.get_mut(&applied.name) {
                     apply_player_info(entity, &applied.info, is_support_class(&entity.class_id));
                 }
+                state.record_inspect_snapshot(applied.name, applied.snapshot);
             } else if DEBUG_TRACE_INSPECT_PACKETS {
                 info!("inspect result deferred: name={inspect_name}");
             }
@@ -291,8 +342,8 @@ pub fn start(args: StartArgs) -> Result<()> {
             continue;
         }
 
-        if last_inspect_queue_scan.elapsed() >= inspect_queue_scan_duration {
-            last_inspect_queue_scan = Instant::now();
+        if last_inspect_queue_scan.has_elapsed(inspect_queue_scan_duration) {
+            last_inspect_queue_scan.restart();
             queue_missing_party_inspects(
                 &ipc.0,
                 &connection_ids_by_port,
@@ -312,13 +363,17 @@ pub fn start(args: StartArgs) -> Result<()> {
         let start = Instant::now();
         let handler_start = Instant::now();
         match packet.header.opcode {
-            // PKTBattleItemUseNotify::OPCODE => {
-            //     if let Some(pkt) =
-            //         parse_pkt(&data, PKTBattleItemUseNotify::new, "PKTBattleItemUseNotify")
-            //     {
-            //         state.on_battle_item_use(&pkt.item_id);
-            //     }
-            // }
+            PKTBattleItemUseNotify::OPCODE => {
+                // only sent for the local player
+                if let Some(pkt) = packet.try_parse::<PKTBattleItemUseNotify>().unwrap()
+                    && let Some(entity) =
+                        entity_tracker.entities.get(&entity_tracker.local_entity_id)
+                {
+                    let item =
+                        battle_item_from_item_id(pkt.item_id, clock::now().timestamp_millis());
+                    state.on_battle_item_use(entity, item);
+                }
+            }
             PKTCounterAttackNotify::OPCODE => {
                 if let Some(pkt) = packet.try_parse::<PKTCounterAttackNotify>().unwrap()
                     && let Some(entity) = entity_tracker.entities.get(&pkt.source_id)
@@ -341,7 +396,7 @@ pub fn start(args: StartArgs) -> Result<()> {
             }
             PKTIdentityGaugeChangeNotify::OPCODE => {
                 if let Some(pkt) = packet.try_parse::<PKTIdentityGaugeChangeNotify>().unwrap() {
-                    let timestamp = Utc::now().timestamp_millis();
+                    let timestamp = clock::now().timestamp_millis();
                     entity_tracker.record_identity_gauge_change(
                         pkt.player_id,
                         pkt.identity_gauge1,
@@ -349,21 +404,35 @@ pub fn start(args: StartArgs) -> Result<()> {
                         pkt.identity_gauge3,
                         timestamp,
                     );
+                    if let Some(entity) = entity_tracker.entities.get(&pkt.player_id) {
+                        state.on_identity_gain(
+                            entity,
+                            (
+                                pkt.identity_gauge1,
+                                pkt.identity_gauge2,
+                                pkt.identity_gauge3,
+                            ),
+                            timestamp,
+                        );
+                    }
                     if manager.can_emit_details() {
-                        app.emit(
+                        sink.emit(
                             "identity-update",
                             Identity {
                                 gauge1: pkt.identity_gauge1,
                                 gauge2: pkt.identity_gauge2,
                                 gauge3: pkt.identity_gauge3,
                             },
-                        )?;
+                        );
                     }
                 }
             }
             PKTIdentityStanceChangeNotify::OPCODE => {
                 if let Some(pkt) = packet.try_parse::<PKTIdentityStanceChangeNotify>().unwrap() {
                     entity_tracker.record_identity_stance(pkt.object_id, pkt.state);
+                    if let Some(entity) = entity_tracker.entities.get(&pkt.object_id) {
+                        state.on_identity_stance(entity, pkt.state);
+                    }
                 }
             }
             PKTInitEnv::OPCODE => {
@@ -378,7 +447,7 @@ pub fn start(args: StartArgs) -> Result<()> {
                     state.raid_difficulty = "".to_string();
                     state.raid_difficulty_id = 0;
                     party_cache = None;
-                    get_and_set_region(&app, &mut state, &mut ban_list);
+                    get_and_set_region(app.as_ref(), &mut state, &mut ban_list);
                     ban_list.refresh();
                     // clear banned if local player isn't on list
                     if !ban_list.is_banned(entity_tracker.local_character_id) {
@@ -566,6 +635,17 @@ pub fn start(args: StartArgs) -> Result<()> {
             PKTNewProjectile::OPCODE => {
                 if let Some(pkt) = packet.try_parse::<PKTNewProjectile>().unwrap() {
                     entity_tracker.new_projectile(&pkt);
+                    // the local player's items are already counted from PKTBattleItemUseNotify
+                    if pkt.projectile_info.owner_id != entity_tracker.local_entity_id
+                        && let Some(item) = battle_item_from_skill_effect(
+                            pkt.projectile_info.skill_effect,
+                            clock::now().timestamp_millis(),
+                        )
+                        && let Some(owner) =
+                            entity_tracker.entities.get(&pkt.projectile_info.owner_id)
+                    {
+                        state.on_battle_item_use(owner, item);
+                    }
                     state.on_source_owner_resolved(
                         pkt.projectile_info.projectile_id,
                         pkt.projectile_info.owner_id,
@@ -605,43 +685,36 @@ pub fn start(args: StartArgs) -> Result<()> {
                     }
                 }
             }
-            // PKTParalyzationStateNotify::OPCODE => {
-            //     if let Some(pkt) = parse_pkt(
-            //         &data,
-            //         PKTParalyzationStateNotify::new,
-            //         "PKTParalyzationStateNotify",
-            //     ) {
-            //         state.on_stagger_change(&pkt);
-            //         if emit_details.load(Ordering::Relaxed) {
-            //             window.emit(
-            //                 "stagger-update",
-            //                 Stagger {
-            //                     current: pkt.paralyzation_point,
-            //                     max: pkt.paralyzation_max_point,
-            //                 },
-            //             )?;
-            //         }
-            //     }
-            // }
+            PKTParalyzationStateNotify::OPCODE => {
+                if let Some(pkt) = packet.try_parse::<PKTParalyzationStateNotify>().unwrap()
+                    && let Some(entity) = entity_tracker.entities.get(&pkt.object_id)
+                {
+                    state.on_stagger_change(
+                        entity,
+                        pkt.paralyzation_point as i64,
+                        pkt.paralyzation_max_point as i64,
+                    );
+                    if manager.can_emit_details() {
+                        sink.emit(
+                            "stagger-update",
+                            Stagger {
+                                current: pkt.paralyzation_point,
+                                max: pkt.paralyzation_max_point,
+                            },
+                        );
+                    }
+                }
+            }
             PKTRaidBegin::OPCODE => {
                 if let Some(pkt) = packet.try_parse::<PKTRaidBegin>().unwrap() {
                     debug_print!("raid begin: {}", pkt.raid_id);
-                    match pkt.raid_id {
-                        308226 | 308227 | 308239 | 308339 => {
-                            state.raid_difficulty = "Trial".to_string();
-                            state.raid_difficulty_id = 7;
-                        }
-                        308428 | 308429 | 308420 | 308410 | 308411 | 308414 | 308422 | 308424
-                        | 308421 | 308412 | 308423 | 308426 | 308416 | 308419 | 308415 | 308437
-                        | 308417 | 308418 | 308425 | 308430 => {
-                            state.raid_difficulty = "Challenge".to_string();
-                            state.raid_difficulty_id = 8;
-                        }
-                        _ => {
-                            state.raid_difficulty = "".to_string();
-                            state.raid_difficulty_id = 0;
-                        }
-                    }
+                    let (difficulty, difficulty_id) = RAID_DEFINITIONS
+                        .raid_difficulty(pkt.raid_id)
+                        .map_or(("", 0), |difficulty| {
+                            (difficulty.name.as_str(), difficulty.id)
+                        });
+                    state.raid_difficulty = difficulty.to_string();
+                    state.raid_difficulty_id = difficulty_id;
                     if !banned {
                         for character_id in
                             party_tracker.borrow().get_all_registered_party_characters()
@@ -688,7 +761,7 @@ pub fn start(args: StartArgs) -> Result<()> {
                 } else {
                     state.on_phase_transition(0);
                 }
-                raid_end_cd = Instant::now();
+                raid_end_cd = Some(clock::tick());
                 info!("phase: 0 - RaidResult");
             }
             PKTRemoveObject::OPCODE => {
@@ -731,7 +804,7 @@ pub fn start(args: StartArgs) -> Result<()> {
                     entity_tracker.infer_entity_class_from_skill(&mut entity, pkt.skill_id);
                     let should_buffer_for_startup =
                         state.startup_barrier_active() && entity.entity_type == EntityType::Player;
-                    let timestamp = Utc::now().timestamp_millis();
+                    let timestamp = clock::now().timestamp_millis();
                     startup_event_seq += 1;
                     if should_buffer_for_startup {
                         state.queue_pending_skill_event(
@@ -776,7 +849,7 @@ pub fn start(args: StartArgs) -> Result<()> {
                                 second: tripod_index.second,
                                 third: tripod_index.third,
                             });
-                    let timestamp = Utc::now().timestamp_millis();
+                    let timestamp = clock::now().timestamp_millis();
                     let skill_option_snapshot = Some(
                         crate::live::entity_tracker::SkillOptionSnapshot::from_skill_option_data(
                             &pkt.skill_option_data,
@@ -825,7 +898,7 @@ pub fn start(args: StartArgs) -> Result<()> {
             //     let pkt = PKTSkillStageNotify::new(&data);
             // }
             PKTSkillDamageAbnormalMoveNotify::OPCODE => {
-                if Instant::now() - raid_end_cd < Duration::from_secs(10) {
+                if raid_end_cd.is_some_and(|cd| cd.elapsed() < Duration::from_secs(10)) {
                     debug_print!("ignoring damage - SkillDamageAbnormalMoveNotify");
                     continue;
                 }
@@ -833,7 +906,7 @@ pub fn start(args: StartArgs) -> Result<()> {
                     .try_parse::<PKTSkillDamageAbnormalMoveNotify>()
                     .unwrap()
                 {
-                    let now = Utc::now().timestamp_millis();
+                    let now = clock::now().timestamp_millis();
                     let mut owner = entity_tracker.get_source_entity(pkt.source_id);
                     entity_tracker.infer_entity_class_from_skill(&mut owner, pkt.skill_id);
                     let local_character_id = id_tracker
@@ -901,12 +974,12 @@ pub fn start(args: StartArgs) -> Result<()> {
             }
             PKTSkillDamageNotify::OPCODE => {
                 // use this to make sure damage packets are not tracked after a raid just wiped
-                if Instant::now() - raid_end_cd < Duration::from_secs(10) {
+                if raid_end_cd.is_some_and(|cd| cd.elapsed() < Duration::from_secs(10)) {
                     debug_print!("ignoring damage - SkillDamageNotify");
                     continue;
                 }
                 if let Some(pkt) = packet.try_parse::<PKTSkillDamageNotify>().unwrap() {
-                    let now = Utc::now().timestamp_millis();
+                    let now = clock::now().timestamp_millis();
                     let mut owner = entity_tracker.get_source_entity(pkt.source_id);
                     entity_tracker.infer_entity_class_from_skill(&mut owner, pkt.skill_id);
                     let local_character_id = id_tracker
@@ -1093,7 +1166,7 @@ pub fn start(args: StartArgs) -> Result<()> {
                     let status_effect = entity_tracker.build_and_register_status_effect(
                         &pkt.status_effect_data,
                         object_id,
-                        Utc::now(),
+                        clock::now(),
                         Some(&state.encounter.entities),
                     );
 
@@ -1166,7 +1239,7 @@ pub fn start(args: StartArgs) -> Result<()> {
                             }
                         }
                     }
-                    let now = Utc::now().timestamp_millis();
+                    let now = clock::now().timestamp_millis();
                     for effect_removed in effects_removed {
                         if effect_removed.status_effect_type == StatusEffectType::HardCrowdControl {
                             let target = entity_tracker.get_source_entity(effect_removed.target_id);
@@ -1210,8 +1283,10 @@ pub fn start(args: StartArgs) -> Result<()> {
             }
             PKTTriggerStartNotify::OPCODE => {
                 if let Some(pkt) = packet.try_parse::<PKTTriggerStartNotify>().unwrap() {
-                    match pkt.signal {
-                        57 | 59 | 61 | 63 | 74 | 76 => {
+                    let signal: i64 = pkt.signal.into();
+                    state.on_trigger(signal);
+                    match RAID_DEFINITIONS.trigger(signal) {
+                        Some(RaidTrigger::Clear) => {
                             party_freeze = true;
                             state.party_info = if let Some(party) = party_cache.take() {
                                 party
@@ -1230,10 +1305,10 @@ pub fn start(args: StartArgs) -> Result<()> {
                             } else {
                                 state.on_phase_transition(2);
                             }
-                            raid_end_cd = Instant::now();
+                            raid_end_cd = Some(clock::tick());
                             info!("phase: 2 - clear - TriggerStartNotify");
                         }
-                        58 | 60 | 62 | 64 | 75 | 77 => {
+                        Some(RaidTrigger::Wipe) => {
                             party_freeze = true;
                             state.party_info = if let Some(party) = party_cache.take() {
                                 party
@@ -1252,13 +1327,10 @@ pub fn start(args: StartArgs) -> Result<()> {
                             } else {
                                 state.on_phase_transition(4);
                             }
-                            raid_end_cd = Instant::now();
+                            raid_end_cd = Some(clock::tick());
                             info!("phase: 4 - wipe - TriggerStartNotify");
                         }
-                        27 | 10 | 11 => {
-                            // debug_print!("old udps sync time - {}", pkt.trigger_signal_type);
-                        }
-                        _ => {}
+                        None => {}
                     }
                 }
             }
@@ -1280,11 +1352,11 @@ pub fn start(args: StartArgs) -> Result<()> {
                     debug_print!("raid zone id: {}", &pkt.zone_id);
                     debug_print!("raid zone level: {}", &pkt.zone_level);
                     state.set_lal_debug_zone(pkt.zone_id, Some(pkt.zone_level as u32));
-                    if let Some((difficulty, difficulty_id)) =
-                        raid_difficulty_from_zone(pkt.zone_id, pkt.zone_level as u32)
+                    if let Some(difficulty) =
+                        RAID_DEFINITIONS.zone_difficulty(pkt.zone_id, pkt.zone_level as u32)
                     {
-                        state.raid_difficulty = difficulty.to_string();
-                        state.raid_difficulty_id = difficulty_id;
+                        state.raid_difficulty = difficulty.name.clone();
+                        state.raid_difficulty_id = difficulty.id;
                     }
                 }
             }
@@ -1307,7 +1379,7 @@ pub fn start(args: StartArgs) -> Result<()> {
                         );
                     if let Some(mut status_effect) = status_effect {
                         entity_tracker
-                            .refresh_status_effect_snapshots(&mut status_effect, Utc::now());
+                            .refresh_status_effect_snapshots(&mut status_effect, clock::now());
                         status_tracker
                             .borrow_mut()
                             .register_status_effect(status_effect.clone());
@@ -1353,7 +1425,7 @@ pub fn start(args: StartArgs) -> Result<()> {
                             if let Some(mut status_effect) = status_effect {
                                 entity_tracker.refresh_status_effect_snapshots(
                                     &mut status_effect,
-                                    Utc::now(),
+                                    clock::now(),
                                 );
                                 status_tracker
                                     .borrow_mut()
@@ -1404,7 +1476,7 @@ pub fn start(args: StartArgs) -> Result<()> {
             state.force_release_startup_barrier(&mut entity_tracker, "inspect_unavailable");
         }
 
-        if last_update.elapsed() >= duration || state.resetting || state.boss_dead_update {
+        if last_update.has_elapsed(duration) || state.resetting || state.boss_dead_update {
             state.try_flush_startup_barrier(&mut entity_tracker);
             let boss_dead = state.boss_dead_update;
             if state.boss_dead_update {
@@ -1413,10 +1485,10 @@ pub fn start(args: StartArgs) -> Result<()> {
 
             if banned {
                 if !ban_toast_sent {
-                    app.emit("banned-event", "")?;
+                    sink.emit("banned-event", "");
                     ban_toast_sent = true;
                 }
-                last_update = Instant::now();
+                last_update.restart();
                 // skip encounter update while a banned player is present
                 if state.resetting {
                     state.soft_reset(true);
@@ -1429,13 +1501,13 @@ pub fn start(args: StartArgs) -> Result<()> {
             }
 
             let damage_valid = state.damage_is_valid;
-            let app_handle = app.clone();
+            let sink = sink.clone();
 
-            let party_info: Option<Vec<Vec<String>>> = if last_party_update.elapsed()
-                >= party_duration
+            let party_info: Option<Vec<Vec<String>>> = if last_party_update
+                .has_elapsed(party_duration)
                 && !party_freeze
             {
-                last_party_update = Instant::now();
+                last_party_update.restart();
 
                 // use cache if available
                 // otherwise get party info
@@ -1466,24 +1538,18 @@ pub fn start(args: StartArgs) -> Result<()> {
             tokio::task::spawn(async move {
                 if !live_update.entities.is_empty() {
                     if !damage_valid {
-                        app_handle
-                            .emit("invalid-damage", "")
-                            .expect("failed to emit invalid-damage");
+                        sink.emit("invalid-damage", "");
                     } else {
-                        app_handle
-                            .emit("encounter-update", Some(live_update))
-                            .expect("failed to emit encounter-update");
+                        sink.emit("encounter-update", Some(live_update));
 
                         if party_info.is_some() {
-                            app_handle
-                                .emit("party-update", party_info)
-                                .expect("failed to emit party-update");
+                            sink.emit("party-update", party_info);
                         }
                     }
                 }
             });
 
-            last_update = Instant::now();
+            last_update.restart();
         }
 
         if state.resetting {
@@ -1494,7 +1560,9 @@ pub fn start(args: StartArgs) -> Result<()> {
             party_cache = None;
         }
 
-        if let Some(ref region) = state.region {
+        if let Some(ref region) = state.region
+            && let Some(heartbeat_api) = heartbeat_api.as_mut()
+        {
             heartbeat_api.heartbeat(region);
         }
 
@@ -1507,6 +1575,19 @@ pub fn start(args: StartArgs) -> Result<()> {
         }
     }
 
+    // a replayed capture ran out, keep the pull in progress
+    if replay_clock.is_some() && !state.saved && !banned {
+        state.party_info = update_party(&party_tracker, &entity_tracker);
+        state.save_to_db(false);
+    }
+    state.wait_for_saves(&runtime);
+
+    if let Some(recorder) = capture
+        && let Err(e) = recorder.finish()
+    {
+        warn!("failed to finish packet capture: {e}");
+    }
+
     Ok(())
 }
 
@@ -1575,7 +1656,7 @@ fn queue_missing_party_inspects(
         return;
     };
 
-    let now = Utc::now().timestamp_millis();
+    let now = clock::now().timestamp_millis();
     if bootstrap_active {
         for name in entity_tracker.take_timed_out_bootstrap_inspects(now) {
             damage_handler.cancel_inspect_request(&name);
@@ -1611,7 +1692,7 @@ fn queue_missing_party_inspects(
             .is_ok()
         {
             if bootstrap_active {
-                entity_tracker.note_bootstrap_inspect_sent(&name, Utc::now().timestamp_millis());
+                entity_tracker.note_bootstrap_inspect_sent(&name, clock::now().timestamp_millis());
             }
             continue;
         }
@@ -1626,38 +1707,15 @@ fn queue_missing_party_inspects(
     }
 }
 
-fn raid_difficulty_from_zone(zone_id: u32, zone_level: u32) -> Option<(&'static str, u32)> {
-    if matches!(zone_id, 37561 | 37562) {
-        // horizon cathedral
-        return match zone_level {
-            0 => Some(("Level 1", 9)),
-            1 => Some(("Level 2", 10)),
-            2 => Some(("Level 3", 11)),
-            _ => None,
-        };
-    }
-
-    match zone_level {
-        0 => Some(("Normal", 0)),
-        1 => Some(("Hard", 1)),
-        2 => Some(("Nightmare", 2)),
-        3 => Some(("Challenge", 3)),
-        4 => match zone_id {
-            37551 => Some(("Matching", 4)),
-            _ => Some(("Solo", 4)),
-        },
-        5 => Some(("Extreme", 5)),
-        _ => None,
-    }
-}
-
-fn get_and_set_region(app: &AppHandle, state: &mut EncounterState, ban_list: &mut BanList) {
-    let ctx = app.state::<AppContext>();
-    if let Ok(region) = ctx.region.read() {
-        state.region = region.clone();
-        state.encounter.region = region.clone();
-        ban_list.set_region(region.clone());
+fn get_and_set_region(app: Option<&AppHandle>, state: &mut EncounterState, ban_list: &mut BanList) {
+    if let Some(app) = app {
+        let ctx = app.state::<AppContext>();
+        if let Ok(region) = ctx.region.read() {
+            state.region = region.clone();
+            state.encounter.region = region.clone();
+        }
     }
+    ban_list.set_region(state.region.clone());
 }
 
 fn exceed_process_duration(opcode: impl std::fmt::Debug, step: &str, elapsed: Duration) {
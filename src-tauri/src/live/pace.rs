@@ -0,0 +1,115 @@
+use std::sync::{Arc, OnceLock};
+
+use crate::database::Repository;
+use crate::database::models::PaceReference;
+use crate::models::{BossHpLog, EncounterPace};
+use log::warn;
+use tokio::task;
+
+/// Compares the current pull against the fastest stored clear of the same boss and difficulty.
+#[derive(Debug, Default)]
+pub struct PaceTracker {
+    // filled in by the lookup task once the clear is loaded
+    reference: Arc<OnceLock<(String, PaceReference)>>,
+    // boss and difficulty of the last lookup, only looked up once per fight
+    loaded: Option<(String, String)>,
+}
+
+impl PaceTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Looks up the reference clear on a blocking task, pace is unavailable until it is loaded.
+    pub fn load(&mut self, repository: &Repository, boss: &str, difficulty: &str) {
+        if boss.is_empty()
+            || self
+                .loaded
+                .as_ref()
+                .is_some_and(|(loaded_boss, loaded_difficulty)| {
+                    loaded_boss == boss && loaded_difficulty == difficulty
+                })
+        {
+            return;
+        }
+
+        self.loaded = Some((boss.to_string(), difficulty.to_string()));
+        self.reference = Arc::new(OnceLock::new());
+
+        let reference = self.reference.clone();
+        let repository = repository.clone();
+        let boss = boss.to_string();
+        let difficulty = difficulty.to_string();
+        task::spawn_blocking(
+            move || match repository.get_fastest_clear(&boss, &difficulty) {
+                Ok(Some(clear)) => {
+                    let _ = reference.set((boss, clear));
+                }
+                Ok(None) => {}
+                Err(err) => warn!("could not load pace reference for {boss}: {err:?}"),
+            },
+        );
+    }
+
+    pub fn pace(&self, boss: &str, boss_hp_percent: f32, elapsed: i64) -> Option<EncounterPace> {
+        let (reference_boss, reference) = self.reference.get()?;
+        if reference_boss != boss {
+            return None;
+        }
+
+        let reference_elapsed = elapsed_at(&reference.hp_log, boss_hp_percent);
+        let delta = reference_elapsed.map(|reference_elapsed| elapsed - reference_elapsed);
+
+        Some(EncounterPace {
+            reference_id: reference.encounter_id,
+            boss_hp_percent,
+            elapsed,
+            reference_elapsed,
+            delta,
+            projected_kill: delta.map(|delta| reference.duration + delta),
+            reference_kill: reference.duration,
+        })
+    }
+}
+
+// ms until the boss was at or below `boss_hp_percent`
+fn elapsed_at(hp_log: &[BossHpLog], boss_hp_percent: f32) -> Option<i64> {
+    hp_log
+        .iter()
+        .find(|log| log.p * 100.0 <= boss_hp_percent)
+        .map(|log| log.time as i64 * 1000)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_pull_to_reference_at_same_boss_hp() {
+        let tracker = PaceTracker {
+            reference: Arc::new(OnceLock::from((
+                "Boss".to_string(),
+                PaceReference {
+                    encounter_id: 7,
+                    duration: 100_000,
+                    hp_log: vec![
+                        BossHpLog::new(0, 100, 1.0),
+                        BossHpLog::new(20, 75, 0.75),
+                        BossHpLog::new(50, 40, 0.4),
+                    ],
+                },
+            ))),
+            loaded: None,
+        };
+
+        let pace = tracker.pace("Boss", 50.0, 60_000).unwrap();
+        assert_eq!(pace.reference_elapsed, Some(50_000));
+        assert_eq!(pace.delta, Some(10_000));
+        assert_eq!(pace.projected_kill, Some(110_000));
+
+        let pace = tracker.pace("Boss", 10.0, 60_000).unwrap();
+        assert_eq!(pace.reference_elapsed, None);
+        assert_eq!(pace.projected_kill, None);
+        assert!(tracker.pace("Other", 50.0, 60_000).is_none());
+    }
+}
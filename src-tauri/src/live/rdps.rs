@@ -1794,7 +1794,7 @@ pub fn resolve_skill_effect_flags(skill_effect_id: u32, is_hyper_awakening: bool
     }
 }
 
-fn get_skill_groups(skill_id: u32) -> &'static [u32] {
+pub(super) fn get_skill_groups(skill_id: u32) -> &'static [u32] {
     SKILL_DATA
         .get(&skill_id)
         .and_then(|skill| skill.groups.as_deref())
@@ -0,0 +1,127 @@
+use std::fmt::Debug;
+use std::sync::Arc;
+#[cfg(test)]
+use std::sync::Mutex;
+
+use log::warn;
+use serde::Serialize;
+#[cfg(test)]
+use serde_json::Value;
+use tauri::{AppHandle, Emitter};
+
+/// Destination for the events the live meter publishes while parsing packets:
+/// `zone-change`, `raid-start`, `phase-transition`, `clear-encounter`, `encounter-update`,
+/// `party-update` and a few lifecycle events (`save-encounter`, `banned-event`, ...).
+///
+/// The Tauri app forwards them to its windows, the headless rebuild binary discards them.
+pub trait EventSink: Send + Sync + Debug {
+    /// `payload` is already serialized to JSON, once for every sink.
+    fn emit_json(&self, event: &str, payload: &str);
+}
+
+impl dyn EventSink {
+    pub fn emit<T: Serialize>(&self, event: &str, payload: T) {
+        match serde_json::to_string(&payload) {
+            Ok(payload) => self.emit_json(event, &payload),
+            Err(e) => warn!("failed to serialize {event} payload: {e}"),
+        }
+    }
+}
+
+impl EventSink for AppHandle {
+    fn emit_json(&self, event: &str, payload: &str) {
+        if let Err(e) = Emitter::emit_str(self, event, payload.to_string()) {
+            warn!("failed to emit {event}: {e}");
+        }
+    }
+}
+
+/// Drops every event, used when there is no frontend to notify.
+#[derive(Debug, Default)]
+pub struct NullSink;
+
+impl EventSink for NullSink {
+    fn emit_json(&self, _event: &str, _payload: &str) {}
+}
+
+/// Keeps every event in memory, in the order it was emitted.
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub struct RecordingSink {
+    events: Mutex<Vec<(String, Value)>>,
+}
+
+#[cfg(test)]
+impl RecordingSink {
+    pub fn events(&self) -> Vec<(String, Value)> {
+        self.events.lock().map(|e| e.clone()).unwrap_or_default()
+    }
+
+    /// Returns the recorded events and clears the buffer.
+    pub fn take(&self) -> Vec<(String, Value)> {
+        self.events
+            .lock()
+            .map(|mut e| std::mem::take(&mut *e))
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+impl EventSink for RecordingSink {
+    fn emit_json(&self, event: &str, payload: &str) {
+        let payload = serde_json::from_str(payload).unwrap_or_default();
+        if let Ok(mut events) = self.events.lock() {
+            events.push((event.to_string(), payload));
+        }
+    }
+}
+
+/// Forwards every event to each of its sinks, so several consumers can follow the same meter.
+#[derive(Debug, Default)]
+pub struct FanoutSink {
+    sinks: Vec<Arc<dyn EventSink>>,
+}
+
+impl FanoutSink {
+    pub fn new(sinks: Vec<Arc<dyn EventSink>>) -> Self {
+        Self { sinks }
+    }
+
+    pub fn push(&mut self, sink: Arc<dyn EventSink>) {
+        self.sinks.push(sink);
+    }
+}
+
+impl EventSink for FanoutSink {
+    fn emit_json(&self, event: &str, payload: &str) {
+        for sink in &self.sinks {
+            sink.emit_json(event, payload);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn fanout_forwards_to_every_sink() {
+        let first = Arc::new(RecordingSink::default());
+        let second = Arc::new(RecordingSink::default());
+        let sinks: Vec<Arc<dyn EventSink>> = vec![first.clone(), second.clone()];
+        let fanout: Arc<dyn EventSink> = Arc::new(FanoutSink::new(sinks));
+
+        fanout.emit("raid-start", 42);
+        fanout.emit("zone-change", "no-toast");
+
+        let expected = vec![
+            ("raid-start".to_string(), json!(42)),
+            ("zone-change".to_string(), json!("no-toast")),
+        ];
+        assert_eq!(first.events(), expected);
+        assert_eq!(second.take(), expected);
+        assert!(second.events().is_empty());
+    }
+}
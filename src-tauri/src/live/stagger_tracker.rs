@@ -0,0 +1,143 @@
+use crate::live::entity_tracker::Entity;
+use crate::models::{EncounterStagger, StaggerCheck, StaggerLog};
+use hashbrown::HashMap;
+
+/// Follows the stagger gauges of bosses. A check starts when a gauge starts filling and ends
+/// when it is full (success) or drops back to empty (failure).
+#[derive(Debug, Default)]
+pub struct StaggerTracker {
+    log: HashMap<String, Vec<StaggerLog>>,
+    checks: Vec<StaggerCheck>,
+    // boss entity id -> check in progress
+    active_checks: HashMap<u64, StaggerCheck>,
+}
+
+impl StaggerTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `relative_timestamp` is in ms since fight start.
+    pub fn on_stagger_change(
+        &mut self,
+        boss: &Entity,
+        point: i64,
+        max: i64,
+        relative_timestamp: i64,
+    ) {
+        if max <= 0 {
+            return;
+        }
+
+        let log = self.log.entry(boss.name.clone()).or_default();
+        let time = (relative_timestamp / 1000) as i32;
+        let p = 1.0 - (point.min(max) as f32 / max as f32);
+        match log.last_mut() {
+            Some(last) if last.time == time => last.p = p,
+            _ => log.push(StaggerLog { time, p }),
+        }
+
+        match self.active_checks.get_mut(&boss.id) {
+            Some(check) if point >= max || point == 0 => {
+                check.end = relative_timestamp;
+                check.success = point >= max;
+                let check = self.active_checks.remove(&boss.id).unwrap();
+                self.checks.push(check);
+            }
+            Some(check) => check.max_stagger = check.max_stagger.max(max),
+            None if point > 0 && point < max => {
+                self.active_checks.insert(
+                    boss.id,
+                    StaggerCheck {
+                        boss: boss.name.clone(),
+                        start: relative_timestamp,
+                        max_stagger: max,
+                        ..Default::default()
+                    },
+                );
+            }
+            None => {}
+        }
+    }
+
+    /// Credits stagger dealt to `target_id` to `source` if the target has a check running.
+    pub fn on_stagger_hit(&mut self, target_id: u64, source: &str, stagger: i64) {
+        if stagger <= 0 {
+            return;
+        }
+        if let Some(check) = self.active_checks.get_mut(&target_id) {
+            *check.contributions.entry(source.to_string()).or_default() += stagger;
+        }
+    }
+
+    /// Everything recorded so far, checks still running count as failed at `relative_timestamp`.
+    pub fn summary(&self, relative_timestamp: i64) -> Option<EncounterStagger> {
+        if self.log.is_empty() {
+            return None;
+        }
+
+        let mut checks = self.checks.clone();
+        checks.extend(self.active_checks.values().map(|check| StaggerCheck {
+            end: relative_timestamp,
+            ..check.clone()
+        }));
+        checks.sort_by_key(|check| check.start);
+
+        Some(EncounterStagger {
+            log: self.log.clone(),
+            checks,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_checks_and_credits_players() {
+        let boss = Entity {
+            id: 7,
+            name: "Boss".to_string(),
+            ..Default::default()
+        };
+        let mut tracker = StaggerTracker::new();
+
+        tracker.on_stagger_change(&boss, 100, 1000, 1_000);
+        tracker.on_stagger_hit(7, "Alpha", 600);
+        tracker.on_stagger_hit(7, "Beta", 300);
+        tracker.on_stagger_hit(8, "Beta", 500);
+        tracker.on_stagger_change(&boss, 1000, 1000, 4_000);
+
+        tracker.on_stagger_change(&boss, 200, 1000, 10_000);
+        tracker.on_stagger_hit(7, "Alpha", 200);
+        tracker.on_stagger_change(&boss, 0, 1000, 20_000);
+
+        tracker.on_stagger_change(&boss, 500, 1000, 30_000);
+
+        let summary = tracker.summary(35_000).unwrap();
+        let results: Vec<_> = summary
+            .checks
+            .iter()
+            .map(|c| (c.start, c.end, c.success))
+            .collect();
+        assert_eq!(
+            results,
+            vec![
+                (1_000, 4_000, true),
+                (10_000, 20_000, false),
+                (30_000, 35_000, false)
+            ]
+        );
+        assert_eq!(summary.checks[0].contributions["Alpha"], 600);
+        assert_eq!(summary.checks[0].contributions["Beta"], 300);
+        assert_eq!(summary.checks[1].contributions.len(), 1);
+        assert_eq!(
+            summary.log["Boss"][..2],
+            [
+                StaggerLog { time: 1, p: 0.9 },
+                StaggerLog { time: 4, p: 0.0 }
+            ]
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use crate::data::*;
+use crate::live::clock;
 use crate::live::entity_tracker::Entity;
 use crate::live::entity_tracker::SkillRuntimeData;
 use crate::live::party_tracker::PartyTracker;
@@ -164,7 +165,7 @@ impl StatusTracker {
         target_entity: &Entity,
         local_character_id: u64,
     ) -> (Vec<StatusEffectDetails>, Vec<StatusEffectDetails>) {
-        let timestamp = Utc::now();
+        let timestamp = clock::now();
 
         let use_party_for_source = if source_entity.entity_type == EntityType::Player {
             self.should_use_party_status_effect(source_entity.character_id, local_character_id)
@@ -0,0 +1,59 @@
+use crate::models::{TimelineEvent, TimelineEventKind};
+
+/// Raid events of the current fight in the order they are saved with the encounter.
+#[derive(Debug, Default)]
+pub struct Timeline {
+    events: Vec<TimelineEvent>,
+}
+
+impl Timeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ignored until the fight has started.
+    pub fn record(
+        &mut self,
+        fight_start: i64,
+        timestamp: i64,
+        kind: TimelineEventKind,
+        entity: Option<&str>,
+        value: Option<i64>,
+    ) {
+        if fight_start == 0 {
+            return;
+        }
+
+        self.events.push(TimelineEvent {
+            time: (timestamp - fight_start).max(0),
+            kind,
+            entity: entity.map(str::to_string),
+            value,
+        });
+    }
+
+    /// Replaces the value of the latest `kind` event of `entity` recorded at `timestamp`, e.g. when
+    /// a crowd control ends before its planned duration.
+    pub fn update_value(
+        &mut self,
+        fight_start: i64,
+        timestamp: i64,
+        kind: TimelineEventKind,
+        entity: &str,
+        value: i64,
+    ) {
+        let time = (timestamp - fight_start).max(0);
+        if let Some(event) = self.events.iter_mut().rev().find(|event| {
+            event.time == time && event.kind == kind && event.entity.as_deref() == Some(entity)
+        }) {
+            event.value = Some(value);
+        }
+    }
+
+    /// Events ordered by time, some are only known after the fact (e.g. infinite crowd control).
+    pub fn events(&self) -> Vec<TimelineEvent> {
+        let mut events = self.events.clone();
+        events.sort_by_key(|event| event.time);
+        events
+    }
+}
@@ -71,6 +71,41 @@ pub fn is_battle_item(skill_effect_id: &u32, _item_type: &str) -> bool {
     false
 }
 
+pub fn battle_item_from_item_id(item_id: u32, timestamp: i64) -> BattleItemUse {
+    let name = EXTERNAL_ITEM_DATA
+        .get(&item_id)
+        .map(|item| item.name.clone())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| format!("Unknown Item {item_id}"));
+
+    BattleItemUse {
+        item_id: Some(item_id),
+        name,
+        timestamp,
+    }
+}
+
+/// Battle item thrown as a projectile, e.g. a grenade or a flare.
+pub fn battle_item_from_skill_effect(
+    skill_effect_id: u32,
+    timestamp: i64,
+) -> Option<BattleItemUse> {
+    if !is_battle_item(&skill_effect_id, "attack") {
+        return None;
+    }
+    let name = SKILL_EFFECT_DATA
+        .get(&skill_effect_id)?
+        .item_name
+        .clone()
+        .filter(|name| !name.is_empty())?;
+
+    Some(BattleItemUse {
+        item_id: None,
+        name,
+        timestamp,
+    })
+}
+
 pub fn get_status_effect_data(buff_id: u32, source_skill: Option<u32>) -> Option<StatusEffect> {
     let buff = SKILL_BUFF_DATA.get(&buff_id)?;
 
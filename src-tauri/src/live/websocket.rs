@@ -0,0 +1,458 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener as StdTcpListener};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::{Context, Result};
+use futures_util::{SinkExt, StreamExt};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::runtime::Handle;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::{StatusCode, header::ORIGIN};
+use url::{Host, Url};
+
+use crate::live::sink::EventSink;
+use crate::models::EntityType;
+
+/// events forwarded to websocket clients, everything else stays inside the app
+const BROADCAST_EVENTS: [&str; 4] = [
+    "encounter-update",
+    "party-update",
+    "raid-start",
+    "clear-encounter",
+];
+/// slow clients skip updates once this many messages are queued for them
+const CHANNEL_CAPACITY: usize = 64;
+
+/// Publishes live meter events as JSON over a local WebSocket server, for stream overlays and bots.
+///
+/// Every message is `{"event": <name>, "payload": <payload>}` with the same payload the meter
+/// window receives. A client can send `{"subscribe": "summary"}` to receive a reduced per-player
+/// summary instead of the full encounter on `encounter-update`, and `{"subscribe": "full"}` to
+/// switch back.
+///
+/// Clients without an `Origin` header (bots, scripts) are always accepted. Browser pages are only
+/// accepted from `file://`, loopback hosts and the origins listed in the settings, so websites
+/// open in a browser can't read the meter.
+#[derive(Debug)]
+pub struct WebSocketSink {
+    tx: broadcast::Sender<Arc<Broadcast>>,
+    /// clients subscribed to the summary, updates are only summarized while there are any
+    summary_clients: Arc<AtomicUsize>,
+}
+
+#[derive(Debug)]
+struct Broadcast {
+    full: String,
+    summary: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum Subscription {
+    #[default]
+    Full,
+    Summary,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClientRequest {
+    subscribe: Subscription,
+}
+
+#[derive(Serialize)]
+struct Envelope<'a, T> {
+    event: &'a str,
+    payload: T,
+}
+
+impl WebSocketSink {
+    /// Binds the server to `127.0.0.1:port` and accepts clients on `runtime` until the app exits.
+    pub fn start(runtime: &Handle, port: u16, allowed_origins: Vec<String>) -> Result<Self> {
+        let address = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+        let listener = StdTcpListener::bind(address)
+            .with_context(|| format!("could not bind live server to {address}"))?;
+        listener.set_nonblocking(true)?;
+
+        let (tx, _) = broadcast::channel(CHANNEL_CAPACITY);
+        let summary_clients = Arc::new(AtomicUsize::new(0));
+        let allowed_origins: Arc<[String]> = allowed_origins.into();
+        let accept_tx = tx.clone();
+        let accept_summary_clients = summary_clients.clone();
+        runtime.spawn(async move {
+            let listener = match TcpListener::from_std(listener) {
+                Ok(listener) => listener,
+                Err(e) => {
+                    warn!("could not start live server: {e}");
+                    return;
+                }
+            };
+            info!("live server listening on ws://{address}");
+
+            loop {
+                match listener.accept().await {
+                    Ok((stream, peer)) => {
+                        let rx = accept_tx.subscribe();
+                        let summary = SummarySubscription::new(accept_summary_clients.clone());
+                        let allowed_origins = allowed_origins.clone();
+                        tokio::spawn(async move {
+                            if let Err(e) = serve_client(stream, rx, summary, allowed_origins).await
+                            {
+                                debug!("live server client {peer} disconnected: {e}");
+                            }
+                        });
+                    }
+                    Err(e) => warn!("live server failed to accept client: {e}"),
+                }
+            }
+        });
+
+        Ok(Self {
+            tx,
+            summary_clients,
+        })
+    }
+}
+
+impl EventSink for WebSocketSink {
+    fn emit_json(&self, event: &str, payload: &str) {
+        if self.tx.receiver_count() == 0 || !BROADCAST_EVENTS.contains(&event) {
+            return;
+        }
+
+        let summary =
+            if event == "encounter-update" && self.summary_clients.load(Ordering::Relaxed) > 0 {
+                EncounterSummary::from_payload(payload).and_then(|summary| {
+                    serde_json::to_string(&Envelope {
+                        event,
+                        payload: summary,
+                    })
+                    .ok()
+                })
+            } else {
+                None
+            };
+        // the payload is spliced in as is instead of being parsed and serialized again
+        let full = match serde_json::to_string(event) {
+            Ok(event) => format!(r#"{{"event":{event},"payload":{payload}}}"#),
+            Err(e) => {
+                warn!("failed to serialize {event} for live server: {e}");
+                return;
+            }
+        };
+
+        // only fails when every client disconnected in the meantime
+        let _ = self.tx.send(Arc::new(Broadcast { full, summary }));
+    }
+}
+
+async fn serve_client(
+    stream: TcpStream,
+    mut rx: broadcast::Receiver<Arc<Broadcast>>,
+    mut summary: SummarySubscription,
+    allowed_origins: Arc<[String]>,
+) -> Result<()> {
+    let websocket = tokio_tungstenite::accept_hdr_async(stream, |request: &Request, response| {
+        check_origin(request, response, &allowed_origins)
+    })
+    .await?;
+    let (mut write, mut read) = websocket.split();
+    let mut subscription = Subscription::default();
+
+    loop {
+        tokio::select! {
+            message = read.next() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    match serde_json::from_str::<ClientRequest>(text.as_str()) {
+                        Ok(request) => {
+                            subscription = request.subscribe;
+                            summary.set(subscription == Subscription::Summary);
+                        }
+                        Err(e) => debug!("ignoring live server request: {e}"),
+                    }
+                }
+                Some(Ok(Message::Close(_))) | None => return Ok(()),
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(e.into()),
+            },
+            broadcast = rx.recv() => match broadcast {
+                Ok(broadcast) => {
+                    let text = match (subscription, &broadcast.summary) {
+                        (Subscription::Summary, Some(summary)) => summary,
+                        _ => &broadcast.full,
+                    };
+                    write.send(Message::text(text.clone())).await?;
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    debug!("live server client lagged, skipped {skipped} updates");
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            },
+        }
+    }
+}
+
+fn check_origin(
+    request: &Request,
+    response: Response,
+    allowed_origins: &[String],
+) -> Result<Response, ErrorResponse> {
+    let Some(origin) = request.headers().get(ORIGIN) else {
+        return Ok(response);
+    };
+    if let Ok(origin) = origin.to_str()
+        && is_allowed_origin(origin, allowed_origins)
+    {
+        return Ok(response);
+    }
+
+    debug!("live server rejected client from origin {origin:?}");
+    let mut rejection = ErrorResponse::new(Some("origin not allowed".to_string()));
+    *rejection.status_mut() = StatusCode::FORBIDDEN;
+    Err(rejection)
+}
+
+fn is_allowed_origin(origin: &str, allowed_origins: &[String]) -> bool {
+    let origin = origin.trim_end_matches('/');
+    if origin == "file://"
+        || allowed_origins
+            .iter()
+            .any(|allowed| allowed.trim_end_matches('/').eq_ignore_ascii_case(origin))
+    {
+        return true;
+    }
+
+    let Ok(url) = Url::parse(origin) else {
+        return false;
+    };
+    matches!(url.scheme(), "http" | "https")
+        && match url.host() {
+            Some(Host::Domain(domain)) => domain.eq_ignore_ascii_case("localhost"),
+            Some(Host::Ipv4(ip)) => IpAddr::V4(ip).is_loopback(),
+            Some(Host::Ipv6(ip)) => IpAddr::V6(ip).is_loopback(),
+            None => false,
+        }
+}
+
+/// Keeps [`WebSocketSink::summary_clients`] up to date for one client, also when it disconnects.
+struct SummarySubscription {
+    clients: Arc<AtomicUsize>,
+    active: bool,
+}
+
+impl SummarySubscription {
+    fn new(clients: Arc<AtomicUsize>) -> Self {
+        Self {
+            clients,
+            active: false,
+        }
+    }
+
+    fn set(&mut self, active: bool) {
+        if active && !self.active {
+            self.clients.fetch_add(1, Ordering::Relaxed);
+        } else if !active && self.active {
+            self.clients.fetch_sub(1, Ordering::Relaxed);
+        }
+        self.active = active;
+    }
+}
+
+impl Drop for SummarySubscription {
+    fn drop(&mut self) {
+        self.set(false);
+    }
+}
+
+/// Reduced `encounter-update` payload: the current boss and one line per player.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct EncounterSummary {
+    fight_start: i64,
+    duration: i64,
+    difficulty: Option<String>,
+    cleared: bool,
+    local_player: String,
+    total_damage_dealt: i64,
+    boss: Option<BossSummary>,
+    players: Vec<PlayerSummary>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BossSummary {
+    name: String,
+    current_hp: i64,
+    max_hp: i64,
+    hp_bars: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PlayerSummary {
+    name: String,
+    class: String,
+    class_id: u32,
+    damage_dealt: i64,
+    damage_percent: f64,
+    dps: i64,
+    rdps: i64,
+    deaths: i64,
+    is_dead: bool,
+}
+
+// only the fields the summary needs, read straight from the serialized encounter
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct EncounterView {
+    fight_start: i64,
+    duration: i64,
+    difficulty: Option<String>,
+    cleared: bool,
+    local_player: String,
+    current_boss: Option<EntityView>,
+    entities: HashMap<String, EntityView>,
+    encounter_damage_stats: EncounterDamageView,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct EncounterDamageView {
+    total_damage_dealt: i64,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct EntityView {
+    name: String,
+    entity_type: EntityType,
+    class: String,
+    class_id: u32,
+    current_hp: i64,
+    max_hp: i64,
+    hp_bars: Option<u32>,
+    is_dead: bool,
+    damage_stats: DamageView,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct DamageView {
+    damage_dealt: i64,
+    dps: i64,
+    rdps: i64,
+    deaths: i64,
+}
+
+impl EncounterSummary {
+    fn from_payload(payload: &str) -> Option<Self> {
+        let encounter: EncounterView = serde_json::from_str(payload).ok()?;
+        let total_damage = encounter.encounter_damage_stats.total_damage_dealt;
+
+        let mut players: Vec<PlayerSummary> = encounter
+            .entities
+            .into_values()
+            .filter(|e| e.entity_type == EntityType::Player && e.damage_stats.damage_dealt > 0)
+            .map(|e| PlayerSummary {
+                damage_percent: if total_damage > 0 {
+                    e.damage_stats.damage_dealt as f64 / total_damage as f64 * 100.0
+                } else {
+                    0.0
+                },
+                name: e.name,
+                class: e.class,
+                class_id: e.class_id,
+                damage_dealt: e.damage_stats.damage_dealt,
+                dps: e.damage_stats.dps,
+                rdps: e.damage_stats.rdps,
+                deaths: e.damage_stats.deaths,
+                is_dead: e.is_dead,
+            })
+            .collect();
+        players.sort_by(|a, b| b.damage_dealt.cmp(&a.damage_dealt));
+
+        Some(Self {
+            fight_start: encounter.fight_start,
+            duration: encounter.duration,
+            difficulty: encounter.difficulty,
+            cleared: encounter.cleared,
+            local_player: encounter.local_player,
+            total_damage_dealt: total_damage,
+            boss: encounter.current_boss.map(|boss| BossSummary {
+                name: boss.name,
+                current_hp: boss.current_hp,
+                max_hp: boss.max_hp,
+                hp_bars: boss.hp_bars,
+            }),
+            players,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn summarizes_players_by_damage() {
+        let payload = json!({
+            "fightStart": 1000,
+            "duration": 60000,
+            "localPlayer": "Alpha",
+            "currentBoss": { "name": "Thaemine", "currentHp": 10, "maxHp": 100, "hpBars": 40 },
+            "encounterDamageStats": { "totalDamageDealt": 400 },
+            "entities": {
+                "Alpha": {
+                    "name": "Alpha", "entityType": "PLAYER", "class": "Bard", "classId": 204,
+                    "damageStats": { "damageDealt": 100, "dps": 5 }
+                },
+                "Beta": {
+                    "name": "Beta", "entityType": "PLAYER", "class": "Sorceress", "classId": 205,
+                    "damageStats": { "damageDealt": 300, "dps": 15, "deaths": 1 }
+                },
+                "Thaemine": {
+                    "name": "Thaemine", "entityType": "BOSS",
+                    "damageStats": { "damageDealt": 999 }
+                }
+            }
+        });
+
+        let summary = EncounterSummary::from_payload(&payload.to_string()).unwrap();
+
+        assert_eq!(summary.total_damage_dealt, 400);
+        assert_eq!(summary.boss.unwrap().hp_bars, Some(40));
+        let names: Vec<_> = summary.players.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["Beta", "Alpha"]);
+        assert_eq!(summary.players[0].damage_percent, 75.0);
+        assert_eq!(summary.players[0].deaths, 1);
+    }
+
+    #[test]
+    fn accepts_only_local_and_listed_origins() {
+        let allowed = ["https://overlay.example.com/".to_string()];
+
+        for origin in [
+            "file://",
+            "http://localhost:8080",
+            "http://127.0.0.1",
+            "https://[::1]:3000",
+            "https://overlay.example.com",
+        ] {
+            assert!(is_allowed_origin(origin, &allowed), "{origin}");
+        }
+        for origin in [
+            "null",
+            "https://example.com",
+            "http://localhost.example.com",
+            "chrome-extension://abc",
+        ] {
+            assert!(!is_allowed_origin(origin, &allowed), "{origin}");
+        }
+    }
+}
@@ -0,0 +1,364 @@
+//! Projects how a stored encounter would have gone with different gear.
+//!
+//! Every recorded hit of a player is run through the stat engine twice, once with the inspect
+//! snapshot the player had and once with an edited copy of it. The hit's damage is scaled by the
+//! ratio of the two expected damages, so everything the snapshot does not model (buffs from other
+//! players, boss defense, ...) stays as it was recorded.
+
+use anyhow::{Result, bail};
+use serde_json::Value;
+
+use crate::data::{EXTERNAL_ARK_PASSIVE_DATA, STAT_TYPE_MAP};
+use crate::live::entity_tracker::InspectSnapshot;
+use crate::live::inspect_stats::{DerivedAbilityFeature, replace_ark_passive_nodes};
+use crate::live::player_stats::PlayerStats;
+use crate::live::rdps::get_skill_groups;
+use crate::models::{
+    EncounterEntity, HitFlag, HitOption, Skill, SnapshotEdit, WhatIfReport, WhatIfSkill,
+};
+use crate::utils::{damage_gem_level_to_value, is_support_spec, support_damage_gem_level_to_value};
+
+/// Returns a copy of `snapshot` with `edit` applied to its derived stats. Gem levels are compared
+/// against the gems stored on the skills of `entity`.
+fn apply_edit(
+    entity: &EncounterEntity,
+    snapshot: &InspectSnapshot,
+    edit: &SnapshotEdit,
+) -> Result<InspectSnapshot> {
+    let mut edited = snapshot.clone();
+    let derived = &mut edited.derived_stats;
+
+    for (name, value) in &edit.stats {
+        let Some(stat_id) = STAT_TYPE_MAP
+            .get(name)
+            .and_then(|id| u8::try_from(*id).ok())
+        else {
+            bail!("unknown stat {name}");
+        };
+        *derived.stat_pairs.entry(stat_id).or_default() += value;
+    }
+    for (skill_id, multiplier) in &edit.skill_damage {
+        *derived
+            .skill_attack_power_multiplier_by_skill
+            .entry(*skill_id)
+            .or_default() += multiplier;
+    }
+    derived
+        .ability_features
+        .extend(edit.ability_features.iter().map(|f| DerivedAbilityFeature {
+            feature_type: f.feature_type.clone(),
+            level: f.level,
+            values: f.values.clone(),
+        }));
+
+    let support = entity.spec.as_deref().is_some_and(is_support_spec);
+    for (skill_id, level) in &edit.gem_levels {
+        let Some(skill) = entity.skills.get(skill_id) else {
+            bail!("unknown skill {skill_id}");
+        };
+        let tier = skill.gem_tier_dmg.unwrap_or(4);
+        let gem_value = |level| {
+            if support {
+                support_damage_gem_level_to_value(level)
+            } else {
+                damage_gem_level_to_value(level, tier)
+            }
+        };
+        let (Some(current), Some(target)) =
+            (gem_value(skill.gem_damage.unwrap_or(0)), gem_value(*level))
+        else {
+            bail!("invalid gem level {level}");
+        };
+        *derived
+            .skill_attack_power_multiplier_by_skill
+            .entry(*skill_id)
+            .or_default() += (target as f64 - current as f64) / 10000.0;
+    }
+
+    if !edit.ark_passive_nodes.is_empty() {
+        let nodes = snapshot
+            .ark_passive_data
+            .iter()
+            .flat_map(|data| [&data.evolution, &data.enlightenment, &data.leap])
+            .flatten()
+            .flatten()
+            .map(|node| (node.id, node.lv as u32))
+            .collect::<Vec<_>>();
+        let mut edited_nodes = nodes
+            .iter()
+            .filter(|(id, _)| !edit.ark_passive_nodes.contains_key(id))
+            .copied()
+            .collect::<Vec<_>>();
+        for (id, points) in &edit.ark_passive_nodes {
+            if !EXTERNAL_ARK_PASSIVE_DATA.contains_key(id) {
+                bail!("unknown ark passive node {id}");
+            }
+            if *points > 0 {
+                edited_nodes.push((*id, *points as u32));
+            }
+        }
+        replace_ark_passive_nodes(derived, &nodes, &edited_nodes, &snapshot.stat_pairs);
+    }
+
+    Ok(edited)
+}
+
+/// Replays the skill hits of `entity` with its stored inspect `snapshot` and with `edit` applied
+/// to it, see the module docs.
+pub fn simulate(
+    entity: &EncounterEntity,
+    snapshot: Value,
+    edit: &SnapshotEdit,
+) -> Result<WhatIfReport> {
+    let snapshot: InspectSnapshot = serde_json::from_value(snapshot)?;
+    let edited = apply_edit(entity, &snapshot, edit)?;
+    let load = |snapshot: &InspectSnapshot| {
+        let mut stats = PlayerStats::create();
+        stats.load_from_snapshot(snapshot, entity.id, entity.class_id);
+        stats
+    };
+
+    Ok(simulate_with_stats(
+        entity,
+        &load(&snapshot),
+        &load(&edited),
+    ))
+}
+
+fn simulate_with_stats(
+    entity: &EncounterEntity,
+    base: &PlayerStats,
+    edited: &PlayerStats,
+) -> WhatIfReport {
+    let mut report = WhatIfReport {
+        player: entity.name.clone(),
+        ..Default::default()
+    };
+
+    let mut skills: Vec<_> = entity.skills.values().collect();
+    skills.sort_by_key(|s| s.id);
+    for skill in skills {
+        let mut ratios = HitRatios::new(skill, base, edited);
+        let mut projected = 0.0;
+        let mut original = 0;
+
+        if skill.skill_cast_log.is_empty() {
+            // older encounters only kept the totals, split them by attack direction instead
+            let positional = skill.back_attack_damage + skill.front_attack_damage;
+            for (damage, option) in [
+                (skill.back_attack_damage, HitOption::BACK_ATTACK),
+                (skill.front_attack_damage, HitOption::FRONTAL_ATTACK),
+                (skill.total_damage - positional, HitOption::NONE),
+            ] {
+                projected += ratios.project(damage, option, &mut report.unmodeled_hits);
+                original += damage;
+            }
+        } else {
+            for hit in skill.skill_cast_log.iter().flat_map(|cast| &cast.hits) {
+                let option = if hit.back_attack {
+                    HitOption::BACK_ATTACK
+                } else if hit.front_attack {
+                    HitOption::FRONTAL_ATTACK
+                } else {
+                    HitOption::NONE
+                };
+                projected += ratios.project(hit.damage, option, &mut report.unmodeled_hits);
+                original += hit.damage;
+            }
+        }
+
+        let projected = projected.round() as i64;
+        report.original_damage += original;
+        report.projected_damage += projected;
+        report.skills.push(WhatIfSkill {
+            skill_id: skill.id,
+            name: skill.name.clone(),
+            original_damage: original,
+            projected_damage: projected,
+            delta: projected - original,
+        });
+    }
+
+    report
+        .skills
+        .sort_by_key(|s| std::cmp::Reverse(s.delta.abs()));
+
+    report
+}
+
+/// Edited / base expected damage of one skill, cached per attack direction.
+struct HitRatios<'a> {
+    skill: &'a Skill,
+    base: &'a PlayerStats,
+    edited: &'a PlayerStats,
+    cache: [Option<Option<f64>>; 3],
+}
+
+impl<'a> HitRatios<'a> {
+    fn new(skill: &'a Skill, base: &'a PlayerStats, edited: &'a PlayerStats) -> Self {
+        Self {
+            skill,
+            base,
+            edited,
+            cache: [None; 3],
+        }
+    }
+
+    fn project(&mut self, damage: i64, option: HitOption, unmodeled: &mut i64) -> f64 {
+        if damage <= 0 {
+            return 0.0;
+        }
+
+        let index = match option {
+            HitOption::BACK_ATTACK => 1,
+            HitOption::FRONTAL_ATTACK => 2,
+            _ => 0,
+        };
+        let ratio = *self.cache[index].get_or_insert_with(|| {
+            let base = expected_damage(self.base, self.skill, &option)?;
+            let edited = expected_damage(self.edited, self.skill, &option)?;
+            Some(edited / base)
+        });
+
+        match ratio {
+            Some(ratio) => damage as f64 * ratio,
+            None => {
+                *unmodeled += 1;
+                damage as f64
+            }
+        }
+    }
+}
+
+/// Damage of a hit relative to the stats, averaged over crits so crit rate edits count as well.
+fn expected_damage(stats: &PlayerStats, skill: &Skill, hit_option: &HitOption) -> Option<f64> {
+    if skill.special.unwrap_or_default() {
+        return None;
+    }
+
+    let groups = get_skill_groups(skill.id);
+    let mut stats = stats.clone();
+    stats.apply_dynamic_effects(
+        skill.id,
+        skill.id,
+        0,
+        groups,
+        groups,
+        None,
+        None,
+        Some(hit_option),
+        &[],
+        &[],
+        0,
+    );
+
+    let damage_attr = stats.resolve_damage_attr(None);
+    let can_crit = !skill.is_hyper_awakening;
+    let attack_power = stats
+        .calculate_final_attack_power(
+            hit_option,
+            &HitFlag::NORMAL,
+            damage_attr,
+            0,
+            skill.is_hyper_awakening,
+            true,
+            can_crit,
+            can_crit,
+        )
+        .value()
+        * (1.0 + stats.get_skill_attack_power_multiplier(skill.id));
+
+    (attack_power > 0.0).then_some(attack_power)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::SKILL_DATA;
+    use crate::live::player_stats::StatSource;
+    use crate::models::{SkillCast, SkillHit};
+    use hashbrown::HashMap;
+
+    fn hit(damage: i64, back_attack: bool) -> SkillHit {
+        SkillHit {
+            damage,
+            back_attack,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn scales_only_the_edited_skill() {
+        let _ = SKILL_DATA.set(HashMap::new());
+
+        let mut base = PlayerStats::create();
+        base.weapon_power.add_self(100_000.0, StatSource::Test);
+        base.int_stat.add_self(400_000.0, StatSource::Test);
+        let mut edited = base.clone();
+        edited.skill_attack_power_multiplier.insert(2, 0.1);
+
+        let skills = [
+            (1, vec![hit(1000, false)]),
+            (2, vec![hit(500, false), hit(500, true)]),
+        ]
+        .into_iter()
+        .map(|(id, hits)| {
+            let skill = Skill {
+                id,
+                skill_cast_log: vec![SkillCast {
+                    hits,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            };
+            (id, skill)
+        })
+        .collect();
+        let entity = EncounterEntity {
+            name: "Alpha".to_string(),
+            skills,
+            ..Default::default()
+        };
+
+        let report = simulate_with_stats(&entity, &base, &edited);
+
+        assert_eq!(report.original_damage, 2000);
+        assert_eq!(report.projected_damage, 2100);
+        assert_eq!(report.unmodeled_hits, 0);
+        assert_eq!(report.skills[0].skill_id, 2);
+        assert_eq!(report.skills[0].delta, 100);
+        assert_eq!(report.skills[1].delta, 0);
+    }
+
+    #[test]
+    fn gem_edits_apply_the_level_difference_to_the_skill() {
+        let entity = EncounterEntity {
+            skills: [(
+                2,
+                Skill {
+                    id: 2,
+                    gem_damage: Some(8),
+                    gem_tier_dmg: Some(4),
+                    ..Default::default()
+                },
+            )]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+        let edit = |level| SnapshotEdit {
+            gem_levels: [(2, level)].into_iter().collect(),
+            ..Default::default()
+        };
+        let multiplier = |level| {
+            apply_edit(&entity, &InspectSnapshot::default(), &edit(level))
+                .unwrap()
+                .derived_stats
+                .skill_attack_power_multiplier_by_skill[&2]
+        };
+
+        assert!((multiplier(10) - 0.08).abs() < 1e-9);
+        assert!((multiplier(0) + 0.36).abs() < 1e-9);
+        assert!(apply_edit(&entity, &InspectSnapshot::default(), &edit(11)).is_err());
+    }
+}
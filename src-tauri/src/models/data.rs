@@ -1,6 +1,7 @@
 use crate::models::utils::{
     int_or_string_as_option_string, int_or_string_as_string, null_or_default,
 };
+use chrono::Weekday;
 use hashbrown::HashMap;
 use serde::{Deserialize, Serialize};
 
@@ -143,7 +144,239 @@ pub struct Esther {
     pub skills: Vec<i32>,
 }
 
+/// Raid zones, difficulties and boss specific rules from `raids.json`.
 #[derive(Debug, Default, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RaidDefinitions {
+    /// difficulty by zone level, unless the zone defines its own
+    pub difficulties: Vec<ZoneDifficulty>,
+    pub zones: Vec<RaidZone>,
+    /// difficulties announced by `PKTRaidBegin` instead of the zone level
+    pub raid_difficulties: Vec<RaidDifficulty>,
+    pub triggers: RaidTriggers,
+    /// difficulty that depends on the boss max hp, e.g. extreme aegir
+    pub hp_difficulties: Vec<HpRule>,
+    /// gate that depends on the boss max hp, for bosses reused across raids
+    pub hp_gates: Vec<HpRule>,
+    pub gate_order: HashMap<String, Vec<String>>,
+    /// phases of bosses without their own entry in `phase_splits`
+    pub default_phase_splits: Vec<PhaseSplitDefinition>,
+    pub phase_splits: HashMap<String, Vec<PhaseSplitDefinition>>,
+    /// reset of regions without their own entry in `weekly_resets`
+    pub default_weekly_reset: WeeklyReset,
+    pub weekly_resets: HashMap<String, WeeklyReset>,
+}
+
+#[derive(Debug, Default, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ZoneDifficulty {
+    pub level: u32,
+    pub name: String,
+    pub id: u32,
+}
+
+#[derive(Debug, Default, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RaidZone {
+    pub name: String,
+    pub zone_ids: Vec<u32>,
+    pub difficulties: Vec<ZoneDifficulty>,
+    pub intermission: Option<Intermission>,
+}
+
+/// Moving into an intermission zone pauses the encounter instead of resetting it.
+#[derive(Debug, Default, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Intermission {
+    /// difficulties where the raid is already cleared when the zone is entered
+    pub clear_difficulties: Vec<String>,
+    /// bosses whose appearance ends the intermission
+    pub end_bosses: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RaidDifficulty {
+    pub name: String,
+    pub id: u32,
+    pub raid_ids: Vec<u32>,
+}
+
+#[derive(Debug, Default, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RaidTriggers {
+    pub clear: Vec<i64>,
+    pub wipe: Vec<i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RaidTrigger {
+    Clear,
+    Wipe,
+}
+
+#[derive(Debug, Default, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HpRule {
+    pub boss: String,
+    /// only applies while the raid has this difficulty
+    pub difficulty: Option<String>,
+    /// checked in order, the first tier the max hp is above wins
+    pub tiers: Vec<HpTier>,
+}
+
+#[derive(Debug, Default, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HpTier {
+    pub above: Option<i64>,
+    pub name: String,
+}
+
+/// A phase ends at the first of its conditions that is reached, or at the kill without any.
+#[derive(Debug, Default, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PhaseSplitDefinition {
+    pub name: String,
+    /// boss hp percent at or below which the phase ends
+    pub percent: Option<f32>,
+    /// hp bars the boss has left when the phase ends
+    pub bars: Option<u32>,
+    /// trigger signal ending the phase
+    pub trigger: Option<i64>,
+    /// phase transition code ending the phase, e.g. `1` when a boss of a multi boss gate dies
+    pub phase: Option<i64>,
+}
+
+/// Weekday and utc hour at which weekly raid rewards reset.
+#[derive(Debug, Default, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WeeklyReset {
+    pub day: String,
+    pub hour: u32,
+}
+
+impl RaidDefinitions {
+    pub fn zone_difficulty(&self, zone_id: u32, zone_level: u32) -> Option<&ZoneDifficulty> {
+        let difficulties = self
+            .zone(zone_id)
+            .filter(|zone| !zone.difficulties.is_empty())
+            .map_or(&self.difficulties, |zone| &zone.difficulties);
+
+        difficulties
+            .iter()
+            .find(|difficulty| difficulty.level == zone_level)
+    }
+
+    pub fn raid_difficulty(&self, raid_id: u32) -> Option<&RaidDifficulty> {
+        self.raid_difficulties
+            .iter()
+            .find(|difficulty| difficulty.raid_ids.contains(&raid_id))
+    }
+
+    pub fn trigger(&self, signal: i64) -> Option<RaidTrigger> {
+        if self.triggers.clear.contains(&signal) {
+            Some(RaidTrigger::Clear)
+        } else if self.triggers.wipe.contains(&signal) {
+            Some(RaidTrigger::Wipe)
+        } else {
+            None
+        }
+    }
+
+    pub fn intermission(&self, zone_id: u32) -> Option<&Intermission> {
+        self.zone(zone_id)
+            .and_then(|zone| zone.intermission.as_ref())
+    }
+
+    pub fn ends_intermission(&self, boss: &str) -> bool {
+        self.zones
+            .iter()
+            .filter_map(|zone| zone.intermission.as_ref())
+            .any(|intermission| intermission.end_bosses.iter().any(|name| name == boss))
+    }
+
+    pub fn adjusted_difficulty(&self, boss: &str, difficulty: &str, max_hp: i64) -> Option<&str> {
+        self.hp_difficulties
+            .iter()
+            .filter(|rule| rule.boss == boss && rule.difficulty.as_deref() == Some(difficulty))
+            .find_map(|rule| rule.tier(max_hp))
+    }
+
+    pub fn hp_gate(&self, boss: &str, max_hp: i64) -> Option<&str> {
+        self.hp_gates
+            .iter()
+            .filter(|rule| rule.boss == boss)
+            .find_map(|rule| rule.tier(max_hp))
+    }
+
+    /// Raid of `gate` and its position within the raid.
+    pub fn gate_position(&self, gate: &str) -> Option<(&str, usize)> {
+        self.gate_order.iter().find_map(|(raid, gates)| {
+            gates
+                .iter()
+                .position(|name| name == gate)
+                .map(|index| (raid.as_str(), index))
+        })
+    }
+
+    pub fn phase_splits(&self, boss: &str) -> &[PhaseSplitDefinition] {
+        self.phase_splits
+            .get(boss)
+            .unwrap_or(&self.default_phase_splits)
+    }
+
+    pub fn weekly_reset(&self, region: Option<&str>) -> &WeeklyReset {
+        region
+            .and_then(|region| self.weekly_resets.get(region))
+            .unwrap_or(&self.default_weekly_reset)
+    }
+
+    fn zone(&self, zone_id: u32) -> Option<&RaidZone> {
+        self.zones
+            .iter()
+            .find(|zone| zone.zone_ids.contains(&zone_id))
+    }
+}
+
+impl Intermission {
+    pub fn clears_raid(&self, difficulty: &str) -> bool {
+        self.clear_difficulties
+            .iter()
+            .any(|clear_difficulty| clear_difficulty == difficulty)
+    }
+}
+
+impl WeeklyReset {
+    const HOUR_MS: i64 = 60 * 60 * 1000;
+    const WEEK_MS: i64 = 7 * 24 * Self::HOUR_MS;
+
+    /// Most recent reset at or before `now`, both in unix ms.
+    pub fn last_reset(&self, now: i64) -> i64 {
+        let day = self.day.parse::<Weekday>().unwrap_or(Weekday::Wed);
+        // the unix epoch was a thursday
+        let days_after_epoch = (day.num_days_from_monday() as i64
+            - Weekday::Thu.num_days_from_monday() as i64)
+            .rem_euclid(7);
+        let anchor = (days_after_epoch * 24 + self.hour as i64) * Self::HOUR_MS;
+
+        now - (now - anchor).rem_euclid(Self::WEEK_MS)
+    }
+
+    pub fn next_reset(&self, now: i64) -> i64 {
+        self.last_reset(now) + Self::WEEK_MS
+    }
+}
+
+impl HpRule {
+    fn tier(&self, max_hp: i64) -> Option<&str> {
+        self.tiers
+            .iter()
+            .find(|tier| tier.above.is_none_or(|above| max_hp > above))
+            .map(|tier| tier.name.as_str())
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct ExternalResourceAddon {
     #[serde(rename = "type", default, deserialize_with = "null_or_default")]
@@ -485,3 +718,33 @@ pub struct ExternalItemClassOptionData {
     #[serde(default, deserialize_with = "null_or_default")]
     pub class_options: HashMap<u32, ExternalResourceAddon>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_last_reset_on_the_configured_weekday_and_hour() {
+        let reset = WeeklyReset {
+            day: "Wednesday".to_string(),
+            hour: 10,
+        };
+        // 2024-01-03 10:00 utc was a wednesday
+        let wednesday = 1_704_276_000_000;
+        let hour = 60 * 60 * 1000;
+
+        assert_eq!(reset.last_reset(wednesday), wednesday);
+        assert_eq!(reset.last_reset(wednesday - 1), wednesday - 7 * 24 * hour);
+        assert_eq!(reset.last_reset(wednesday + 50 * hour), wednesday);
+        assert_eq!(
+            reset.next_reset(wednesday + hour),
+            wednesday + 7 * 24 * hour
+        );
+
+        let korea = WeeklyReset {
+            day: "Tuesday".to_string(),
+            hour: 21,
+        };
+        assert_eq!(korea.last_reset(wednesday), wednesday - 13 * hour);
+    }
+}
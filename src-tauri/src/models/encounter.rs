@@ -4,12 +4,13 @@ use hashbrown::{HashMap, HashSet};
 use serde::{Deserialize, Serialize};
 use serde_with::DisplayFromStr;
 use serde_with::serde_as;
+use strum_macros::{AsRefStr, EnumString};
 
 use crate::models::EntityType;
 use crate::models::StatusEffect;
 
-#[derive(Debug, Serialize, Clone, Default)]
-#[serde(rename_all = "camelCase")]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
 pub struct Encounter {
     pub last_combat_packet: i64,
     pub fight_start: i64,
@@ -29,8 +30,8 @@ pub struct Encounter {
     pub region: Option<String>,
 }
 
-#[derive(Debug, Serialize, Clone, Default)]
-#[serde(rename_all = "camelCase")]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
 pub struct EncounterDamageStats {
     pub total_damage_dealt: i64,
     pub top_damage_dealt: i64,
@@ -47,11 +48,32 @@ pub struct EncounterDamageStats {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub misc: Option<EncounterMisc>,
     pub boss_hp_log: HashMap<String, Vec<BossHpLog>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stagger: Option<EncounterStagger>,
+    /// only set on live snapshots
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pace: Option<EncounterPace>,
 }
 
-#[serde_as]
-#[derive(Debug, Default, Serialize, Clone)]
+/// Progress of the current pull against the fastest stored clear of the boss.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+pub struct EncounterPace {
+    pub reference_id: i32,
+    pub boss_hp_percent: f32,
+    /// ms since fight start
+    pub elapsed: i64,
+    /// ms the fastest clear took to bring the boss to the same hp
+    pub reference_elapsed: Option<i64>,
+    /// ms behind the fastest clear, negative when ahead
+    pub delta: Option<i64>,
+    pub projected_kill: Option<i64>,
+    pub reference_kill: i64,
+}
+
+#[serde_as]
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
 pub struct EncounterEntity {
     pub id: u64,
     #[serde_as(as = "DisplayFromStr")]
@@ -83,6 +105,18 @@ pub struct EncounterEntity {
     pub loadout_hash: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub combat_power: Option<f32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub battle_items: Vec<BattleItemUse>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BattleItemUse {
+    /// only known for the local player, other players' items are seen as thrown projectiles
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item_id: Option<u32>,
+    pub name: String,
+    pub timestamp: i64,
 }
 
 #[serde_as]
@@ -92,6 +126,8 @@ pub struct DamageStats {
     pub damage_dealt: i64,
     pub hyper_awakening_damage: i64,
     pub damage_taken: i64,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub damage_taken_by: Vec<DamageTakenSource>,
     pub buffed_by: HashMap<u32, i64>,
     pub debuffed_by: HashMap<u32, i64>,
     pub buffed_by_support: i64,
@@ -147,6 +183,35 @@ pub struct DamageStats {
 pub struct DeathInfo {
     pub death_time: i64,
     pub dead_for: Option<i64>,
+    /// last hits taken before dying, oldest first
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub recap: Vec<DamageTakenEvent>,
+    /// incapacitations still running when the player died
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub incapacitations: Vec<IncapacitatedEvent>,
+}
+
+/// Damage a player took from one skill of one source.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DamageTakenSource {
+    pub source: String,
+    pub skill_id: u32,
+    pub skill_name: String,
+    pub damage: i64,
+    pub hits: i64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DamageTakenEvent {
+    pub timestamp: i64,
+    pub source: String,
+    pub skill_id: u32,
+    pub skill_name: String,
+    pub damage: i64,
+    /// hp left after the hit
+    pub current_hp: i64,
 }
 
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
@@ -184,7 +249,14 @@ pub struct Identity {
     pub gauge3: u32,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Stagger {
+    pub current: u32,
+    pub max: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct IncapacitatedEvent {
     #[serde(rename = "type")]
@@ -200,31 +272,80 @@ pub enum IncapacitationEventType {
     CROWD_CONTROL,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, AsRefStr, EnumString)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum TimelineEventKind {
+    RaidStart,
+    PhaseTransition,
+    BossKill,
+    Death,
+    Revive,
+    CrowdControl,
+    Counter,
+    AbnormalMove,
+    Trigger,
+    Intermission,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineEvent {
+    /// ms since fight start
+    pub time: i64,
+    pub kind: TimelineEventKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entity: Option<String>,
+    /// phase code, trigger signal or incapacitation duration in ms, depending on the kind
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PhaseSplit {
+    pub name: String,
+    /// ms since fight start the phase ended at
+    pub end: i64,
+}
+
 pub type IdentityLog = Vec<(i64, (u32, u32, u32))>;
 
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", default)]
 pub struct IdentityArcanist {
     // timestamp, (percentage, card, card)
     pub log: Vec<(i32, (f32, u32, u32))>,
     pub average: f64,
     pub card_draws: HashMap<u32, u32>,
+    pub cards_used: u32,
+    /// share of the fight with at least one card in hand
+    pub uptime: f64,
 }
 
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", default)]
 pub struct IdentityArtistBard {
     // timestamp, (percentage, bubble)
     pub log: Vec<(i32, (f32, u32))>,
     pub average: f64,
+    pub bubbles_used: u32,
+    /// share of the fight with at least one bubble ready
+    pub uptime: f64,
 }
 
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", default)]
 pub struct IdentityGeneric {
     // timestamp, percentage
     pub log: Vec<(i32, f32)>,
     pub average: f64,
+    // timestamp, stance
+    pub stance_log: Vec<(i32, u8)>,
+    /// stance -> ms held, the neutral stance 0 is left out
+    pub stance_durations: HashMap<u8, i64>,
+    /// share of the fight in a stance, or with a full gauge for classes without stances
+    pub uptime: f64,
 }
 
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
@@ -326,6 +447,38 @@ impl BossHpLog {
     }
 }
 
+/// Stagger gauges of the bosses over the fight and every time one of them was filled up.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct EncounterStagger {
+    /// boss name -> gauge samples, one per second at most
+    pub log: HashMap<String, Vec<StaggerLog>>,
+    pub checks: Vec<StaggerCheck>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct StaggerLog {
+    /// seconds since fight start
+    pub time: i32,
+    /// part of the gauge still left to fill, 1 when the gauge appears
+    pub p: f32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct StaggerCheck {
+    pub boss: String,
+    /// ms since fight start
+    pub start: i64,
+    pub end: i64,
+    pub max_stagger: i64,
+    /// the gauge was filled, otherwise it reset or the fight ended first
+    pub success: bool,
+    /// stagger dealt by each player while the gauge was up
+    pub contributions: HashMap<String, i64>,
+}
+
 #[derive(Debug, Default, Deserialize, Clone)]
 pub struct EngravingData {
     pub id: u32,
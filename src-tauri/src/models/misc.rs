@@ -2,6 +2,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::settings::Settings;
 use hashbrown::HashMap;
+use serde_json::Value;
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct LoadResult {
@@ -152,6 +153,182 @@ pub struct CharacterStatisticsUnavailable {
     pub support_logs: i32,
 }
 
+#[derive(Debug, Default, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RosterStatisticsCriteria {
+    pub range: String,
+    pub mode: String,
+    pub damage_type: String,
+    pub boss_to_raid: HashMap<String, String>,
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+    pub difficulty: String,
+    pub min_duration: i32,
+}
+
+#[derive(Debug, Default, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RosterStatistics {
+    pub characters: Vec<CharacterStatistics>,
+    pub weeks: Vec<RosterWeek>,
+    pub specs: Vec<RosterSpecPerformance>,
+    pub raids: Vec<RosterRaidProgression>,
+}
+
+#[derive(Debug, Default, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RosterWeek {
+    pub start_time: i64,
+    pub attempts: i32,
+    pub clears: i32,
+    pub characters: i32,
+    pub raid_time: i64,
+}
+
+#[derive(Debug, Default, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RosterSpecPerformance {
+    pub class_id: i32,
+    pub spec: Option<String>,
+    pub characters: Vec<String>,
+    pub attempts: i32,
+    pub clears: i32,
+    pub clear_rate: f32,
+    pub median_dps: Option<i64>,
+    pub best_dps: Option<i64>,
+    pub median_rdps: Option<i64>,
+    pub best_rdps: Option<i64>,
+}
+
+#[derive(Debug, Default, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RosterRaidProgression {
+    pub raid: String,
+    pub difficulty: Option<String>,
+    pub attempts: i32,
+    pub clears: i32,
+    pub clear_rate: f32,
+    pub first_clear: Option<i64>,
+    pub last_clear: Option<i64>,
+    pub characters: Vec<RosterRaidCharacter>,
+}
+
+#[derive(Debug, Default, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RosterRaidCharacter {
+    pub name: String,
+    pub class_id: i32,
+    pub attempts: i32,
+    pub clears: i32,
+    pub first_clear: Option<i64>,
+    pub last_clear: Option<i64>,
+    pub median_dps: Option<i64>,
+    pub best_dps: Option<i64>,
+}
+
+#[derive(Debug, Default, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LoadoutProgressionCriteria {
+    pub character: CharacterInfo,
+    pub range: String,
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+    pub bosses: Vec<String>,
+    pub difficulty: String,
+    pub min_duration: i32,
+}
+
+#[derive(Debug, Default, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadoutProgression {
+    pub timeline: Vec<LoadoutSnapshot>,
+    pub changes: Vec<LoadoutChange>,
+}
+
+#[derive(Debug, Default, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadoutSnapshot {
+    pub encounter_id: i32,
+    pub fight_start: i64,
+    pub boss_name: String,
+    pub difficulty: Option<String>,
+    pub gear_score: f32,
+    pub combat_power: Option<f32>,
+    pub loadout_hash: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadoutChange {
+    pub encounter_id: i32,
+    pub fight_start: i64,
+    pub loadout_hash: Option<String>,
+    pub previous_loadout_hash: Option<String>,
+    pub gear_score: f32,
+    pub previous_gear_score: f32,
+    pub combat_power: Option<f32>,
+    pub previous_combat_power: Option<f32>,
+    pub ark_passive_changed: bool,
+    pub bosses: Vec<LoadoutBossComparison>,
+}
+
+#[derive(Debug, Default, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadoutBossComparison {
+    pub boss_name: String,
+    pub difficulty: Option<String>,
+    pub before: LoadoutPerformance,
+    pub after: LoadoutPerformance,
+    pub dps_change: Option<f32>,
+    pub rdps_change: Option<f32>,
+}
+
+#[derive(Debug, Default, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadoutPerformance {
+    pub clears: i32,
+    pub median_dps: Option<i64>,
+    pub median_rdps: Option<i64>,
+}
+
+#[derive(Debug, Default, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SupportSynergyCriteria {
+    pub range: String,
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+    pub bosses: Vec<String>,
+    pub difficulty: String,
+    pub min_duration: i32,
+    pub cleared_only: bool,
+}
+
+#[derive(Debug, Default, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SupportSynergy {
+    pub character_id: u64,
+    pub name: String,
+    pub class_id: u32,
+    pub spec: Option<String>,
+    pub pulls: i32,
+    pub supports: Vec<SupportPairing>,
+}
+
+#[derive(Debug, Default, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SupportPairing {
+    pub character_id: u64,
+    pub name: String,
+    pub class_id: u32,
+    pub spec: Option<String>,
+    pub pulls: i32,
+    pub clears: i32,
+    pub average_rdps: i64,
+    pub best_rdps: i64,
+    pub total_damage: i64,
+    pub last_seen: i64,
+}
+
 #[derive(Debug, Default, Deserialize, Clone)]
 #[serde(rename_all = "camelCase", default)]
 pub struct RaidProgressionCriteria {
@@ -248,6 +425,204 @@ pub struct RaidProgressionPull {
     pub player_count: i32,
 }
 
+#[derive(Debug, Default, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RaidRunCriteria {
+    pub range: String,
+    pub raid: String,
+    pub difficulty: String,
+    pub local_player: String,
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+}
+
+#[derive(Debug, Default, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RaidRunStatistics {
+    pub runs: Vec<RaidRun>,
+    pub full_clears: i32,
+    /// start to end of the fully cleared runs, walking between gates included
+    pub average_full_clear_time: Option<i64>,
+    pub fastest_full_clear_time: Option<i64>,
+    pub average_active_time: Option<i64>,
+    pub average_idle_time: Option<i64>,
+}
+
+#[derive(Debug, Default, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RaidRun {
+    pub raid: String,
+    pub difficulty: String,
+    pub local_player: String,
+    pub start: i64,
+    pub end: i64,
+    /// time spent in combat
+    pub active_time: i64,
+    pub idle_time: i64,
+    pub pulls: i32,
+    pub full_clear: bool,
+    pub gates: Vec<RaidRunGate>,
+}
+
+#[derive(Debug, Default, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RaidRunGate {
+    pub gate: String,
+    pub boss_name: String,
+    pub pulls: i32,
+    pub cleared: bool,
+    pub active_time: i64,
+    /// the clearing encounter, or the last pull
+    pub encounter_id: i32,
+}
+
+#[derive(Debug, Default, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WeeklyCharacter {
+    pub character: CharacterInfo,
+    /// region of the latest encounter, decides the reset time
+    pub region: Option<String>,
+    pub week_start: i64,
+    pub next_reset: i64,
+    pub raids: Vec<WeeklyRaid>,
+}
+
+#[derive(Debug, Default, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct WeeklyRaid {
+    pub raid: String,
+    pub gate_count: i32,
+    pub gates: Vec<WeeklyGateClear>,
+}
+
+#[derive(Debug, Default, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct WeeklyGateClear {
+    pub gate: String,
+    pub boss_name: String,
+    pub difficulty: String,
+    /// first clear of the gate on this difficulty since the reset
+    pub encounter_id: i32,
+    pub cleared_at: i64,
+}
+
+#[derive(Debug, Default, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TeammateCriteria {
+    pub range: String,
+    /// matches any name the character has used
+    pub search: String,
+    pub min_pulls: i32,
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+}
+
+#[derive(Debug, Default, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Teammate {
+    pub character_id: u64,
+    /// latest name
+    pub name: String,
+    pub names: Vec<TeammateName>,
+    pub class_id: i32,
+    pub class: String,
+    pub spec: Option<String>,
+    pub max_gear_score: f32,
+    pub shared_pulls: i32,
+    pub shared_clears: i32,
+    pub clear_rate: f32,
+    pub first_seen: i64,
+    pub last_seen: i64,
+    pub bosses: Vec<TeammateBoss>,
+}
+
+#[derive(Debug, Default, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TeammateName {
+    pub name: String,
+    pub first_seen: i64,
+    pub last_seen: i64,
+}
+
+#[derive(Debug, Default, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TeammateBoss {
+    pub boss_name: String,
+    pub difficulty: Option<String>,
+    pub pulls: i32,
+    pub clears: i32,
+    /// of cleared pulls
+    pub median_dps: Option<i64>,
+    pub median_rdps: Option<i64>,
+}
+
+#[derive(Debug, Default, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TeammateDetails {
+    pub teammate: Teammate,
+    /// newest first
+    pub pulls: Vec<TeammatePull>,
+}
+
+#[derive(Debug, Default, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TeammatePull {
+    pub id: i32,
+    pub fight_start: i64,
+    pub boss_name: String,
+    pub difficulty: Option<String>,
+    pub duration: i64,
+    pub cleared: bool,
+    pub local_player: String,
+    pub name: String,
+    pub class_id: i32,
+    pub spec: Option<String>,
+    pub gear_score: f32,
+    pub dps: i64,
+    pub rdps: Option<i64>,
+}
+
+#[derive(Debug, Default, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PhaseSplitStatistics {
+    pub boss_name: String,
+    pub difficulty: String,
+    pub clears: i32,
+    pub phases: Vec<BestPhaseSplit>,
+    /// sum of the best split of every phase, if each one has been cleared
+    pub sum_of_best: Option<i64>,
+    pub best_clear: Option<i64>,
+    pub best_clear_id: Option<i32>,
+    pub pull: Option<PullPhaseSplits>,
+}
+
+#[derive(Debug, Default, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BestPhaseSplit {
+    pub name: String,
+    pub duration: i64,
+    pub encounter_id: i32,
+}
+
+#[derive(Debug, Default, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PullPhaseSplits {
+    pub id: i32,
+    pub phases: Vec<PullPhaseSplit>,
+    /// time lost against the best splits of the phases this pull finished
+    pub time_lost: i64,
+}
+
+#[derive(Debug, Default, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PullPhaseSplit {
+    pub name: String,
+    pub end: i64,
+    pub duration: i64,
+    pub best: Option<i64>,
+    pub delta: Option<i64>,
+}
+
 #[derive(Debug, Default, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct RaidProgressionPlayer {
@@ -290,6 +665,16 @@ pub struct SearchFilter {
     pub local_player: String,
 }
 
+/// A name a character id was seen under.
+#[derive(Debug, Default, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CharacterName {
+    pub character_id: u64,
+    pub name: String,
+    pub first_seen: i64,
+    pub last_seen: i64,
+}
+
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct CharacterInfo {
@@ -305,6 +690,108 @@ pub struct EncounterDbInfo {
     pub size: String,
     pub total_encounters: i32,
     pub total_encounters_filtered: i32,
+    /// `PRAGMA integrity_check` result, only filled in when a check was requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub integrity: Option<String>,
+}
+
+/// Encounters picked for a bulk export, using the same filters as the logs list or the character
+/// statistics page.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum EncounterSelection {
+    #[serde(rename_all = "camelCase")]
+    Search {
+        search: String,
+        filter: SearchFilter,
+    },
+    #[serde(rename_all = "camelCase")]
+    Character {
+        criteria: CharacterStatisticsCriteria,
+    },
+}
+
+#[derive(Default, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CsvExportSummary {
+    pub encounters: i32,
+    pub player_rows: i32,
+    pub skill_rows: i32,
+    pub players_path: String,
+    pub skills_path: String,
+}
+
+#[derive(Default, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseMergeSummary {
+    pub imported: i32,
+    pub duplicates: i32,
+}
+
+/// Differences between the inspect snapshots a player had in two encounters.
+#[derive(Default, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InspectSnapshotDiff {
+    pub name: String,
+    pub before_encounter: i32,
+    pub after_encounter: i32,
+    /// both encounters point at the same stored snapshot
+    pub identical: bool,
+    pub changes: Vec<InspectSnapshotChange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InspectSnapshotChange {
+    /// dotted path into the snapshot, e.g. `equippedItems[3].rawHoneLevel`
+    pub path: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+/// Changes applied on top of a player's inspect snapshot, in the same units the inspect data uses.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SnapshotEdit {
+    /// stat type name (e.g. `criticalhit`, `weapon_dam`) -> raw value added to the inspect stats
+    pub stats: HashMap<String, i64>,
+    /// skill id -> additional skill damage multiplier, `0.04` for +4% damage on that skill
+    pub skill_damage: HashMap<u32, f64>,
+    /// ark passive / engraving features to add, as found in the ability data
+    pub ability_features: Vec<AbilityFeatureEdit>,
+    /// skill id -> damage gem level, `0` takes the gem off
+    pub gem_levels: HashMap<u32, u8>,
+    /// ark passive node id -> points, `0` removes the node, nodes not in the snapshot are added
+    pub ark_passive_nodes: HashMap<u32, u8>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AbilityFeatureEdit {
+    pub feature_type: String,
+    pub level: u32,
+    pub values: Vec<i64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WhatIfReport {
+    pub player: String,
+    pub original_damage: i64,
+    pub projected_damage: i64,
+    pub skills: Vec<WhatIfSkill>,
+    /// hits the stat engine could not evaluate, counted with their recorded damage
+    pub unmodeled_hits: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WhatIfSkill {
+    pub skill_id: u32,
+    pub name: String,
+    pub original_damage: i64,
+    pub projected_damage: i64,
+    pub delta: i64,
 }
 
 #[derive(Debug, Clone)]
@@ -313,7 +800,7 @@ pub struct CastEvent {
     pub cooldown_duration_ms: i64,
 }
 
-#[derive(Debug, Default, Clone, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase", default)]
 pub struct InspectInfo {
     pub combat_power: Option<CombatPower>,
@@ -360,7 +847,7 @@ pub struct CombatPower {
     pub score: f32,
 }
 
-#[derive(Debug, Default, Clone, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase", default)]
 pub struct GemData {
     pub tier: u8,
@@ -27,6 +27,21 @@ pub struct GeneralSettings {
     pub mini: bool,
     pub beta_channel: bool,
     pub exitlag_compat: bool,
+    /// write raw packet captures of every session to the captures folder for offline replay
+    pub record_packet_captures: bool,
+    /// publish live encounter updates over a local websocket for stream overlays and bots
+    pub live_server: bool,
+    #[serde(default = "default_live_server_port")]
+    pub live_server_port: u16,
+    /// browser origins allowed to connect besides `file://` and localhost pages
+    pub live_server_origins: Vec<String>,
+    /// periodically copy encounters.db into the backups folder
+    pub auto_backup: bool,
+    #[serde(default = "default_backup_interval_hours")]
+    pub backup_interval_hours: u32,
+    /// number of backups kept, older ones are deleted
+    #[serde(default = "default_backup_retention")]
+    pub backup_retention: u32,
     #[serde(flatten)]
     pub extra: Map<String, Value>,
 }
@@ -34,3 +49,15 @@ pub struct GeneralSettings {
 fn default_true() -> bool {
     true
 }
+
+fn default_live_server_port() -> u16 {
+    crate::constants::DEFAULT_LIVE_SERVER_PORT
+}
+
+fn default_backup_interval_hours() -> u32 {
+    crate::constants::DEFAULT_BACKUP_INTERVAL_HOURS
+}
+
+fn default_backup_retention() -> u32 {
+    crate::constants::DEFAULT_BACKUP_RETENTION
+}
@@ -4,6 +4,7 @@ use std::{
         Arc,
         atomic::{AtomicBool, Ordering},
     },
+    time::Duration,
 };
 
 use log::*;
@@ -14,6 +15,7 @@ use crate::api::BanList;
 use crate::{
     constants::{BETA_ENDPOINT, DEFAULT_PORT},
     context::AppContext,
+    database::{Database, backup::spawn_backup_schedule},
     settings::*,
     shell::ShellManager,
     ui::{AppHandleExtensions, WindowExtensions, setup_tray},
@@ -44,6 +46,17 @@ pub fn setup(app: &mut App) -> Result<(), Box<dyn Error>> {
             .unwrap_or(false);
     let update_checked: Arc<AtomicBool> = check_updates(app_handle, is_beta);
 
+    if let Some(general) = settings.as_ref().map(|s| &s.general)
+        && general.auto_backup
+    {
+        spawn_backup_schedule(
+            app.state::<Database>().inner().clone(),
+            context.backups_path.clone(),
+            Duration::from_secs(u64::from(general.backup_interval_hours.max(1)) * 3600),
+            general.backup_retention.max(1) as usize,
+        );
+    }
+
     let exitlag_compat = settings
         .as_ref()
         .map(|s| s.general.exitlag_compat)
@@ -52,23 +65,70 @@ pub fn setup(app: &mut App) -> Result<(), Box<dyn Error>> {
     #[cfg(feature = "meter-core")]
     tokio::task::spawn(async move {
         while !update_checked.load(Ordering::Relaxed) {
-            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            tokio::time::sleep(Duration::from_millis(100)).await;
         }
 
         info!("done checking for updates, starting packet handling");
 
-        let ipc = crate::nineveh::setup_nineveh(app_handle.clone(), exitlag_compat)
-            .await
-            .expect("could not setup nineveh IPC");
-
         use crate::{
             api::{HeartBeatApi, StatsApi},
-            live::StartArgs,
+            database::Repository,
+            live::{
+                SaveContext, StartArgs,
+                capture::{CaptureRecorder, ReplayPacing, replay_capture},
+                sink::{EventSink, FanoutSink},
+                websocket::WebSocketSink,
+            },
             local::LocalPlayerRepository,
         };
 
         let context = app_handle.state::<AppContext>();
 
+        // developer hook: replay a recorded capture instead of attaching to the game
+        let replay_path = std::env::var_os("LOA_LOGS_REPLAY_CAPTURE").map(std::path::PathBuf::from);
+        let (ipc, replay_clock) = match replay_path {
+            Some(path) => {
+                let replay = replay_capture(
+                    &path,
+                    tokio::runtime::Handle::current(),
+                    ReplayPacing::Realtime(1.0),
+                )
+                .expect("could not open packet capture");
+                info!(
+                    "replaying packet capture {} recorded on v{}",
+                    path.display(),
+                    replay.header.app_version
+                );
+                if let Ok(mut region) = context.region.write() {
+                    *region = replay.header.region;
+                }
+                (replay.ipc, Some(replay.clock))
+            }
+            None => {
+                let ipc = crate::nineveh::setup_nineveh(app_handle.clone(), exitlag_compat)
+                    .await
+                    .expect("could not setup nineveh IPC");
+                (ipc, None)
+            }
+        };
+
+        let capture = if replay_clock.is_none()
+            && settings
+                .as_ref()
+                .is_some_and(|s| s.general.record_packet_captures)
+        {
+            let region = context.region.read().ok().and_then(|r| r.clone());
+            match CaptureRecorder::create(&context.captures_path, &context.version, region) {
+                Ok(recorder) => Some(recorder),
+                Err(e) => {
+                    warn!("could not start packet capture: {e}");
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
         let base_url = option_env!("STATS_API")
             .unwrap_or("https://api.snow.xyz")
             .to_owned();
@@ -84,20 +144,48 @@ pub fn setup(app: &mut App) -> Result<(), Box<dyn Error>> {
             context.version.clone(),
         );
         let ban_list = BanList::new();
-        app_handle.manage(StatsApi::new(
+        let stats_api = StatsApi::new(
             base_url,
             local_info.client_id.clone(),
             context.version.clone(),
-        ));
+        );
+        let save_context = SaveContext {
+            repository: app_handle.state::<Repository>().inner().clone(),
+            stats_api: Some(stats_api),
+            meter_version: app_handle.package_info().version.to_string(),
+        };
+        let window_sink: Arc<dyn EventSink> = Arc::new(app_handle.clone());
+        let mut sink = FanoutSink::new(vec![window_sink]);
+        if let Some(general) = settings.as_ref().map(|s| &s.general)
+            && general.live_server
+        {
+            let runtime = tokio::runtime::Handle::current();
+            match WebSocketSink::start(
+                &runtime,
+                general.live_server_port,
+                general.live_server_origins.clone(),
+            ) {
+                Ok(server) => sink.push(Arc::new(server)),
+                Err(e) => warn!("could not start live server: {e}"),
+            }
+        }
+        let sink: Arc<dyn EventSink> = Arc::new(sink);
+
+        let region = context.region.read().ok().and_then(|r| r.clone());
         let args = StartArgs {
-            app: app_handle,
+            app: Some(app_handle.clone()),
+            sink,
+            save_context,
             ipc,
             runtime: tokio::runtime::Handle::current(),
             settings,
+            region,
             local_info,
             local_player_repository,
-            heartbeat_api,
+            heartbeat_api: Some(heartbeat_api),
             ban_list,
+            capture,
+            replay_clock,
         };
 
         tokio::task::spawn_blocking(move || {
@@ -3,7 +3,7 @@
 use hashbrown::HashMap;
 
 use crate::{
-    data::RAID_MAP,
+    data::{RAID_DEFINITIONS, RAID_MAP},
     models::{ArkPassiveNode, Encounter, EncounterEntity, EntityType, StatusEffect},
 };
 
@@ -204,6 +204,22 @@ pub fn damage_gem_value_to_level(value: u32, tier: u8) -> u8 {
     }
 }
 
+/// Inverse of [`damage_gem_value_to_level`], `None` for levels above 10.
+pub fn damage_gem_level_to_value(level: u8, tier: u8) -> Option<u32> {
+    let values: [u32; 11] = if tier == 4 {
+        [0, 800, 1200, 1600, 2000, 2400, 2800, 3200, 3600, 4000, 4400]
+    } else {
+        [0, 300, 600, 900, 1200, 1500, 1800, 2100, 2400, 3000, 4000]
+    };
+
+    values.get(level as usize).copied()
+}
+
+/// Inverse of [`support_damage_gem_value_to_level`], `None` for levels above 10.
+pub fn support_damage_gem_level_to_value(level: u8) -> Option<u32> {
+    (level <= 10).then_some(level as u32 * 100)
+}
+
 pub fn cooldown_gem_value_to_level(value: u32, tier: u8) -> u8 {
     if tier == 4 {
         match value {
@@ -589,16 +605,10 @@ pub fn get_player_spec(
 }
 
 pub fn boss_to_raid_map(boss: &str, max_hp: i64) -> Option<String> {
-    match boss {
-        "Phantom Legion Commander Brelshaza" => {
-            if max_hp > 100_000_000_000 {
-                Some("Act 2: Brelshaza G2".to_string())
-            } else {
-                Some("Brelshaza G6".to_string())
-            }
-        }
-        _ => RAID_MAP.get(boss).cloned(),
-    }
+    RAID_DEFINITIONS
+        .hp_gate(boss, max_hp)
+        .map(str::to_string)
+        .or_else(|| RAID_MAP.get(boss).cloned())
 }
 
 pub fn is_valid_player(player: &EncounterEntity) -> bool {
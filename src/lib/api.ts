@@ -5,16 +5,32 @@ import { relaunch } from "@tauri-apps/plugin-process";
 
 import type { AppSettings } from "./settings";
 import type {
+  BackupInfo,
   Encounter,
   CharacterStatistics,
   EncounterDbInfo,
   EncounterEvent,
   EncountersOverview,
   IdentityEvent,
+  InspectSnapshotDiff,
+  LoadoutProgression,
   NinevehEvent,
+  PhaseSplitStatistics,
   RaidProgressionRange,
   RaidProgressionStatistics,
+  RaidRunStatistics,
   PartyEvent,
+  RosterStatistics,
+  SearchFilter,
+  SnapshotEdit,
+  StaggerEvent,
+  SupportSynergy,
+  Teammate,
+  TeammateDetails,
+  TimelineEvent,
+  TimelineEventKind,
+  WeeklyRaid,
+  WhatIfReport,
   ZoneChangeEvent
 } from "./types";
 
@@ -114,6 +130,15 @@ export interface CharacterInfo {
 
 export const getLocalCharacters = (): Promise<CharacterInfo[]> => invoke("get_local_characters");
 
+export interface CharacterName {
+  characterId: number;
+  name: string;
+  firstSeen: number;
+  lastSeen: number;
+}
+
+export const getCharacterNames = (name: string): Promise<CharacterName[]> => invoke("get_character_names", { name });
+
 export interface CharacterStatisticsCriteria {
   character: CharacterInfo;
   range: "current_week" | "previous_week" | "last4_weeks" | "last8_weeks" | "all";
@@ -133,6 +158,33 @@ export interface CharacterStatisticsCriteria {
 export const getCharacterStatistics = (criteria: CharacterStatisticsCriteria): Promise<CharacterStatistics> =>
   invoke("get_character_statistics", { criteria });
 
+export interface RosterStatisticsCriteria {
+  range: CharacterStatisticsCriteria["range"];
+  mode: CharacterStatisticsCriteria["mode"];
+  damageType?: CharacterStatisticsCriteria["damageType"];
+  bossToRaid?: Record<string, string>;
+  startTime?: number;
+  endTime?: number;
+  difficulty?: string;
+  minDuration?: number;
+}
+
+export const getRosterStatistics = (criteria: RosterStatisticsCriteria): Promise<RosterStatistics> =>
+  invoke("get_roster_statistics", { criteria });
+
+export interface LoadoutProgressionCriteria {
+  character: CharacterInfo;
+  range: CharacterStatisticsCriteria["range"];
+  startTime?: number;
+  endTime?: number;
+  bosses?: string[];
+  difficulty?: string;
+  minDuration?: number;
+}
+
+export const getLoadoutProgression = (criteria: LoadoutProgressionCriteria): Promise<LoadoutProgression> =>
+  invoke("get_loadout_progression", { criteria });
+
 export interface RaidProgressionCriteria {
   range: CharacterStatisticsCriteria["range"];
   bossToRaid?: Record<string, string>;
@@ -158,6 +210,57 @@ export const getRaidProgressionRange = (criteria: RaidProgressionRangeCriteria):
 export const getRaidProgressionStatistics = (criteria: RaidProgressionCriteria): Promise<RaidProgressionStatistics> =>
   invoke("get_raid_progression_statistics", { criteria });
 
+export const getPhaseSplits = (boss: string, difficulty: string, encounterId?: number): Promise<PhaseSplitStatistics> =>
+  invoke("get_phase_splits", { boss, difficulty, encounterId });
+
+export interface RaidRunCriteria {
+  range: CharacterStatisticsCriteria["range"];
+  raid?: string;
+  difficulty?: string;
+  localPlayer?: string;
+  startTime?: number;
+  endTime?: number;
+}
+
+export const getRaidRuns = (criteria: RaidRunCriteria): Promise<RaidRunStatistics> =>
+  invoke("get_raid_runs", { criteria });
+
+export interface WeeklyCharacter {
+  character: CharacterInfo;
+  region?: string;
+  weekStart: number;
+  nextReset: number;
+  raids: WeeklyRaid[];
+}
+
+export const getWeeklyTracker = (): Promise<WeeklyCharacter[]> => invoke("get_weekly_tracker");
+
+export interface TeammateCriteria {
+  range: CharacterStatisticsCriteria["range"];
+  search?: string;
+  minPulls?: number;
+  startTime?: number;
+  endTime?: number;
+}
+
+export const getTeammates = (criteria: TeammateCriteria): Promise<Teammate[]> => invoke("get_teammates", { criteria });
+
+export const getTeammateDetails = (characterId: number): Promise<TeammateDetails> =>
+  invoke("get_teammate_details", { characterId });
+
+export interface SupportSynergyCriteria {
+  range: CharacterStatisticsCriteria["range"];
+  startTime?: number;
+  endTime?: number;
+  bosses?: string[];
+  difficulty?: string;
+  minDuration?: number;
+  clearedOnly?: boolean;
+}
+
+export const getSupportSynergy = (criteria: SupportSynergyCriteria): Promise<SupportSynergy[]> =>
+  invoke("get_support_synergy", { criteria });
+
 export interface SyncArgs {
   encounter: number;
   upstream: string;
@@ -228,6 +331,46 @@ export const getSyncCandidates = (forceResync: boolean): Promise<number[]> =>
 
 export const loadEncounter = (id: string): Promise<Encounter> => invoke("load_encounter", { id });
 
+export const getEncounterEvents = (id: string, kinds: TimelineEventKind[] = []): Promise<TimelineEvent[]> =>
+  invoke("get_encounter_events", { id, kinds });
+
+export const exportEncounter = (id: string, path: string): Promise<void> =>
+  invoke("export_encounter", { id, path });
+
+export const importEncounter = (path: string): Promise<{ id: number; duplicate: boolean }> =>
+  invoke("import_encounter", { path });
+
+export const diffInspectSnapshots = (
+  name: string,
+  beforeEncounter: number,
+  afterEncounter: number
+): Promise<InspectSnapshotDiff> => invoke("diff_inspect_snapshots", { name, beforeEncounter, afterEncounter });
+
+export const simulateWhatIf = (id: string, name: string, edit: SnapshotEdit): Promise<WhatIfReport> =>
+  invoke("simulate_what_if", { id, name, edit });
+
+export type EncounterSelection =
+  | { kind: "search"; search: string; filter: SearchFilter }
+  | { kind: "character"; criteria: CharacterStatisticsCriteria };
+
+export const exportEncountersCsv = (
+  selection: EncounterSelection,
+  directory: string
+): Promise<{ encounters: number; playerRows: number; skillRows: number; playersPath: string; skillsPath: string }> =>
+  invoke("export_encounters_csv", { selection, directory });
+
+export const mergeDatabase = (path: string): Promise<{ imported: number; duplicates: number }> =>
+  invoke("merge_database", { path });
+
+export const backupDatabase = (): Promise<string> => invoke("backup_database");
+
+export const listBackups = (): Promise<BackupInfo[]> => invoke("list_backups");
+
+export const restoreDatabase = (path: string): Promise<void> => invoke("restore_database", { path });
+
+export const checkDatabaseIntegrity = (minDuration: number): Promise<EncounterDbInfo> =>
+  invoke("check_database_integrity", { minDuration });
+
 export const getLastEncounterVersion = (): Promise<string | null> => invoke("get_last_encounter_version");
 
 export const toggleMeterPause = (): Promise<void> => emit("toggle-meter-pause");
@@ -254,6 +397,9 @@ export const onIdentityUpdate = (handler: (event: IdentityEvent) => void) =>
     })
   );
 
+export const onStaggerUpdate = (handler: (event: { payload: StaggerEvent }) => void) =>
+  listen<StaggerEvent>("stagger-update", handler);
+
 export const onEncounterUpdate = (handler: (event: EncounterEvent) => void) => listen("encounter-update", handler);
 
 export const onPartyUpdate = (handler: (event: PartyEvent) => void) => listen("party-update", handler);
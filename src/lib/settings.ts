@@ -79,6 +79,13 @@ export interface GeneralSettings {
   autoHideDelay: number;
   betaChannel: boolean;
   exitlagCompat: boolean;
+  recordPacketCaptures: boolean;
+  liveServer: boolean;
+  liveServerPort: number;
+  liveServerOrigins: string[];
+  autoBackup: boolean;
+  backupIntervalHours: number;
+  backupRetention: number;
 }
 
 export interface Shortcuts {
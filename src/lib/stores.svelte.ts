@@ -198,7 +198,14 @@ export const defaultSettings: AppSettings = {
     autoShow: false,
     autoHideDelay: 5,
     betaChannel: false,
-    exitlagCompat: false
+    exitlagCompat: false,
+    recordPacketCaptures: false,
+    liveServer: false,
+    liveServerPort: 6041,
+    liveServerOrigins: [],
+    autoBackup: false,
+    backupIntervalHours: 24,
+    backupRetention: 7
   },
   shortcuts: {
     hideMeter: "Control+ArrowDown",
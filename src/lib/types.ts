@@ -157,6 +157,99 @@ export interface CharacterStatisticsUnavailable {
   supportLogs: number;
 }
 
+export interface RosterStatistics {
+  characters: CharacterStatistics[];
+  weeks: RosterWeek[];
+  specs: RosterSpecPerformance[];
+  raids: RosterRaidProgression[];
+}
+
+export interface RosterWeek {
+  startTime: number;
+  attempts: number;
+  clears: number;
+  characters: number;
+  raidTime: number;
+}
+
+export interface RosterSpecPerformance {
+  classId: number;
+  spec?: string;
+  characters: string[];
+  attempts: number;
+  clears: number;
+  clearRate: number;
+  medianDps?: number;
+  bestDps?: number;
+  medianRdps?: number;
+  bestRdps?: number;
+}
+
+export interface RosterRaidProgression {
+  raid: string;
+  difficulty?: string;
+  attempts: number;
+  clears: number;
+  clearRate: number;
+  firstClear?: number;
+  lastClear?: number;
+  characters: RosterRaidCharacter[];
+}
+
+export interface RosterRaidCharacter {
+  name: string;
+  classId: number;
+  attempts: number;
+  clears: number;
+  firstClear?: number;
+  lastClear?: number;
+  medianDps?: number;
+  bestDps?: number;
+}
+
+export interface LoadoutProgression {
+  timeline: LoadoutSnapshot[];
+  changes: LoadoutChange[];
+}
+
+export interface LoadoutSnapshot {
+  encounterId: number;
+  fightStart: number;
+  bossName: string;
+  difficulty?: string;
+  gearScore: number;
+  combatPower?: number;
+  loadoutHash?: string;
+}
+
+export interface LoadoutChange {
+  encounterId: number;
+  fightStart: number;
+  loadoutHash?: string;
+  previousLoadoutHash?: string;
+  gearScore: number;
+  previousGearScore: number;
+  combatPower?: number;
+  previousCombatPower?: number;
+  arkPassiveChanged: boolean;
+  bosses: LoadoutBossComparison[];
+}
+
+export interface LoadoutBossComparison {
+  bossName: string;
+  difficulty?: string;
+  before: LoadoutPerformance;
+  after: LoadoutPerformance;
+  dpsChange?: number;
+  rdpsChange?: number;
+}
+
+export interface LoadoutPerformance {
+  clears: number;
+  medianDps?: number;
+  medianRdps?: number;
+}
+
 export interface RaidProgressionStatistics {
   summary: RaidProgressionSummary;
   gates: RaidProgressionGate[];
@@ -220,6 +313,156 @@ export interface RaidProgressionPull {
   playerCount: number;
 }
 
+export interface RaidRunStatistics {
+  runs: RaidRun[];
+  fullClears: number;
+  averageFullClearTime?: number;
+  fastestFullClearTime?: number;
+  averageActiveTime?: number;
+  averageIdleTime?: number;
+}
+
+export interface RaidRun {
+  raid: string;
+  difficulty: string;
+  localPlayer: string;
+  start: number;
+  end: number;
+  activeTime: number;
+  idleTime: number;
+  pulls: number;
+  fullClear: boolean;
+  gates: RaidRunGate[];
+}
+
+export interface RaidRunGate {
+  gate: string;
+  bossName: string;
+  pulls: number;
+  cleared: boolean;
+  activeTime: number;
+  encounterId: number;
+}
+
+export interface WeeklyRaid {
+  raid: string;
+  gateCount: number;
+  gates: WeeklyGateClear[];
+}
+
+export interface WeeklyGateClear {
+  gate: string;
+  bossName: string;
+  difficulty: string;
+  encounterId: number;
+  clearedAt: number;
+}
+
+export interface Teammate {
+  characterId: number;
+  name: string;
+  names: TeammateName[];
+  classId: number;
+  class: string;
+  spec?: string;
+  maxGearScore: number;
+  sharedPulls: number;
+  sharedClears: number;
+  clearRate: number;
+  firstSeen: number;
+  lastSeen: number;
+  bosses: TeammateBoss[];
+}
+
+export interface TeammateName {
+  name: string;
+  firstSeen: number;
+  lastSeen: number;
+}
+
+export interface TeammateBoss {
+  bossName: string;
+  difficulty?: string;
+  pulls: number;
+  clears: number;
+  medianDps?: number;
+  medianRdps?: number;
+}
+
+export interface TeammateDetails {
+  teammate: Teammate;
+  pulls: TeammatePull[];
+}
+
+export interface TeammatePull {
+  id: number;
+  fightStart: number;
+  bossName: string;
+  difficulty?: string;
+  duration: number;
+  cleared: boolean;
+  localPlayer: string;
+  name: string;
+  classId: number;
+  spec?: string;
+  gearScore: number;
+  dps: number;
+  rdps?: number;
+}
+
+export interface SupportSynergy {
+  characterId: number;
+  name: string;
+  classId: number;
+  spec?: string;
+  pulls: number;
+  supports: SupportPairing[];
+}
+
+export interface SupportPairing {
+  characterId: number;
+  name: string;
+  classId: number;
+  spec?: string;
+  pulls: number;
+  clears: number;
+  averageRdps: number;
+  bestRdps: number;
+  totalDamage: number;
+  lastSeen: number;
+}
+
+export interface PhaseSplitStatistics {
+  bossName: string;
+  difficulty: string;
+  clears: number;
+  phases: BestPhaseSplit[];
+  sumOfBest?: number;
+  bestClear?: number;
+  bestClearId?: number;
+  pull?: PullPhaseSplits;
+}
+
+export interface BestPhaseSplit {
+  name: string;
+  duration: number;
+  encounterId: number;
+}
+
+export interface PullPhaseSplits {
+  id: number;
+  phases: PullPhaseSplit[];
+  timeLost: number;
+}
+
+export interface PullPhaseSplit {
+  name: string;
+  end: number;
+  duration: number;
+  best?: number;
+  delta?: number;
+}
+
 export interface RaidProgressionPlayer {
   name: string;
   classId: number;
@@ -259,6 +502,18 @@ export interface EncounterDamageStats {
   misc?: EncounterMisc;
   bossHpLog: { [key: string]: Array<BossHpLog> };
   staggerStats?: StaggerStats;
+  stagger?: EncounterStagger;
+  pace?: EncounterPace;
+}
+
+export interface EncounterPace {
+  referenceId: number;
+  bossHpPercent: number;
+  elapsed: number;
+  referenceElapsed?: number;
+  delta?: number;
+  projectedKill?: number;
+  referenceKill: number;
 }
 
 export interface EncounterMisc {
@@ -330,6 +585,25 @@ export interface StaggerStats {
   staggersPerMin: number;
 }
 
+export interface EncounterStagger {
+  log: { [key: string]: Array<StaggerLog> };
+  checks: Array<StaggerCheck>;
+}
+
+export interface StaggerLog {
+  time: number;
+  p: number;
+}
+
+export interface StaggerCheck {
+  boss: string;
+  start: number;
+  end: number;
+  maxStagger: number;
+  success: boolean;
+  contributions: { [key: string]: number };
+}
+
 export interface MostDamageTakenEntity {
   name: string;
   damageTaken: number;
@@ -359,6 +633,13 @@ export interface Entity {
   spec?: string;
   loadoutHash?: string;
   combatPower?: number;
+  battleItems?: Array<BattleItemUse>;
+}
+
+export interface BattleItemUse {
+  itemId?: number;
+  name: string;
+  timestamp: number;
 }
 
 export interface ArkPassiveData {
@@ -449,6 +730,7 @@ export interface Tripod {
 export interface DamageStats {
   damageDealt: number;
   damageTaken: number;
+  damageTakenBy?: DamageTakenSource[];
   hyperAwakeningDamage?: number;
   buffedBy: { [key: number]: number };
   debuffedBy: { [key: number]: number };
@@ -487,6 +769,25 @@ export interface DamageStats {
 export interface DeathInfo {
   deathTime: number;
   deadFor?: number;
+  recap?: DamageTakenEvent[];
+  incapacitations?: IncapacitatedEvent[];
+}
+
+export interface DamageTakenSource {
+  source: string;
+  skillId: number;
+  skillName: string;
+  damage: number;
+  hits: number;
+}
+
+export interface DamageTakenEvent {
+  timestamp: number;
+  source: string;
+  skillId: number;
+  skillName: string;
+  damage: number;
+  currentHp: number;
 }
 
 export interface SkillStats {
@@ -506,6 +807,11 @@ export interface IdentityStats {
   log: IdentityLogType;
   average: number;
   cardDraws?: { [key: number]: number };
+  cardsUsed?: number;
+  bubblesUsed?: number;
+  stanceLog?: Array<[number, number]>;
+  stanceDurations?: { [key: number]: number };
+  uptime?: number;
 }
 
 export interface StatusEffect {
@@ -579,6 +885,25 @@ export interface StaggerEvent {
   max: number;
 }
 
+export type TimelineEventKind =
+  | "raid_start"
+  | "phase_transition"
+  | "boss_kill"
+  | "death"
+  | "revive"
+  | "crowd_control"
+  | "counter"
+  | "abnormal_move"
+  | "trigger"
+  | "intermission";
+
+export interface TimelineEvent {
+  time: number;
+  kind: TimelineEventKind;
+  entity?: string;
+  value?: number;
+}
+
 export interface IncapacitatedEvent {
   type: IncapacitationEventType;
   timestamp: number;
@@ -743,6 +1068,49 @@ export interface EncounterDbInfo {
   size: string;
   totalEncounters: number;
   totalEncountersFiltered: number;
+  integrity?: string;
+}
+
+export interface BackupInfo {
+  path: string;
+  created: number;
+  size: number;
+}
+
+export interface InspectSnapshotChange {
+  path: string;
+  before?: unknown;
+  after?: unknown;
+}
+
+export interface InspectSnapshotDiff {
+  name: string;
+  beforeEncounter: number;
+  afterEncounter: number;
+  identical: boolean;
+  changes: InspectSnapshotChange[];
+}
+
+export interface SnapshotEdit {
+  stats?: Record<string, number>;
+  skillDamage?: Record<number, number>;
+  abilityFeatures?: { featureType: string; level: number; values: number[] }[];
+}
+
+export interface WhatIfSkill {
+  skillId: number;
+  name: string;
+  originalDamage: number;
+  projectedDamage: number;
+  delta: number;
+}
+
+export interface WhatIfReport {
+  player: string;
+  originalDamage: number;
+  projectedDamage: number;
+  skills: WhatIfSkill[];
+  unmodeledHits: number;
 }
 
 export class SearchFilter {